@@ -0,0 +1,236 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{EnsureOrigin, Get},
+	weights::Weight,
+};
+use laminar_primitives::{AssetId, CurrencyId};
+use orml_utilities::with_transaction_result;
+use sp_runtime::{traits::Convert, DispatchError, DispatchResult, RuntimeDebug};
+use sp_std::{marker::PhantomData, prelude::Vec};
+use traits::TradableAssets;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+mod default_weight;
+mod mock;
+mod tests;
+
+pub trait WeightInfo {
+	fn register_asset() -> Weight;
+	fn update_asset() -> Weight;
+}
+
+pub trait Config: frame_system::Config {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Config>::Event>;
+
+	/// Required origin for registering and updating assets.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Synthetic currency IDs of the legacy `CurrencyId` variants.
+	type LegacySyntheticCurrencyIds: Get<Vec<CurrencyId>>;
+
+	/// Weight information for the extrinsics in this module.
+	type WeightInfo: WeightInfo;
+}
+
+/// Asset metadata.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+pub struct AssetMetadata {
+	/// Asset symbol, i.e. `FEUR`.
+	pub symbol: Vec<u8>,
+
+	/// Number of decimals of balances.
+	pub decimals: u8,
+
+	/// The key to query prices from oracle.
+	pub oracle_key: CurrencyId,
+
+	/// If the asset is a synthetic asset.
+	pub is_synthetic: bool,
+
+	/// If the asset could be traded in margin trading pairs.
+	pub is_tradable: bool,
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as AssetRegistry {
+		/// Metadata of registered assets, and of the legacy currencies that have been given one.
+		pub Assets get(fn assets): map hasher(twox_64_concat) CurrencyId => Option<AssetMetadata>;
+
+		/// The next asset ID.
+		pub NextAssetId get(fn next_asset_id): AssetId;
+
+		/// Synthetic currency IDs, updated on asset metadata changes.
+		///
+		/// DEFAULT-NOTE: `None` until any asset metadata set, and `LegacySyntheticCurrencyIds`
+		/// would be used.
+		SyntheticCurrencies: Option<Vec<CurrencyId>>;
+	}
+
+	add_extra_genesis {
+		config(assets): Vec<(CurrencyId, AssetMetadata)>;
+
+		build(|config: &GenesisConfig| {
+			config.assets.iter().for_each(|(currency_id, metadata)| {
+				if let CurrencyId::Asset(asset_id) = currency_id {
+					NextAssetId::mutate(|id| *id = (*id).max(asset_id.saturating_add(1)));
+				}
+				Module::<T>::insert_asset(*currency_id, metadata.clone());
+			})
+		})
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Config> {
+		/// No new asset ID available.
+		NoAvailableAssetId,
+		/// Asset not registered.
+		AssetNotFound,
+		/// Asset symbol could not be empty.
+		EmptySymbol,
+	}
+}
+
+decl_event! {
+	pub enum Event {
+		/// Asset registered: \[currency_id\]
+		AssetRegistered(CurrencyId),
+
+		/// Asset metadata updated: \[currency_id\]
+		AssetUpdated(CurrencyId),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Register a new asset. It would be identified by `CurrencyId::Asset(asset_id)` with a new asset ID.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::register_asset()]
+		pub fn register_asset(origin, metadata: AssetMetadata) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				let currency_id = Self::do_register_asset(metadata)?;
+				Self::deposit_event(Event::AssetRegistered(currency_id));
+				Ok(())
+			})?;
+		}
+
+		/// Update metadata of an asset.
+		///
+		/// Registered assets must exist. Legacy currencies could be given metadata by this call, to
+		/// migrate them into the registry.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::update_asset()]
+		pub fn update_asset(origin, currency_id: CurrencyId, metadata: AssetMetadata) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				Self::do_update_asset(currency_id, metadata)?;
+				Self::deposit_event(Event::AssetUpdated(currency_id));
+				Ok(())
+			})?;
+		}
+	}
+}
+
+// Dispatchable calls implementation
+
+impl<T: Config> Module<T> {
+	fn do_register_asset(metadata: AssetMetadata) -> Result<CurrencyId, DispatchError> {
+		ensure!(!metadata.symbol.is_empty(), Error::<T>::EmptySymbol);
+
+		let asset_id = NextAssetId::try_mutate(|id| -> Result<AssetId, Error<T>> {
+			let current_id = *id;
+			*id = id.checked_add(1).ok_or(Error::<T>::NoAvailableAssetId)?;
+			Ok(current_id)
+		})?;
+		let currency_id = CurrencyId::Asset(asset_id);
+		Self::insert_asset(currency_id, metadata);
+
+		Ok(currency_id)
+	}
+
+	fn do_update_asset(currency_id: CurrencyId, metadata: AssetMetadata) -> DispatchResult {
+		ensure!(!metadata.symbol.is_empty(), Error::<T>::EmptySymbol);
+		if let CurrencyId::Asset(_) = currency_id {
+			ensure!(Assets::contains_key(currency_id), Error::<T>::AssetNotFound);
+		}
+
+		Self::insert_asset(currency_id, metadata);
+		Ok(())
+	}
+
+	/// Insert metadata of `currency_id`, and update synthetic currency IDs accordingly.
+	fn insert_asset(currency_id: CurrencyId, metadata: AssetMetadata) {
+		let mut synthetic_currencies = Self::synthetic_currency_ids();
+		let position = synthetic_currencies.iter().position(|id| *id == currency_id);
+		match (metadata.is_synthetic, position) {
+			(true, None) => synthetic_currencies.push(currency_id),
+			(false, Some(index)) => {
+				synthetic_currencies.remove(index);
+			}
+			_ => {}
+		}
+		SyntheticCurrencies::put(synthetic_currencies);
+
+		Assets::insert(currency_id, metadata);
+	}
+}
+
+// Storage getters
+
+impl<T: Config> Module<T> {
+	/// The key to query price of `currency_id` from oracle. Defaults to `currency_id` itself if no
+	/// metadata.
+	pub fn oracle_key(currency_id: CurrencyId) -> CurrencyId {
+		Self::assets(currency_id).map_or(currency_id, |m| m.oracle_key)
+	}
+
+	/// If `currency_id` could be traded. Legacy currencies without metadata are tradable, while
+	/// registered assets are not unless marked as tradable.
+	pub fn is_tradable(currency_id: CurrencyId) -> bool {
+		Self::assets(currency_id).map_or(!matches!(currency_id, CurrencyId::Asset(_)), |m| m.is_tradable)
+	}
+
+	/// Synthetic currency IDs: legacy ones not marked as non-synthetic in the registry, followed by
+	/// registered synthetic assets.
+	pub fn synthetic_currency_ids() -> Vec<CurrencyId> {
+		SyntheticCurrencies::get().unwrap_or_else(T::LegacySyntheticCurrencyIds::get)
+	}
+}
+
+impl<T: Config> TradableAssets for Module<T> {
+	fn is_tradable(currency_id: CurrencyId) -> bool {
+		Self::is_tradable(currency_id)
+	}
+}
+
+/// Synthetic currency IDs of the asset registry.
+pub struct SyntheticCurrencyIds<T>(PhantomData<T>);
+
+impl<T: Config> Get<Vec<CurrencyId>> for SyntheticCurrencyIds<T> {
+	fn get() -> Vec<CurrencyId> {
+		Module::<T>::synthetic_currency_ids()
+	}
+}
+
+/// Converts a currency ID to its oracle key in the asset registry.
+pub struct OracleKey<T>(PhantomData<T>);
+
+impl<T: Config> Convert<CurrencyId, CurrencyId> for OracleKey<T> {
+	fn convert(currency_id: CurrencyId) -> CurrencyId {
+		Module::<T>::oracle_key(currency_id)
+	}
+}
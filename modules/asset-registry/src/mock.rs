@@ -0,0 +1,126 @@
+//! Mocks for the asset-registry module.
+
+#![cfg(test)]
+
+use frame_support::{impl_outer_event, impl_outer_origin, ord_parameter_types, parameter_types};
+use frame_system::EnsureSignedBy;
+use sp_core::H256;
+use sp_runtime::{testing::Header, traits::IdentityLookup, Perbill};
+
+use super::*;
+
+impl_outer_origin! {
+	pub enum Origin for Runtime {}
+}
+
+mod asset_registry {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Runtime {
+		frame_system<T>,
+		asset_registry,
+	}
+}
+
+ord_parameter_types! {
+	pub const UpdateOrigin: AccountId = 0;
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Runtime;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: u32 = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub LegacySyntheticCurrencyIds: Vec<CurrencyId> = vec![CurrencyId::FEUR, CurrencyId::FJPY];
+}
+
+type AccountId = u64;
+impl frame_system::Config for Runtime {
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = ::sp_runtime::traits::BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type AccountData = ();
+	type BaseCallFilter = ();
+	type SystemWeightInfo = ();
+}
+pub type System = frame_system::Module<Runtime>;
+
+impl Config for Runtime {
+	type Event = TestEvent;
+	type UpdateOrigin = EnsureSignedBy<UpdateOrigin, AccountId>;
+	type LegacySyntheticCurrencyIds = LegacySyntheticCurrencyIds;
+	type WeightInfo = ();
+}
+
+pub type AssetRegistry = Module<Runtime>;
+pub type MockSyntheticCurrencyIds = SyntheticCurrencyIds<Runtime>;
+
+const ALICE_ACC_ID: AccountId = 0;
+pub fn alice() -> Origin {
+	Origin::signed(ALICE_ACC_ID)
+}
+
+const BOB_ACC_ID: AccountId = 1;
+pub fn bob() -> Origin {
+	Origin::signed(BOB_ACC_ID)
+}
+
+pub fn metadata(symbol: &[u8], oracle_key: CurrencyId, is_synthetic: bool) -> AssetMetadata {
+	AssetMetadata {
+		symbol: symbol.to_vec(),
+		decimals: 18,
+		oracle_key,
+		is_synthetic,
+		is_tradable: true,
+	}
+}
+
+#[derive(Default)]
+pub struct ExtBuilder {
+	assets: Vec<(CurrencyId, AssetMetadata)>,
+}
+
+impl ExtBuilder {
+	pub fn assets(mut self, assets: Vec<(CurrencyId, AssetMetadata)>) -> Self {
+		self.assets = assets;
+		self
+	}
+
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap();
+
+		GenesisConfig { assets: self.assets }
+			.assimilate_storage(&mut t)
+			.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+}
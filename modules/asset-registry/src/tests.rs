@@ -0,0 +1,174 @@
+//! Unit tests for the asset-registry module.
+
+#![cfg(test)]
+
+use super::*;
+use mock::*;
+
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::traits::BadOrigin;
+
+#[test]
+fn register_asset_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		let feur = metadata(b"FEUR", CurrencyId::FEUR, true);
+		assert_noop!(AssetRegistry::register_asset(bob(), feur.clone()), BadOrigin);
+
+		assert_ok!(AssetRegistry::register_asset(alice(), feur.clone()));
+		assert_eq!(AssetRegistry::assets(CurrencyId::Asset(0)), Some(feur));
+		assert_eq!(AssetRegistry::next_asset_id(), 1);
+
+		let event = TestEvent::asset_registry(Event::AssetRegistered(CurrencyId::Asset(0)));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"FGBP", CurrencyId::Asset(1), true)
+		));
+		assert!(AssetRegistry::assets(CurrencyId::Asset(1)).is_some());
+		assert_eq!(AssetRegistry::next_asset_id(), 2);
+	});
+}
+
+#[test]
+fn register_asset_fails_if_empty_symbol() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			AssetRegistry::register_asset(alice(), metadata(b"", CurrencyId::FEUR, true)),
+			Error::<Runtime>::EmptySymbol
+		);
+	});
+}
+
+#[test]
+fn register_asset_fails_if_no_available_id() {
+	ExtBuilder::default().build().execute_with(|| {
+		NextAssetId::put(AssetId::max_value());
+		assert_noop!(
+			AssetRegistry::register_asset(alice(), metadata(b"FGBP", CurrencyId::FEUR, true)),
+			Error::<Runtime>::NoAvailableAssetId
+		);
+	});
+}
+
+#[test]
+fn update_asset_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		let feur = metadata(b"FEUR", CurrencyId::FEUR, true);
+		assert_noop!(
+			AssetRegistry::update_asset(bob(), CurrencyId::FEUR, feur.clone()),
+			BadOrigin
+		);
+		assert_noop!(
+			AssetRegistry::update_asset(alice(), CurrencyId::Asset(0), feur.clone()),
+			Error::<Runtime>::AssetNotFound
+		);
+
+		// legacy currencies could be migrated into the registry
+		assert_ok!(AssetRegistry::update_asset(alice(), CurrencyId::FEUR, feur.clone()));
+		assert_eq!(AssetRegistry::assets(CurrencyId::FEUR), Some(feur));
+
+		let event = TestEvent::asset_registry(Event::AssetUpdated(CurrencyId::FEUR));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"FGBP", CurrencyId::FEUR, true)
+		));
+		let fgbp = metadata(b"FGBP", CurrencyId::Asset(0), true);
+		assert_ok!(AssetRegistry::update_asset(alice(), CurrencyId::Asset(0), fgbp.clone()));
+		assert_eq!(AssetRegistry::assets(CurrencyId::Asset(0)), Some(fgbp));
+	});
+}
+
+#[test]
+fn genesis_assets_work() {
+	ExtBuilder::default()
+		.assets(vec![
+			(CurrencyId::FEUR, metadata(b"FEUR", CurrencyId::FEUR, true)),
+			(CurrencyId::Asset(3), metadata(b"FGBP", CurrencyId::Asset(3), true)),
+		])
+		.build()
+		.execute_with(|| {
+			assert!(AssetRegistry::assets(CurrencyId::FEUR).is_some());
+			assert!(AssetRegistry::assets(CurrencyId::Asset(3)).is_some());
+			assert_eq!(AssetRegistry::next_asset_id(), 4);
+		});
+}
+
+#[test]
+fn oracle_key_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(AssetRegistry::oracle_key(CurrencyId::FEUR), CurrencyId::FEUR);
+
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"FEUR2", CurrencyId::FEUR, true)
+		));
+		assert_eq!(AssetRegistry::oracle_key(CurrencyId::Asset(0)), CurrencyId::FEUR);
+	});
+}
+
+#[test]
+fn synthetic_currency_ids_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			MockSyntheticCurrencyIds::get(),
+			vec![CurrencyId::FEUR, CurrencyId::FJPY]
+		);
+
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"FGBP", CurrencyId::Asset(0), true)
+		));
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"GBP", CurrencyId::Asset(1), false)
+		));
+		assert_ok!(AssetRegistry::update_asset(
+			alice(),
+			CurrencyId::FJPY,
+			metadata(b"FJPY", CurrencyId::FJPY, false)
+		));
+		assert_eq!(
+			MockSyntheticCurrencyIds::get(),
+			vec![CurrencyId::FEUR, CurrencyId::Asset(0)]
+		);
+	});
+}
+
+#[test]
+fn synthetic_currency_ids_from_genesis_assets() {
+	ExtBuilder::default()
+		.assets(vec![
+			(CurrencyId::FEUR, metadata(b"FEUR", CurrencyId::FEUR, false)),
+			(CurrencyId::Asset(0), metadata(b"FGBP", CurrencyId::Asset(0), true)),
+		])
+		.build()
+		.execute_with(|| {
+			assert_eq!(
+				MockSyntheticCurrencyIds::get(),
+				vec![CurrencyId::FJPY, CurrencyId::Asset(0)]
+			);
+		});
+}
+
+#[test]
+fn is_tradable_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		// legacy currencies without metadata
+		assert!(AssetRegistry::is_tradable(CurrencyId::FEUR));
+		assert!(!AssetRegistry::is_tradable(CurrencyId::Asset(0)));
+
+		assert_ok!(AssetRegistry::register_asset(
+			alice(),
+			metadata(b"FGBP", CurrencyId::Asset(0), true)
+		));
+		assert!(AssetRegistry::is_tradable(CurrencyId::Asset(0)));
+
+		let mut feur = metadata(b"FEUR", CurrencyId::FEUR, true);
+		feur.is_tradable = false;
+		assert_ok!(AssetRegistry::update_asset(alice(), CurrencyId::FEUR, feur));
+		assert!(!AssetRegistry::is_tradable(CurrencyId::FEUR));
+	});
+}
@@ -12,6 +12,20 @@ impl crate::WeightInfo for () {
 			.saturating_add(DbWeight::get().reads(6 as Weight))
 			.saturating_add(DbWeight::get().writes(3 as Weight))
 	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_spread_formula() -> Weight {
+		(170_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_spread_skew() -> Weight {
+		(170_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
 	fn set_enabled_leverages() -> Weight {
 		(71_972_000 as Weight)
 			.saturating_add(DbWeight::get().reads(6 as Weight))
@@ -3,36 +3,42 @@
 use codec::{Decode, Encode};
 use frame_support::{
 	decl_error, decl_event, decl_module, decl_storage, ensure,
-	storage::IterableStorageMap,
+	storage::{IterableStorageDoubleMap, IterableStorageMap},
 	traits::{EnsureOrigin, Get, UnixTime},
 	weights::Weight,
 	Parameter,
 };
 use frame_system::ensure_signed;
 use primitives::{
-	arithmetic::fixed_i128_mul_signum, AccumulateConfig, Balance, Leverage, Leverages, LiquidityPoolId, Price,
-	SwapRate, TradingPair,
+	arithmetic::fixed_i128_mul_signum, AccumulateConfig, Balance, CurrencyId, Leverage, Leverages, LiquidityPoolId,
+	Price, SwapRate, TradingPair,
 };
 use sp_arithmetic::{FixedI128, FixedPointNumber};
 use sp_runtime::{
 	traits::{AtLeast32Bit, Saturating, Zero},
 	DispatchResult, ModuleId, RuntimeDebug,
 };
-use sp_std::{cmp::max, prelude::*, result};
+use sp_std::{cell::Cell, cmp::max, prelude::*, result};
 
+use orml_traits::PriceProvider;
 use orml_utilities::with_transaction_result;
 
 use traits::{
 	LiquidityPools, MarginProtocolLiquidityPools, MarginProtocolLiquidityPoolsManager, OnDisableLiquidityPool,
-	OnRemoveLiquidityPool, OpenPositionError,
+	OnRemoveLiquidityPool, OpenPositionError, TradableAssets,
 };
 
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
 mod default_weight;
 mod mock;
 mod tests;
 
 pub trait WeightInfo {
 	fn set_spread() -> Weight;
+	fn set_spread_formula() -> Weight;
+	fn set_spread_skew() -> Weight;
 	fn set_enabled_leverages() -> Weight;
 	fn set_swap_rate() -> Weight;
 	fn set_additional_swap_rate() -> Weight;
@@ -55,9 +61,6 @@ pub struct MarginTradingPairOption<Moment> {
 	/// DEFAULT-NOTE: default not enabled.
 	pub enabled: bool,
 
-	/// The max spread. The minimum of max spread and pool's spread would be used in trading.
-	pub max_spread: Option<Price>,
-
 	/// Swap rate.
 	///
 	/// DEFAULT-NOTE: zero rate if not set.
@@ -67,6 +70,26 @@ pub struct MarginTradingPairOption<Moment> {
 	pub accumulate_config: Option<AccumulateConfig<Moment>>,
 }
 
+/// Trading pair option of margin liquidity pools before `Releases::V2`, with max spread. Only
+/// used in storage migration.
+#[derive(Encode, Decode)]
+struct MarginTradingPairOptionV1<Moment> {
+	enabled: bool,
+	max_spread: Option<Price>,
+	swap_rate: SwapRate,
+	accumulate_config: Option<AccumulateConfig<Moment>>,
+}
+
+/// Trading pair option in a pool of margin before `Releases::V3`, with enabled leverages as a
+/// bitmask. Only used in storage migration.
+#[derive(Encode, Decode)]
+struct MarginPoolTradingPairOptionV2 {
+	enabled: bool,
+	bid_spread: Option<Price>,
+	ask_spread: Option<Price>,
+	enabled_trades: u16,
+}
+
 /// Pool option of margin liquidity pools.
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Default)]
 pub struct MarginPoolOption {
@@ -100,12 +123,42 @@ pub struct MarginPoolTradingPairOption {
 	/// DEFAULT-NOTE: `None`, pool owner must set spread.
 	pub ask_spread: Option<Price>,
 
-	/// Enabled leverages.
+	/// Enabled leverage ranges, for long and short positions.
 	///
 	/// DEFAULT-NOTE: No leverage.
 	pub enabled_trades: Leverages,
 }
 
+/// Volatility-adaptive spread formula of a trading pair in a pool.
+///
+/// spread = base + multiplier * volatility
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, RuntimeDebug, Eq, PartialEq, Default)]
+pub struct SpreadFormula {
+	/// Base spread.
+	pub base: Price,
+
+	/// Multiplier of the oracle price volatility.
+	pub multiplier: Price,
+}
+
+/// Storage version of the module, for storage migrations.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Releases {
+	/// Max spread stored in `MarginTradingPairOption`.
+	V1,
+	/// Max spread stored in `MaxSpreads`.
+	V2,
+	/// Enabled leverages of pool trading pairs stored as ranges.
+	V3,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
 pub const MODULE_ID: ModuleId = ModuleId(*b"lami/mlp");
 pub const ONE_MINUTE: u64 = 60;
 
@@ -131,6 +184,15 @@ pub trait Config: frame_system::Config {
 	/// Type used for expressing timestamp.
 	type Moment: AtLeast32Bit + Parameter + Default + Copy + From<u64>;
 
+	/// Provides market prices for volatility sampling.
+	type PriceProvider: PriceProvider<CurrencyId, Price>;
+
+	/// Number of price samples kept for volatility calculation. Prices are sampled once per minute.
+	type VolatilityWindow: Get<u32>;
+
+	/// Tradability of currencies. Trading pairs may only be enabled if both currencies are tradable.
+	type TradableAssets: TradableAssets;
+
 	/// Weight information for the extrinsics in this module.
 	type WeightInfo: WeightInfo;
 }
@@ -140,6 +202,12 @@ decl_storage! {
 		/// Trading pair options.
 		pub TradingPairOptions get(fn trading_pair_options): map hasher(twox_64_concat) TradingPair => MarginTradingPairOption<T::Moment>;
 
+		/// The max spread of trading pairs. The minimum of max spread and pool's spread would be
+		/// used in trading.
+		///
+		/// Stored as plain values, so that they could be gradually updated.
+		pub MaxSpreads get(fn max_spread): map hasher(twox_64_concat) TradingPair => Option<Price>;
+
 		/// Liquidity pool options, managed by pool owner.
 		pub PoolOptions get(fn pool_options): map hasher(twox_64_concat) LiquidityPoolId => MarginPoolOption;
 
@@ -156,6 +224,26 @@ decl_storage! {
 
 		/// The last time swap rate was accumulated.
 		pub LastAccumulateTime get(fn last_accumulate_time): T::Moment;
+
+		/// Spread formulas of trading pairs in a liquidity pool.
+		///
+		/// If set, the spread is computed from oracle price volatility instead of the fixed bid/ask spread.
+		pub PoolSpreadFormulas get(fn pool_spread_formula): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) TradingPair => Option<SpreadFormula>;
+
+		/// Spread skews of trading pairs in a liquidity pool.
+		///
+		/// If set, the spread on the side increasing the pool's net exposure is widened by
+		/// `skew * abs(net_exposure)`, and the other side tightened by the same amount.
+		pub PoolSpreadSkews get(fn pool_spread_skew): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) TradingPair => Option<Price>;
+
+		/// Rolling window of sampled prices of enabled trading pairs, oldest first.
+		pub PriceSamples get(fn price_samples): map hasher(twox_64_concat) TradingPair => Vec<Price>;
+
+		/// The last time prices were sampled.
+		pub LastPriceSampleTime get(fn last_price_sample_time): T::Moment;
+
+		/// Storage version of the module. New chains start with the latest version.
+		StorageVersion get(fn storage_version) build(|_: &GenesisConfig<T>| Releases::V3): Releases;
 	}
 
 	add_extra_genesis {
@@ -166,9 +254,9 @@ decl_storage! {
 				<TradingPairOptions<T>>::insert(&pair, MarginTradingPairOption {
 					enabled: true,
 					swap_rate: swap_rate.clone(),
-					max_spread: Some(*max_spread),
 					accumulate_config: Some(accumulate_config.clone()),
 				});
+				MaxSpreads::insert(&pair, max_spread);
 			})
 		})
 	}
@@ -182,6 +270,12 @@ decl_event!(
 		/// Spread set: \[who, pool_id, pair, bid, ask\]
 		SpreadSet(AccountId, LiquidityPoolId, TradingPair, Price, Price),
 
+		/// Spread formula set: \[who, pool_id, pair, formula\]
+		SpreadFormulaSet(AccountId, LiquidityPoolId, TradingPair, Option<SpreadFormula>),
+
+		/// Spread skew set: \[who, pool_id, pair, skew\]
+		SpreadSkewSet(AccountId, LiquidityPoolId, TradingPair, Option<Price>),
+
 		/// Enabled trades set: \[who, pool_id, pair, enabled_leverages\]
 		EnabledTradesSet(AccountId, LiquidityPoolId, TradingPair, Leverages),
 
@@ -226,6 +320,10 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_to_v2().saturating_add(Self::migrate_to_v3())
+		}
+
 		/// Set bid and ask spread for `pair` in `pool_id`.
 		///
 		/// May only be called from the pool owner.
@@ -239,7 +337,41 @@ decl_module! {
 			})?;
 		}
 
-		/// Set enabled leverages for `pair` in `pool_id`.
+		/// Set or clear the volatility-adaptive spread formula for `pair` in `pool_id`.
+		///
+		/// May only be called from the pool owner.
+		#[weight = T::WeightInfo::set_spread_formula()]
+		pub fn set_spread_formula(origin, #[compact] pool_id: LiquidityPoolId, pair: TradingPair, formula: Option<SpreadFormula>) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				ensure!(Self::is_owner(pool_id, &who), Error::<T>::NoPermission);
+
+				PoolSpreadFormulas::mutate(pool_id, pair, |f| *f = formula);
+
+				Self::deposit_event(RawEvent::SpreadFormulaSet(who, pool_id, pair, formula));
+
+				Ok(())
+			})?;
+		}
+
+		/// Set or clear the inventory skew of spread for `pair` in `pool_id`.
+		///
+		/// May only be called from the pool owner.
+		#[weight = T::WeightInfo::set_spread_skew()]
+		pub fn set_spread_skew(origin, #[compact] pool_id: LiquidityPoolId, pair: TradingPair, skew: Option<Price>) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				ensure!(Self::is_owner(pool_id, &who), Error::<T>::NoPermission);
+
+				PoolSpreadSkews::mutate(pool_id, pair, |s| *s = skew);
+
+				Self::deposit_event(RawEvent::SpreadSkewSet(who, pool_id, pair, skew));
+
+				Ok(())
+			})?;
+		}
+
+		/// Set enabled leverage ranges for `pair` in `pool_id`.
 		///
 		/// May only be called from the pool owner.
 		#[weight = T::WeightInfo::set_enabled_leverages()]
@@ -295,7 +427,7 @@ decl_module! {
 		pub fn set_max_spread(origin, pair: TradingPair, #[compact] max_spread: Price) {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
-				<TradingPairOptions<T>>::mutate(&pair, |o| o.max_spread = Some(max_spread));
+				MaxSpreads::insert(&pair, max_spread);
 				Self::deposit_event(RawEvent::MaxSpreadUpdated(pair, max_spread));
 				Ok(())
 			})?;
@@ -329,6 +461,7 @@ decl_module! {
 		pub fn enable_trading_pair(origin, pair: TradingPair) {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
+				ensure!(Self::is_tradable_pair(pair), Error::<T>::CurrencyNotTradable);
 				<TradingPairOptions<T>>::mutate(&pair, |o| o.enabled = true);
 				Self::deposit_event(RawEvent::TradingPairEnabled(pair));
 				Ok(())
@@ -420,9 +553,20 @@ decl_module! {
 			// Truncate seconds, keep minutes
 			let now_as_secs: T::Moment = now_as_mins * ONE_MINUTE.into();
 
+			let should_sample = <LastPriceSampleTime<T>>::get() != now_as_secs;
+			if should_sample {
+				<LastPriceSampleTime<T>>::set(now_as_secs);
+			}
+
 			let mut read_count = 0;
 			let mut write_count = 0;
 			<TradingPairOptions<T>>::iter().for_each(|(pair, option)| {
+				if should_sample && option.enabled {
+					read_count += 1;
+					write_count += 1;
+					Self::sample_price(pair);
+				}
+
 				if let Some(accumulate_config) = option.accumulate_config {
 					read_count += 1;
 					let frequency_as_mins = accumulate_config.frequency / ONE_MINUTE.into();
@@ -457,6 +601,12 @@ decl_error! {
 
 		/// `frequency` of accumulate config is too low.
 		FrequencyTooLow,
+
+		/// Enabled leverage ranges are invalid.
+		InvalidLeverages,
+
+		/// Currency of the trading pair is not tradable.
+		CurrencyNotTradable,
 	}
 }
 
@@ -464,10 +614,6 @@ decl_error! {
 impl<T: Config> Module<T> {
 	// Trading pair option
 
-	pub fn max_spread(pair: TradingPair) -> Option<Price> {
-		Self::trading_pair_options(pair).max_spread
-	}
-
 	pub fn accumulate_config(pair: TradingPair) -> Option<AccumulateConfig<T::Moment>> {
 		Self::trading_pair_options(pair).accumulate_config
 	}
@@ -476,8 +622,13 @@ impl<T: Config> Module<T> {
 		Self::trading_pair_options(pair).swap_rate
 	}
 
+	/// Returns `true` if `pair` is enabled and both of its currencies are still tradable.
 	pub fn is_trading_pair_enabled(pair: TradingPair) -> bool {
-		Self::trading_pair_options(pair).enabled
+		Self::trading_pair_options(pair).enabled && Self::is_tradable_pair(pair)
+	}
+
+	fn is_tradable_pair(pair: TradingPair) -> bool {
+		T::TradableAssets::is_tradable(pair.base) && T::TradableAssets::is_tradable(pair.quote)
 	}
 
 	// Pool margin option
@@ -495,9 +646,21 @@ impl<T: Config> Module<T> {
 
 	// Pool trading pair margin option
 
-	/// `PoolTradingPairOptions` getter. Bid/ask spread is capped by max spread.
+	/// `PoolTradingPairOptions` getter. Bid/ask spread is computed by spread formula if set,
+	/// skewed by the pool's net exposure if spread skew set, and capped by max spread.
 	pub fn pool_trading_pair_options(pool_id: LiquidityPoolId, pair: TradingPair) -> MarginPoolTradingPairOption {
 		let mut option = PoolTradingPairOptions::get(pool_id, pair);
+		if let Some(formula) = Self::pool_spread_formula(pool_id, pair) {
+			let spread = Self::formula_spread(pair, formula);
+			option.bid_spread = Some(spread);
+			option.ask_spread = Some(spread);
+		}
+		if let Some(skew) = Self::pool_spread_skew(pool_id, pair) {
+			let (bid_spread, ask_spread) =
+				Self::skew_spreads(pool_id, pair, skew, option.bid_spread, option.ask_spread);
+			option.bid_spread = bid_spread;
+			option.ask_spread = ask_spread;
+		}
 		if let Some(max_spread) = Self::max_spread(pair) {
 			option.bid_spread = option.bid_spread.map(|s| s.min(max_spread));
 			option.ask_spread = option.ask_spread.map(|s| s.min(max_spread));
@@ -505,10 +668,72 @@ impl<T: Config> Module<T> {
 		option
 	}
 
+	/// Volatility of `pair`, the mean absolute change between consecutive price samples.
+	///
+	/// Returns zero if there are less than two samples.
+	pub fn volatility(pair: TradingPair) -> Price {
+		let samples = Self::price_samples(pair);
+		if samples.len() < 2 {
+			return Price::zero();
+		}
+
+		let changes_sum = samples.windows(2).fold(Price::zero(), |acc, w| {
+			let change = if w[1] > w[0] {
+				w[1].saturating_sub(w[0])
+			} else {
+				w[0].saturating_sub(w[1])
+			};
+			acc.saturating_add(change)
+		});
+		let changes_count = Price::saturating_from_integer((samples.len() - 1) as u128);
+		changes_sum.checked_div(&changes_count).unwrap_or_else(Price::zero)
+	}
+
+	/// spread = base + multiplier * volatility
+	pub fn formula_spread(pair: TradingPair, formula: SpreadFormula) -> Price {
+		formula
+			.base
+			.saturating_add(formula.multiplier.saturating_mul(Self::volatility(pair)))
+	}
+
+	/// Skew bid/ask spreads by net exposure of `pair` in `pool_id`.
+	///
+	/// If traders are net long, the pool is net short and the ask side would increase the pool's
+	/// exposure, so ask spread is widened and bid spread tightened; vice versa if traders are net
+	/// short.
+	pub fn skew_spreads(
+		pool_id: LiquidityPoolId,
+		pair: TradingPair,
+		skew: Price,
+		bid_spread: Option<Price>,
+		ask_spread: Option<Price>,
+	) -> (Option<Price>, Option<Price>) {
+		let net_exposure = T::PoolManager::net_exposure(pool_id, pair);
+		let adjustment = skew.saturating_mul(Price::from_inner(net_exposure.saturating_abs().into_inner() as u128));
+		let widen = |s: Option<Price>| s.map(|s| s.saturating_add(adjustment));
+		let tighten = |s: Option<Price>| s.map(|s| s.saturating_sub(adjustment));
+
+		if net_exposure.is_positive() {
+			(tighten(bid_spread), widen(ask_spread))
+		} else {
+			(widen(bid_spread), tighten(ask_spread))
+		}
+	}
+
 	pub fn is_pool_trading_pair_enabled(pool_id: LiquidityPoolId, pair: TradingPair) -> bool {
 		PoolTradingPairOptions::get(pool_id, pair).enabled
 	}
 
+	/// Trading pairs enabled in `pool_id`, sorted.
+	pub fn pool_enabled_trading_pairs(pool_id: LiquidityPoolId) -> Vec<TradingPair> {
+		let mut pairs: Vec<TradingPair> = PoolTradingPairOptions::iter_prefix(pool_id)
+			.filter(|(_, option)| option.enabled)
+			.map(|(pair, _)| pair)
+			.collect();
+		pairs.sort();
+		pairs
+	}
+
 	pub fn is_pool_trading_pair_leverage_enabled(
 		pool_id: LiquidityPoolId,
 		pair: TradingPair,
@@ -632,6 +857,7 @@ impl<T: Config> Module<T> {
 		enabled: Leverages,
 	) -> DispatchResult {
 		ensure!(Self::is_owner(pool_id, who), Error::<T>::NoPermission);
+		ensure!(enabled.is_valid(), Error::<T>::InvalidLeverages);
 		PoolTradingPairOptions::mutate(pool_id, pair, |o| o.enabled_trades = enabled);
 		Ok(())
 	}
@@ -639,6 +865,19 @@ impl<T: Config> Module<T> {
 
 // Private methods
 impl<T: Config> Module<T> {
+	/// Append current price of `pair` to its samples, dropping the oldest ones out of window.
+	fn sample_price(pair: TradingPair) {
+		if let Some(price) = T::PriceProvider::get_price(pair.base, pair.quote) {
+			PriceSamples::mutate(pair, |samples| {
+				samples.push(price);
+				let window = T::VolatilityWindow::get() as usize;
+				if samples.len() > window {
+					samples.drain(..samples.len() - window);
+				}
+			});
+		}
+	}
+
 	fn accumulate_rates(pair: TradingPair) {
 		for pool_id in T::BaseLiquidityPools::all() {
 			let long_rate = Self::swap_rate(pool_id, pair, true);
@@ -654,15 +893,66 @@ impl<T: Config> Module<T> {
 	}
 }
 
+// Storage migrations
+impl<T: Config> Module<T> {
+	/// Move max spread out of `MarginTradingPairOption` into `MaxSpreads`.
+	fn migrate_to_v2() -> Weight {
+		if Self::storage_version() >= Releases::V2 {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let count = Cell::new(0);
+		<TradingPairOptions<T>>::translate(|pair, old: MarginTradingPairOptionV1<T::Moment>| {
+			count.set(count.get() + 1);
+			if let Some(max_spread) = old.max_spread {
+				MaxSpreads::insert(pair, max_spread);
+			}
+			Some(MarginTradingPairOption {
+				enabled: old.enabled,
+				swap_rate: old.swap_rate,
+				accumulate_config: old.accumulate_config,
+			})
+		});
+		StorageVersion::put(Releases::V2);
+
+		T::DbWeight::get().reads_writes(count.get() + 1, count.get() * 2 + 1)
+	}
+
+	/// Convert enabled leverages of pool trading pairs from the legacy bitmask into ranges.
+	fn migrate_to_v3() -> Weight {
+		if Self::storage_version() >= Releases::V3 {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let count = Cell::new(0);
+		PoolTradingPairOptions::translate(|_pool_id, _pair, old: MarginPoolTradingPairOptionV2| {
+			count.set(count.get() + 1);
+			Some(MarginPoolTradingPairOption {
+				enabled: old.enabled,
+				bid_spread: old.bid_spread,
+				ask_spread: old.ask_spread,
+				enabled_trades: Leverages::from_legacy_bits(old.enabled_trades),
+			})
+		});
+		StorageVersion::put(Releases::V3);
+
+		T::DbWeight::get().reads_writes(count.get() + 1, count.get() + 1)
+	}
+}
+
 impl<T: Config> OnDisableLiquidityPool for Module<T> {
 	fn on_disable(pool_id: LiquidityPoolId) {
 		PoolTradingPairOptions::remove_prefix(&pool_id);
+		PoolSpreadFormulas::remove_prefix(&pool_id);
+		PoolSpreadSkews::remove_prefix(&pool_id);
 	}
 }
 
 impl<T: Config> OnRemoveLiquidityPool for Module<T> {
 	fn on_remove(pool_id: LiquidityPoolId) {
 		PoolTradingPairOptions::remove_prefix(&pool_id);
+		PoolSpreadFormulas::remove_prefix(&pool_id);
+		PoolSpreadSkews::remove_prefix(&pool_id);
 		AccumulatedSwapRates::remove_prefix(&pool_id);
 		PoolOptions::remove(&pool_id);
 	}
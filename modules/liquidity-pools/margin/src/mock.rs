@@ -12,7 +12,11 @@ use sp_runtime::{
 };
 
 use orml_currencies::Currency;
-use orml_traits::parameter_type_with_key;
+use orml_traits::{parameter_type_with_key, DataProvider, DefaultPriceProvider};
+use sp_std::{
+	cell::RefCell,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+};
 
 use primitives::{Balance, CurrencyId, LiquidityPoolId};
 use traits::{BaseLiquidityPoolManager, MarginProtocolLiquidityPoolsManager};
@@ -152,10 +156,62 @@ impl module_base_liquidity_pools::Config<MarginInstance> for Runtime {
 pub type BaseLiquidityPools = module_base_liquidity_pools::Module<Runtime, MarginInstance>;
 
 pub struct DummyPoolManager;
+impl DummyPoolManager {
+	pub fn set_net_exposure(pair: TradingPair, net_exposure: FixedI128) {
+		NET_EXPOSURES.with(|v| v.borrow_mut().insert(pair, net_exposure));
+	}
+}
+
 impl MarginProtocolLiquidityPoolsManager for DummyPoolManager {
 	fn ensure_can_enable_trading_pair(_pool_id: LiquidityPoolId, _pair: TradingPair) -> DispatchResult {
 		Ok(())
 	}
+
+	fn net_exposure(_pool_id: LiquidityPoolId, pair: TradingPair) -> FixedI128 {
+		NET_EXPOSURES.with(|v| v.borrow().get(&pair).copied().unwrap_or_default())
+	}
+}
+
+thread_local! {
+	static PRICES: RefCell<BTreeMap<CurrencyId, Price>> = RefCell::new(BTreeMap::new());
+	static NET_EXPOSURES: RefCell<BTreeMap<TradingPair, FixedI128>> = RefCell::new(BTreeMap::new());
+	static NON_TRADABLE: RefCell<BTreeSet<CurrencyId>> = RefCell::new(BTreeSet::new());
+}
+
+pub struct MockTradableAssets;
+impl MockTradableAssets {
+	pub fn set_tradable(currency_id: CurrencyId, is_tradable: bool) {
+		NON_TRADABLE.with(|v| {
+			if is_tradable {
+				v.borrow_mut().remove(&currency_id)
+			} else {
+				v.borrow_mut().insert(currency_id)
+			}
+		});
+	}
+}
+
+impl TradableAssets for MockTradableAssets {
+	fn is_tradable(currency_id: CurrencyId) -> bool {
+		NON_TRADABLE.with(|v| !v.borrow().contains(&currency_id))
+	}
+}
+
+pub struct MockPrices;
+impl MockPrices {
+	pub fn set_mock_price(currency_id: CurrencyId, price: Option<Price>) {
+		if let Some(p) = price {
+			PRICES.with(|v| v.borrow_mut().insert(currency_id, p));
+		} else {
+			PRICES.with(|v| v.borrow_mut().remove(&currency_id));
+		}
+	}
+}
+
+impl DataProvider<CurrencyId, Price> for MockPrices {
+	fn get(key: &CurrencyId) -> Option<Price> {
+		PRICES.with(|v| v.borrow_mut().get(key).map(|p| *p))
+	}
 }
 
 parameter_types! {
@@ -169,6 +225,10 @@ impl pallet_timestamp::Config for Runtime {
 }
 pub type Timestamp = pallet_timestamp::Module<Runtime>;
 
+parameter_types! {
+	pub const VolatilityWindow: u32 = 3;
+}
+
 impl Config for Runtime {
 	type Event = ();
 	type BaseLiquidityPools = module_base_liquidity_pools::Module<Runtime, MarginInstance>;
@@ -177,6 +237,9 @@ impl Config for Runtime {
 	type MaxSwapRate = MaxSwap;
 	type UnixTime = Timestamp;
 	type Moment = u64;
+	type PriceProvider = DefaultPriceProvider<CurrencyId, MockPrices>;
+	type VolatilityWindow = VolatilityWindow;
+	type TradableAssets = MockTradableAssets;
 	type WeightInfo = ();
 }
 pub type ModuleLiquidityPools = Module<Runtime>;
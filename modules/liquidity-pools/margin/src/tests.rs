@@ -3,11 +3,24 @@
 use super::*;
 use mock::*;
 
-use frame_support::{assert_noop, assert_ok, traits::OnInitialize};
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::put_storage_value,
+	traits::{OnInitialize, OnRuntimeUpgrade},
+	StorageHasher, Twox64Concat,
+};
 
-use primitives::{CurrencyId, Leverage, Leverages};
+use primitives::{CurrencyId, Leverage, LeverageRange, Leverages};
 use traits::{LiquidityPools, MarginProtocolLiquidityPools};
 
+/// Long 4x to 15x, and short 10x.
+fn enabled_leverages() -> Leverages {
+	Leverages {
+		long: Some(LeverageRange { min: 4, max: 15 }),
+		short: Some(LeverageRange { min: 10, max: 10 }),
+	}
+}
+
 fn swap_rate(pair: TradingPair, is_long: bool) -> FixedI128 {
 	<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::swap_rate(0, pair, is_long)
 }
@@ -28,7 +41,7 @@ fn is_enabled_should_work() {
 			Origin::signed(ALICE),
 			0,
 			pair,
-			Leverage::ShortTen | Leverage::LongFive,
+			enabled_leverages(),
 		));
 		assert_eq!(
 			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::ShortTen),
@@ -42,6 +55,18 @@ fn is_enabled_should_work() {
 			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::ShortFifty),
 			false
 		);
+		assert_eq!(
+			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::long(15).unwrap()),
+			true
+		);
+		assert_eq!(
+			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::long(16).unwrap()),
+			false
+		);
+		assert_eq!(
+			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::LongThree),
+			false
+		);
 
 		assert_eq!(
 			ModuleLiquidityPools::is_pool_trading_pair_leverage_enabled(0, pair, Leverage::ShortTen),
@@ -50,6 +75,41 @@ fn is_enabled_should_work() {
 	});
 }
 
+#[test]
+fn set_enabled_leverages_fails_if_invalid() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::AUSD,
+			quote: CurrencyId::FEUR,
+		};
+		assert_ok!(BaseLiquidityPools::create_pool(Origin::signed(ALICE)));
+		assert_noop!(
+			ModuleLiquidityPools::set_enabled_leverages(
+				Origin::signed(ALICE),
+				0,
+				pair,
+				Leverages {
+					long: Some(LeverageRange { min: 15, max: 4 }),
+					short: None,
+				},
+			),
+			Error::<Runtime>::InvalidLeverages
+		);
+		assert_noop!(
+			ModuleLiquidityPools::set_enabled_leverages(
+				Origin::signed(ALICE),
+				0,
+				pair,
+				Leverages {
+					long: None,
+					short: Some(LeverageRange { min: 0, max: 4 }),
+				},
+			),
+			Error::<Runtime>::InvalidLeverages
+		);
+	});
+}
+
 #[test]
 fn should_disable_pool() {
 	new_test_ext().execute_with(|| {
@@ -66,7 +126,7 @@ fn should_disable_pool() {
 			Origin::signed(ALICE),
 			0,
 			pair,
-			Leverage::ShortTen | Leverage::LongFive,
+			enabled_leverages(),
 		));
 		assert_eq!(
 			ModuleLiquidityPools::pool_trading_pair_options(0, pair),
@@ -74,7 +134,7 @@ fn should_disable_pool() {
 				enabled: false,
 				bid_spread: None,
 				ask_spread: None,
-				enabled_trades: Leverage::ShortTen | Leverage::LongFive,
+				enabled_trades: enabled_leverages(),
 			}
 		);
 		assert_ok!(BaseLiquidityPools::disable_pool(Origin::signed(ALICE), 0));
@@ -240,6 +300,223 @@ fn should_set_max_spread() {
 	});
 }
 
+#[test]
+fn should_set_spread_formula() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::FEUR,
+			quote: CurrencyId::AUSD,
+		};
+		let formula = SpreadFormula {
+			base: Price::from_inner(10),
+			multiplier: Price::saturating_from_integer(2),
+		};
+		assert_ok!(BaseLiquidityPools::create_pool(Origin::signed(ALICE)));
+
+		assert_noop!(
+			ModuleLiquidityPools::set_spread_formula(Origin::signed(BOB), 0, pair, Some(formula)),
+			Error::<Runtime>::NoPermission
+		);
+
+		assert_ok!(ModuleLiquidityPools::set_spread_formula(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			Some(formula)
+		));
+		assert_eq!(ModuleLiquidityPools::pool_spread_formula(0, pair), Some(formula));
+
+		// no price samples, base spread is used
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(10))
+		);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::ask_spread(0, pair),
+			Some(Price::from_inner(10))
+		);
+
+		assert_ok!(ModuleLiquidityPools::set_spread_formula(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			None
+		));
+		assert_eq!(ModuleLiquidityPools::pool_spread_formula(0, pair), None);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			None
+		);
+	});
+}
+
+#[test]
+fn spread_formula_should_follow_volatility() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::FEUR,
+			quote: CurrencyId::AUSD,
+		};
+		MockPrices::set_mock_price(CurrencyId::AUSD, Some(Price::saturating_from_integer(1)));
+		assert_ok!(ModuleLiquidityPools::enable_trading_pair(
+			Origin::signed(UpdateOrigin::get()),
+			pair
+		));
+		assert_ok!(BaseLiquidityPools::create_pool(Origin::signed(ALICE)));
+		assert_ok!(ModuleLiquidityPools::set_spread_formula(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			Some(SpreadFormula {
+				base: Price::saturating_from_rational(1, 1000),
+				multiplier: Price::saturating_from_integer(2),
+			})
+		));
+
+		let prices = [(10, 10), (11, 10), (10, 10), (12, 10)];
+		for (i, (n, d)) in prices.iter().enumerate() {
+			MockPrices::set_mock_price(CurrencyId::FEUR, Some(Price::saturating_from_rational(*n, *d)));
+			execute_time((i as u64 + 1) * ONE_MINUTE);
+		}
+
+		// only latest 3 samples are kept
+		assert_eq!(
+			ModuleLiquidityPools::price_samples(pair),
+			vec![
+				Price::saturating_from_rational(11, 10),
+				Price::saturating_from_rational(10, 10),
+				Price::saturating_from_rational(12, 10),
+			]
+		);
+		// volatility = (0.1 + 0.2) / 2
+		assert_eq!(
+			ModuleLiquidityPools::volatility(pair),
+			Price::saturating_from_rational(15, 100)
+		);
+
+		// spread = 0.001 + 2 * 0.15
+		let spread = Price::saturating_from_rational(301, 1000);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(spread)
+		);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::ask_spread(0, pair),
+			Some(spread)
+		);
+
+		// sample only once per minute
+		MockPrices::set_mock_price(CurrencyId::FEUR, Some(Price::saturating_from_integer(2)));
+		System::set_block_number(5);
+		Timestamp::set_timestamp(4 * ONE_MINUTE + 10_000); // + 10s
+		<ModuleLiquidityPools as OnInitialize<u64>>::on_initialize(5);
+		assert_eq!(ModuleLiquidityPools::price_samples(pair).len(), 3);
+		assert_eq!(
+			ModuleLiquidityPools::price_samples(pair)[2],
+			Price::saturating_from_rational(12, 10)
+		);
+
+		// capped by max spread
+		assert_ok!(ModuleLiquidityPools::set_max_spread(
+			Origin::signed(UpdateOrigin::get()),
+			pair,
+			Price::saturating_from_rational(1, 10),
+		));
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::ask_spread(0, pair),
+			Some(Price::saturating_from_rational(1, 10))
+		);
+	});
+}
+
+#[test]
+fn spread_skew_should_follow_net_exposure() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::FEUR,
+			quote: CurrencyId::AUSD,
+		};
+		assert_ok!(BaseLiquidityPools::create_pool(Origin::signed(ALICE)));
+		assert_ok!(ModuleLiquidityPools::set_spread(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			Price::from_inner(100),
+			Price::from_inner(100)
+		));
+
+		assert_noop!(
+			ModuleLiquidityPools::set_spread_skew(
+				Origin::signed(BOB),
+				0,
+				pair,
+				Some(Price::saturating_from_integer(1))
+			),
+			Error::<Runtime>::NoPermission
+		);
+		assert_ok!(ModuleLiquidityPools::set_spread_skew(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			Some(Price::saturating_from_integer(1))
+		));
+		assert_eq!(
+			ModuleLiquidityPools::pool_spread_skew(0, pair),
+			Some(Price::saturating_from_integer(1))
+		);
+
+		// no exposure, no skew
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(100))
+		);
+
+		// traders net long, widen ask and tighten bid
+		DummyPoolManager::set_net_exposure(pair, FixedI128::from_inner(30));
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(70))
+		);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::ask_spread(0, pair),
+			Some(Price::from_inner(130))
+		);
+
+		// traders net short, widen bid and tighten ask, not below zero
+		DummyPoolManager::set_net_exposure(pair, FixedI128::from_inner(-150));
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(250))
+		);
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::ask_spread(0, pair),
+			Some(Price::from_inner(0))
+		);
+
+		// capped by max spread
+		assert_ok!(ModuleLiquidityPools::set_max_spread(
+			Origin::signed(UpdateOrigin::get()),
+			pair,
+			Price::from_inner(200),
+		));
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(200))
+		);
+
+		assert_ok!(ModuleLiquidityPools::set_spread_skew(
+			Origin::signed(ALICE),
+			0,
+			pair,
+			None
+		));
+		assert_eq!(
+			<ModuleLiquidityPools as MarginProtocolLiquidityPools<AccountId>>::bid_spread(0, pair),
+			Some(Price::from_inner(100))
+		);
+	});
+}
+
 #[test]
 fn should_set_enabled_trades() {
 	new_test_ext().execute_with(|| {
@@ -257,14 +534,14 @@ fn should_set_enabled_trades() {
 			Origin::signed(ALICE),
 			0,
 			pair,
-			Leverage::ShortTen | Leverage::LongFive,
+			enabled_leverages(),
 		));
 
 		let pool_option = MarginPoolTradingPairOption {
 			enabled: false,
 			bid_spread: None,
 			ask_spread: None,
-			enabled_trades: Leverage::ShortTen | Leverage::LongFive,
+			enabled_trades: enabled_leverages(),
 		};
 
 		assert_eq!(ModuleLiquidityPools::pool_trading_pair_options(0, pair), pool_option);
@@ -590,6 +867,31 @@ fn should_enable_disable_trading_pairs() {
 	})
 }
 
+#[test]
+fn trading_pair_with_non_tradable_currency_is_not_enabled() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::AUSD,
+			quote: CurrencyId::FEUR,
+		};
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, false);
+		assert_noop!(
+			ModuleLiquidityPools::enable_trading_pair(Origin::signed(UpdateOrigin::get()), pair),
+			Error::<Runtime>::CurrencyNotTradable
+		);
+
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, true);
+		assert_ok!(ModuleLiquidityPools::enable_trading_pair(
+			Origin::signed(UpdateOrigin::get()),
+			pair
+		));
+		assert!(ModuleLiquidityPools::is_trading_pair_enabled(pair));
+
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, false);
+		assert!(!ModuleLiquidityPools::is_trading_pair_enabled(pair));
+	})
+}
+
 #[test]
 fn liquidity_provider_should_enable_disable_trading_pairs() {
 	new_test_ext().execute_with(|| {
@@ -609,12 +911,14 @@ fn liquidity_provider_should_enable_disable_trading_pairs() {
 			pair
 		));
 		assert!(ModuleLiquidityPools::is_pool_trading_pair_enabled(0, pair));
+		assert_eq!(ModuleLiquidityPools::pool_enabled_trading_pairs(0), vec![pair]);
 		assert_ok!(ModuleLiquidityPools::liquidity_pool_disable_trading_pair(
 			Origin::signed(ALICE),
 			0,
 			pair
 		));
 		assert!(!ModuleLiquidityPools::is_pool_trading_pair_enabled(0, pair));
+		assert_eq!(ModuleLiquidityPools::pool_enabled_trading_pairs(0), vec![]);
 	})
 }
 
@@ -674,3 +978,95 @@ fn should_set_min_leveraged_amount() {
 		);
 	})
 }
+
+#[test]
+fn migrate_to_v2_should_work() {
+	new_test_ext().execute_with(|| {
+		let pair = TradingPair {
+			base: CurrencyId::AUSD,
+			quote: CurrencyId::FEUR,
+		};
+		let swap_rate = SwapRate {
+			long: FixedI128::saturating_from_rational(-1, 100),
+			short: FixedI128::saturating_from_rational(1, 100),
+		};
+		let accumulate_config = AccumulateConfig {
+			frequency: 60,
+			offset: 1,
+		};
+		put_storage_value(
+			b"MarginLiquidityPools",
+			b"TradingPairOptions",
+			&Twox64Concat::hash(&pair.encode()),
+			MarginTradingPairOptionV1 {
+				enabled: true,
+				max_spread: Some(Price::saturating_from_rational(1, 100)),
+				swap_rate: swap_rate.clone(),
+				accumulate_config: Some(accumulate_config.clone()),
+			},
+		);
+		assert_eq!(ModuleLiquidityPools::storage_version(), Releases::V1);
+
+		ModuleLiquidityPools::migrate_to_v2();
+
+		assert_eq!(
+			ModuleLiquidityPools::trading_pair_options(pair),
+			MarginTradingPairOption {
+				enabled: true,
+				swap_rate,
+				accumulate_config: Some(accumulate_config),
+			}
+		);
+		assert_eq!(
+			ModuleLiquidityPools::max_spread(pair),
+			Some(Price::saturating_from_rational(1, 100))
+		);
+		assert_eq!(ModuleLiquidityPools::storage_version(), Releases::V2);
+
+		// migrated only once
+		ModuleLiquidityPools::migrate_to_v2();
+		assert_eq!(ModuleLiquidityPools::trading_pair_options(pair).enabled, true);
+	});
+}
+
+#[test]
+fn migrate_to_v3_should_work() {
+	new_test_ext().execute_with(|| {
+		let pool_id = 0;
+		let pair = TradingPair {
+			base: CurrencyId::AUSD,
+			quote: CurrencyId::FEUR,
+		};
+		// legacy encoding: enabled, bid spread, ask spread and the bitmask of
+		// `LongTwo | LongFive | ShortThree`
+		let old = (
+			true,
+			Some(Price::saturating_from_rational(1, 100)),
+			None::<Price>,
+			0b0000_0010_0000_0101u16,
+		);
+		let key = [
+			Twox64Concat::hash(&pool_id.encode()),
+			Twox64Concat::hash(&pair.encode()),
+		]
+		.concat();
+		put_storage_value(b"MarginLiquidityPools", b"PoolTradingPairOptions", &key, old);
+		StorageVersion::put(Releases::V2);
+
+		ModuleLiquidityPools::on_runtime_upgrade();
+
+		assert_eq!(
+			PoolTradingPairOptions::get(pool_id, pair),
+			MarginPoolTradingPairOption {
+				enabled: true,
+				bid_spread: Some(Price::saturating_from_rational(1, 100)),
+				ask_spread: None,
+				enabled_trades: Leverages {
+					long: Some(LeverageRange { min: 2, max: 2 }),
+					short: Some(LeverageRange { min: 3, max: 3 }),
+				},
+			}
+		);
+		assert_eq!(ModuleLiquidityPools::storage_version(), Releases::V3);
+	});
+}
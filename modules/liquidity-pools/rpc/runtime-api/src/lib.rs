@@ -0,0 +1,70 @@
+//! Runtime API definition for liquidity pools modules.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+// The `unnecessary_mut_passed` warning originates from `decl_runtime_apis` macro.
+#![allow(clippy::unnecessary_mut_passed)]
+
+use codec::{Codec, Decode, Encode};
+use laminar_primitives::{Balance, CurrencyId, IdentityInfo, Leverages, LiquidityPoolId, Price, TradingPair};
+use sp_arithmetic::{FixedI128, FixedU128, Permill};
+use sp_core::RuntimeDebug;
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct LiquidityPoolInfo<AccountId> {
+	pub pool_id: LiquidityPoolId,
+	pub owner: AccountId,
+	pub liquidity: Balance,
+	pub identity: Option<IdentityInfo>,
+	pub is_verified: bool,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct MarginPoolTradingPairInfo {
+	pub pair: TradingPair,
+	pub bid_spread: Option<Price>,
+	pub ask_spread: Option<Price>,
+	pub enabled_leverages: Leverages,
+	pub long_swap_rate: FixedI128,
+	pub short_swap_rate: FixedI128,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct MarginPoolInfo<AccountId> {
+	pub pool: LiquidityPoolInfo<AccountId>,
+	pub trading_pairs: Vec<MarginPoolTradingPairInfo>,
+	pub enp: Option<FixedI128>,
+	pub ell: Option<FixedI128>,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct SyntheticPoolCurrencyInfo {
+	pub currency_id: CurrencyId,
+	pub bid_spread: Option<Price>,
+	pub ask_spread: Option<Price>,
+	pub additional_collateral_ratio: Permill,
+	pub collateral_ratio: Option<FixedU128>,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct SyntheticPoolInfo<AccountId> {
+	pub pool: LiquidityPoolInfo<AccountId>,
+	pub currencies: Vec<SyntheticPoolCurrencyInfo>,
+}
+
+sp_api::decl_runtime_apis! {
+	pub trait LiquidityPoolsApi<AccountId> where
+		AccountId: Codec,
+	{
+		fn margin_pools() -> Vec<MarginPoolInfo<AccountId>>;
+		fn synthetic_pools() -> Vec<SyntheticPoolInfo<AccountId>>;
+	}
+}
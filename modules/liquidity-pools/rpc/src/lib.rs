@@ -0,0 +1,80 @@
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use liquidity_pools_rpc_runtime_api::{
+	LiquidityPoolInfo, LiquidityPoolsApi as LiquidityPoolsRuntimeApi, MarginPoolInfo, MarginPoolTradingPairInfo,
+	SyntheticPoolCurrencyInfo, SyntheticPoolInfo,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+#[rpc]
+pub trait LiquidityPoolsApi<BlockHash, AccountId> {
+	#[rpc(name = "liquidityPools_marginPools")]
+	fn margin_pools(&self, at: Option<BlockHash>) -> Result<Vec<MarginPoolInfo<AccountId>>>;
+
+	#[rpc(name = "liquidityPools_syntheticPools")]
+	fn synthetic_pools(&self, at: Option<BlockHash>) -> Result<Vec<SyntheticPoolInfo<AccountId>>>;
+}
+
+/// A struct that implements the [`LiquidityPoolsApi`].
+pub struct LiquidityPools<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> LiquidityPools<C, B> {
+	/// Create new `LiquidityPools` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Self {
+			client,
+			_marker: Default::default(),
+		}
+	}
+}
+
+pub enum Error {
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId> LiquidityPoolsApi<<Block as BlockT>::Hash, AccountId> for LiquidityPools<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: LiquidityPoolsRuntimeApi<Block, AccountId>,
+	AccountId: Codec,
+{
+	fn margin_pools(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<MarginPoolInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		api.margin_pools(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to get margin pools.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn synthetic_pools(&self, at: Option<<Block as BlockT>::Hash>) -> Result<Vec<SyntheticPoolInfo<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		api.synthetic_pools(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to get synthetic pools.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
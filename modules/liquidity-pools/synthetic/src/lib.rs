@@ -1,12 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use frame_support::{decl_error, decl_event, decl_module, decl_storage, ensure, traits::EnsureOrigin, weights::Weight};
+use frame_support::{
+	decl_error, decl_event, decl_module, decl_storage, ensure, storage::IterableStorageDoubleMap, traits::EnsureOrigin,
+	weights::Weight,
+};
 use frame_system::ensure_signed;
 use orml_utilities::with_transaction_result;
 use primitives::{Balance, CurrencyId, LiquidityPoolId, Price};
 use sp_runtime::{DispatchResult, ModuleId, Permill, RuntimeDebug};
 use sp_std::prelude::*;
-use traits::{LiquidityPools, OnDisableLiquidityPool, OnRemoveLiquidityPool, SyntheticProtocolLiquidityPools};
+use traits::{
+	LiquidityPools, OnDisableLiquidityPool, OnRemoveLiquidityPool, SyntheticProtocolLiquidityPools, TradableAssets,
+};
 
 mod default_weight;
 mod mock;
@@ -59,6 +64,9 @@ pub trait Config: frame_system::Config {
 	/// Required origin for updating protocol options.
 	type UpdateOrigin: EnsureOrigin<Self::Origin>;
 
+	/// Tradability of currencies. Synthetic may only be enabled and minted if tradable.
+	type TradableAssets: TradableAssets;
+
 	/// Weight information for the extrinsics in this module.
 	type WeightInfo: WeightInfo;
 }
@@ -194,6 +202,9 @@ decl_error! {
 
 		/// Spread is higher than max allowed.
 		SpreadTooHigh,
+
+		/// Currency is not tradable.
+		CurrencyNotTradable,
 	}
 }
 
@@ -245,6 +256,7 @@ impl<T: Config> SyntheticProtocolLiquidityPools<T::AccountId> for Module<T> {
 
 	fn can_mint(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> bool {
 		Self::pool_currency_options(pool_id, currency_id).synthetic_enabled
+			&& T::TradableAssets::is_tradable(currency_id)
 	}
 }
 
@@ -259,6 +271,16 @@ impl<T: Config> Module<T> {
 		}
 		option
 	}
+
+	/// Synthetic currencies enabled to mint in `pool_id`, sorted.
+	pub fn pool_enabled_currencies(pool_id: LiquidityPoolId) -> Vec<CurrencyId> {
+		let mut currencies: Vec<CurrencyId> = PoolCurrencyOptions::iter_prefix(pool_id)
+			.filter(|(_, option)| option.synthetic_enabled)
+			.map(|(currency_id, _)| currency_id)
+			.collect();
+		currencies.sort();
+		currencies
+	}
 }
 
 // Dispatchable calls implementation
@@ -302,6 +324,10 @@ impl<T: Config> Module<T> {
 		enabled: bool,
 	) -> DispatchResult {
 		ensure!(Self::is_owner(pool_id, who), Error::<T>::NoPermission);
+		ensure!(
+			!enabled || T::TradableAssets::is_tradable(currency_id),
+			Error::<T>::CurrencyNotTradable
+		);
 		PoolCurrencyOptions::mutate(pool_id, currency_id, |o| o.synthetic_enabled = enabled);
 		Ok(())
 	}
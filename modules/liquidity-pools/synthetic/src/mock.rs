@@ -14,6 +14,7 @@ use sp_runtime::{
 use orml_currencies::Currency;
 use orml_traits::parameter_type_with_key;
 use primitives::{Balance, CurrencyId, LiquidityPoolId};
+use sp_std::{cell::RefCell, collections::btree_set::BTreeSet};
 use traits::BaseLiquidityPoolManager;
 
 pub type BlockNumber = u64;
@@ -148,10 +149,34 @@ impl module_base_liquidity_pools::Config<SyntheticInstance> for Runtime {
 }
 pub type BaseLiquidityPools = module_base_liquidity_pools::Module<Runtime, SyntheticInstance>;
 
+thread_local! {
+	static NON_TRADABLE: RefCell<BTreeSet<CurrencyId>> = RefCell::new(BTreeSet::new());
+}
+
+pub struct MockTradableAssets;
+impl MockTradableAssets {
+	pub fn set_tradable(currency_id: CurrencyId, is_tradable: bool) {
+		NON_TRADABLE.with(|v| {
+			if is_tradable {
+				v.borrow_mut().remove(&currency_id)
+			} else {
+				v.borrow_mut().insert(currency_id)
+			}
+		});
+	}
+}
+
+impl TradableAssets for MockTradableAssets {
+	fn is_tradable(currency_id: CurrencyId) -> bool {
+		NON_TRADABLE.with(|v| !v.borrow().contains(&currency_id))
+	}
+}
+
 impl Config for Runtime {
 	type Event = ();
 	type BaseLiquidityPools = module_base_liquidity_pools::Module<Runtime, SyntheticInstance>;
 	type UpdateOrigin = EnsureSignedBy<UpdateOrigin, AccountId>;
+	type TradableAssets = MockTradableAssets;
 	type WeightInfo = ();
 }
 pub type ModuleLiquidityPools = Module<Runtime>;
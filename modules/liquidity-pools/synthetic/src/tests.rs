@@ -321,5 +321,38 @@ fn should_set_synthetic_enabled() {
 			<ModuleLiquidityPools as SyntheticProtocolLiquidityPools<AccountId>>::can_mint(0, CurrencyId::AUSD),
 			true
 		);
+		assert_eq!(ModuleLiquidityPools::pool_enabled_currencies(0), vec![CurrencyId::AUSD]);
+	});
+}
+
+#[test]
+fn synthetic_of_non_tradable_currency_cannot_be_enabled_or_minted() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(BaseLiquidityPools::create_pool(Origin::signed(ALICE)));
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, false);
+		assert_noop!(
+			ModuleLiquidityPools::set_synthetic_enabled(Origin::signed(ALICE), 0, CurrencyId::FEUR, true),
+			Error::<Runtime>::CurrencyNotTradable
+		);
+
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, true);
+		assert_ok!(ModuleLiquidityPools::set_synthetic_enabled(
+			Origin::signed(ALICE),
+			0,
+			CurrencyId::FEUR,
+			true
+		));
+		assert!(<ModuleLiquidityPools as SyntheticProtocolLiquidityPools<AccountId>>::can_mint(0, CurrencyId::FEUR));
+
+		MockTradableAssets::set_tradable(CurrencyId::FEUR, false);
+		assert!(!<ModuleLiquidityPools as SyntheticProtocolLiquidityPools<AccountId>>::can_mint(0, CurrencyId::FEUR));
+
+		// disabling is always allowed
+		assert_ok!(ModuleLiquidityPools::set_synthetic_enabled(
+			Origin::signed(ALICE),
+			0,
+			CurrencyId::FEUR,
+			false
+		));
 	});
 }
@@ -7,16 +7,17 @@
 #![allow(clippy::unnecessary_mut_passed)]
 
 use codec::{Codec, Decode, Encode};
-use laminar_primitives::LiquidityPoolId;
-use sp_arithmetic::FixedI128;
+use laminar_primitives::{Balance, Leverage, LiquidityPoolId, PriceRoute, TradingPair};
+use sp_arithmetic::{FixedI128, Permill};
 use sp_core::RuntimeDebug;
+use sp_runtime::DispatchError;
 use sp_std::prelude::*;
 
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[derive(Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Default, RuntimeDebug)]
 pub struct MarginTraderState {
 	pub equity: FixedI128,
 	pub margin_held: FixedI128,
@@ -26,18 +27,99 @@ pub struct MarginTraderState {
 }
 
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
-#[derive(Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct MarginTraderStatus {
+	pub trader_state: MarginTraderState,
+	pub is_margin_called: bool,
+	pub should_stop_out: bool,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Default, RuntimeDebug)]
 pub struct MarginPoolState {
 	pub enp: FixedI128,
 	pub ell: FixedI128,
 	pub required_deposit: FixedI128,
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct MarginPosition {
+	pub position_id: u64,
+	pub pool_id: LiquidityPoolId,
+	pub pair: TradingPair,
+	pub leverage: Leverage,
+	pub open_price: FixedI128,
+	pub current_price: FixedI128,
+	pub unrealized_pl: FixedI128,
+	pub accumulated_swap: FixedI128,
+	pub margin_held: FixedI128,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct MarginOpenPositionQuote {
+	pub fill_price: FixedI128,
+	pub margin_held: FixedI128,
+	pub trader_state: MarginTraderState,
+	pub enp: FixedI128,
+	pub ell: FixedI128,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum MarginRisk {
+	MarginCall,
+	StopOut,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct MarginRiskThreshold {
+	pub margin_call: Permill,
+	pub stop_out: Permill,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct MarginTraderRisk<AccountId> {
+	pub who: AccountId,
+	pub pool_id: LiquidityPoolId,
+	pub risk: MarginRisk,
+	pub margin_level: FixedI128,
+	pub threshold: MarginRiskThreshold,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct MarginPoolRisk {
+	pub pool_id: LiquidityPoolId,
+	pub risk: MarginRisk,
+	pub enp: FixedI128,
+	pub ell: FixedI128,
+	pub enp_threshold: MarginRiskThreshold,
+	pub ell_threshold: MarginRiskThreshold,
+}
+
 sp_api::decl_runtime_apis! {
+	/// Version 2 added all the methods but `trader_state` and `pool_state`.
+	#[api_version(2)]
 	pub trait MarginProtocolApi<AccountId> where
 		AccountId: Codec,
 	{
 		fn trader_state(who: AccountId, pool_id: LiquidityPoolId) -> MarginTraderState;
+		fn trader_status(who: AccountId, pool_id: LiquidityPoolId) -> MarginTraderStatus;
 		fn pool_state(pool_id: LiquidityPoolId) -> Option<MarginPoolState>;
+		fn positions(who: AccountId, pool_id: Option<LiquidityPoolId>) -> Vec<MarginPosition>;
+		fn open_position_quote(
+			who: AccountId,
+			pool_id: LiquidityPoolId,
+			pair: TradingPair,
+			leverage: Leverage,
+			leveraged_amount: Balance,
+		) -> Result<MarginOpenPositionQuote, DispatchError>;
+		fn price_route(pair: TradingPair) -> PriceRoute;
+		fn trader_liquidation_candidates(offset: u32, limit: u32) -> Vec<MarginTraderRisk<AccountId>>;
+		fn pool_liquidation_candidates(offset: u32, limit: u32) -> Vec<MarginPoolRisk>;
 	}
 }
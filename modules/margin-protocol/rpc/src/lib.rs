@@ -1,17 +1,31 @@
 use codec::Codec;
-use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use futures::{future, StreamExt, TryStreamExt};
+use jsonrpc_core::{
+	futures::{
+		future::Executor as Executor01, stream as stream01, Future as Future01, Sink as Sink01, Stream as Stream01,
+	},
+	Error as RpcError, ErrorCode, Result,
+};
 use jsonrpc_derive::rpc;
-use laminar_primitives::LiquidityPoolId;
+use jsonrpc_pubsub::{manager::SubscriptionManager, typed::Subscriber, SubscriptionId};
+use laminar_primitives::{Balance, Leverage, LiquidityPoolId, PriceRoute, TradingPair};
 pub use margin_protocol_rpc_runtime_api::{
-	MarginPoolState, MarginProtocolApi as MarginProtocolRuntimeApi, MarginTraderState,
+	MarginOpenPositionQuote, MarginPoolRisk, MarginPoolState, MarginPosition,
+	MarginProtocolApi as MarginProtocolRuntimeApi, MarginRisk, MarginRiskThreshold, MarginTraderRisk,
+	MarginTraderState, MarginTraderStatus,
 };
-use sp_api::ProvideRuntimeApi;
+use sc_client_api::BlockchainEvents;
+use serde::Serialize;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, DispatchError};
 use std::sync::Arc;
 
 #[rpc]
 pub trait MarginProtocolApi<BlockHash, AccountId> {
+	/// RPC metadata
+	type Metadata;
+
 	#[rpc(name = "margin_traderState")]
 	fn trader_state(
 		&self,
@@ -22,43 +36,207 @@ pub trait MarginProtocolApi<BlockHash, AccountId> {
 
 	#[rpc(name = "margin_poolState")]
 	fn pool_state(&self, pool_id: LiquidityPoolId, at: Option<BlockHash>) -> Result<Option<MarginPoolState>>;
+
+	#[rpc(name = "margin_positions")]
+	fn positions(
+		&self,
+		who: AccountId,
+		pool_id: Option<LiquidityPoolId>,
+		at: Option<BlockHash>,
+	) -> Result<Vec<MarginPosition>>;
+
+	#[rpc(name = "margin_openPositionQuote")]
+	fn open_position_quote(
+		&self,
+		who: AccountId,
+		pool_id: LiquidityPoolId,
+		pair: TradingPair,
+		leverage: Leverage,
+		leveraged_amount: Balance,
+		at: Option<BlockHash>,
+	) -> Result<std::result::Result<MarginOpenPositionQuote, DispatchError>>;
+
+	#[rpc(name = "margin_priceRoute")]
+	fn price_route(&self, pair: TradingPair, at: Option<BlockHash>) -> Result<PriceRoute>;
+
+	#[rpc(name = "margin_traderLiquidationCandidates")]
+	fn trader_liquidation_candidates(
+		&self,
+		offset: u32,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<MarginTraderRisk<AccountId>>>;
+
+	#[rpc(name = "margin_poolLiquidationCandidates")]
+	fn pool_liquidation_candidates(
+		&self,
+		offset: u32,
+		limit: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<MarginPoolRisk>>;
+
+	/// Subscribe to trader state, margin call and stop out status of `who` in `pool_id`. Pushes the
+	/// status at the best block, and then every change of it on new best blocks.
+	#[pubsub(
+		subscription = "margin_traderStatusChanged",
+		subscribe,
+		name = "margin_subscribeTraderStatus"
+	)]
+	fn subscribe_trader_status(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<MarginTraderStatus>,
+		who: AccountId,
+		pool_id: LiquidityPoolId,
+	);
+
+	#[pubsub(
+		subscription = "margin_traderStatusChanged",
+		unsubscribe,
+		name = "margin_unsubscribeTraderStatus"
+	)]
+	fn unsubscribe_trader_status(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
+
+	/// Subscribe to state of `pool_id`. Pushes the state at the best block, and then every change
+	/// of it on new best blocks.
+	#[pubsub(
+		subscription = "margin_poolStateChanged",
+		subscribe,
+		name = "margin_subscribePoolState"
+	)]
+	fn subscribe_pool_state(
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<Option<MarginPoolState>>,
+		pool_id: LiquidityPoolId,
+	);
+
+	#[pubsub(
+		subscription = "margin_poolStateChanged",
+		unsubscribe,
+		name = "margin_unsubscribePoolState"
+	)]
+	fn unsubscribe_pool_state(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool>;
 }
 
 /// A struct that implements the [`MarginProtocolApi`].
 pub struct MarginProtocol<C, B> {
 	client: Arc<C>,
+	manager: SubscriptionManager,
 	_marker: std::marker::PhantomData<B>,
 }
 
 impl<C, B> MarginProtocol<C, B> {
-	/// Create new `MarginProtocol` with the given reference to the client.
-	pub fn new(client: Arc<C>) -> Self {
+	/// Create new `MarginProtocol` with the given reference to the client, and the executor to
+	/// run subscriptions.
+	pub fn new<E>(client: Arc<C>, executor: E) -> Self
+	where
+		E: Executor01<Box<dyn Future01<Item = (), Error = ()> + Send>> + Send + Sync + 'static,
+	{
 		Self {
 			client,
+			manager: SubscriptionManager::new(Arc::new(executor)),
 			_marker: Default::default(),
 		}
 	}
 }
 
+impl<C, Block> MarginProtocol<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + HeaderBackend<Block> + BlockchainEvents<Block>,
+{
+	/// Push the value returned by `f` at the best block to `subscriber`, and then every change of
+	/// it on new best blocks. Blocks at which `f` returns `None` are skipped.
+	fn subscribe_changes<T, F>(&self, subscriber: Subscriber<T>, f: F)
+	where
+		T: Serialize + Clone + PartialEq + Send + 'static,
+		F: Fn(&C, &BlockId<Block>) -> Option<T> + Send + 'static,
+	{
+		let client = self.client.clone();
+		let initial = f(&*client, &BlockId::hash(client.info().best_hash));
+		let mut last = initial.clone();
+
+		let changes = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.filter_map(move |notification| {
+				let value = f(&*client, &BlockId::hash(notification.hash)).filter(|value| last.as_ref() != Some(value));
+				if value.is_some() {
+					last = value.clone();
+				}
+				future::ready(value)
+			})
+			.map(|value| Ok::<_, ()>(Ok(value)))
+			.compat();
+
+		self.manager.add(subscriber, |sink| {
+			sink.sink_map_err(|e| log::warn!("Error sending notifications: {:?}", e))
+				.send_all(stream01::iter_ok(initial.map(Ok)).chain(changes))
+				// the resulting stream is ignored, if the first stream is over the subscriber is gone
+				.map(|_| ())
+		});
+	}
+}
+
 pub enum Error {
 	RuntimeError,
+	UnsupportedApiVersion,
 }
 
 impl From<Error> for i64 {
 	fn from(e: Error) -> i64 {
 		match e {
 			Error::RuntimeError => 1,
+			Error::UnsupportedApiVersion => 2,
 		}
 	}
 }
 
-impl<C, Block, AccountId> MarginProtocolApi<<Block as BlockT>::Hash, AccountId> for MarginProtocol<C, Block>
+/// Whether the runtime API at `at` is of version 2 or later, which added all the methods but
+/// `trader_state` and `pool_state`.
+fn has_api_v2<C, Block, AccountId>(client: &C, at: &BlockId<Block>) -> bool
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: MarginProtocolRuntimeApi<Block, AccountId>,
+	AccountId: Codec,
+{
+	client
+		.runtime_api()
+		.has_api_with::<dyn MarginProtocolRuntimeApi<Block, AccountId, Error = ()>, _>(at, |v| v >= 2)
+		.unwrap_or(false)
+}
+
+/// Fail if the runtime API at `at` is older than version 2.
+fn ensure_api_v2<C, Block, AccountId>(client: &C, at: &BlockId<Block>) -> Result<()>
 where
 	Block: BlockT,
-	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C: ProvideRuntimeApi<Block>,
 	C::Api: MarginProtocolRuntimeApi<Block, AccountId>,
 	AccountId: Codec,
 {
+	if has_api_v2::<_, _, AccountId>(client, at) {
+		Ok(())
+	} else {
+		Err(RpcError {
+			code: ErrorCode::ServerError(Error::UnsupportedApiVersion.into()),
+			message: "Not supported by the runtime, margin protocol API version 2 required.".into(),
+			data: None,
+		})
+	}
+}
+
+impl<C, Block, AccountId> MarginProtocolApi<<Block as BlockT>::Hash, AccountId> for MarginProtocol<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C::Api: MarginProtocolRuntimeApi<Block, AccountId>,
+	AccountId: Codec + Clone + Send + 'static,
+{
+	type Metadata = sc_rpc_api::Metadata;
+
 	fn trader_state(
 		&self,
 		who: AccountId,
@@ -91,4 +269,129 @@ where
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
+
+	fn positions(
+		&self,
+		who: AccountId,
+		pool_id: Option<LiquidityPoolId>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<MarginPosition>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		ensure_api_v2::<_, _, AccountId>(&*self.client, &at)?;
+		api.positions(&at, who, pool_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to get positions.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn open_position_quote(
+		&self,
+		who: AccountId,
+		pool_id: LiquidityPoolId,
+		pair: TradingPair,
+		leverage: Leverage,
+		leveraged_amount: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<std::result::Result<MarginOpenPositionQuote, DispatchError>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		ensure_api_v2::<_, _, AccountId>(&*self.client, &at)?;
+		api.open_position_quote(&at, who, pool_id, pair, leverage, leveraged_amount)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to get open position quote.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn price_route(&self, pair: TradingPair, at: Option<<Block as BlockT>::Hash>) -> Result<PriceRoute> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		ensure_api_v2::<_, _, AccountId>(&*self.client, &at)?;
+		api.price_route(&at, pair).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to get price route.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn trader_liquidation_candidates(
+		&self,
+		offset: u32,
+		limit: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<MarginTraderRisk<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		ensure_api_v2::<_, _, AccountId>(&*self.client, &at)?;
+		api.trader_liquidation_candidates(&at, offset, limit)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to get trader liquidation candidates.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn pool_liquidation_candidates(
+		&self,
+		offset: u32,
+		limit: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<MarginPoolRisk>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		ensure_api_v2::<_, _, AccountId>(&*self.client, &at)?;
+		api.pool_liquidation_candidates(&at, offset, limit)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to get pool liquidation candidates.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn subscribe_trader_status(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<MarginTraderStatus>,
+		who: AccountId,
+		pool_id: LiquidityPoolId,
+	) {
+		self.subscribe_changes(subscriber, move |client, at| {
+			if !has_api_v2::<_, _, AccountId>(client, at) {
+				return None;
+			}
+			client.runtime_api().trader_status(at, who.clone(), pool_id).ok()
+		});
+	}
+
+	fn unsubscribe_trader_status(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
+
+	fn subscribe_pool_state(
+		&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<Option<MarginPoolState>>,
+		pool_id: LiquidityPoolId,
+	) {
+		self.subscribe_changes(subscriber, move |client, at| {
+			client.runtime_api().pool_state(at, pool_id).ok()
+		});
+	}
+
+	fn unsubscribe_pool_state(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.manager.cancel(id))
+	}
 }
@@ -72,4 +72,11 @@ impl crate::WeightInfo for () {
 			.saturating_add(DbWeight::get().reads(5 as Weight))
 			.saturating_add(DbWeight::get().writes(3 as Weight))
 	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_price_route() -> Weight {
+		(80_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
 }
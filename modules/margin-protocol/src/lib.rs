@@ -3,6 +3,7 @@
 use codec::{Decode, Encode};
 use frame_support::{
 	debug, decl_error, decl_event, decl_module, decl_storage, ensure,
+	storage::{migration::StorageIterator, with_transaction, TransactionOutcome},
 	traits::{EnsureOrigin, Get},
 	weights::{DispatchClass, Weight},
 	IterableStorageDoubleMap, IterableStorageMap,
@@ -15,7 +16,7 @@ use orml_traits::{BasicCurrency, PriceProvider};
 use orml_utilities::with_transaction_result;
 use primitives::{
 	arithmetic::{fixed_i128_from_fixed_u128, fixed_i128_from_u128, fixed_i128_mul_signum, u128_from_fixed_i128},
-	Balance, CurrencyId, Leverage, LiquidityPoolId, Price, TradingPair,
+	Balance, CurrencyId, Leverage, LiquidityPoolId, Price, PriceRoute, TradingPair,
 };
 use sp_arithmetic::{
 	traits::{Bounded, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Saturating},
@@ -35,8 +36,8 @@ use sp_runtime::{
 };
 use sp_std::{cmp, prelude::*, result};
 use traits::{
-	BaseLiquidityPoolManager, LiquidityPools, MarginProtocolLiquidityPools, MarginProtocolLiquidityPoolsManager,
-	OpenPositionError,
+	is_risk_pricing, price_usage, with_price_usage, with_risk_prices, BaseLiquidityPoolManager, LiquidityPools,
+	MarginProtocolLiquidityPools, MarginProtocolLiquidityPoolsManager, OpenPositionError, PriceGuard, PriceUsage,
 };
 
 #[cfg(feature = "std")]
@@ -60,10 +61,14 @@ pub trait WeightInfo {
 	fn liquidity_pool_become_safe() -> Weight;
 	fn liquidity_pool_force_close() -> Weight;
 	fn set_trading_pair_risk_threshold() -> Weight;
+	fn set_price_route() -> Weight;
 }
 
 const MODULE_ID: ModuleId = ModuleId(*b"lami/mgn");
 
+/// Maximum number of legs in a price route.
+pub const MAX_PRICE_ROUTE_LEGS: usize = 4;
+
 pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
@@ -77,6 +82,12 @@ pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
 	/// Provides market prices.
 	type PriceProvider: PriceProvider<CurrencyId, Price>;
 
+	/// Provides prices for risk checks, i.e. margin levels, ENP and ELL.
+	type RiskPriceProvider: PriceProvider<CurrencyId, Price>;
+
+	/// Rejects stale prices.
+	type PriceGuard: PriceGuard;
+
 	/// The account ID of treasury.
 	type GetTreasuryAccountId: Get<Self::AccountId>;
 
@@ -105,32 +116,42 @@ pub type PositionId = u64;
 #[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
 pub struct Position<T: Config> {
 	/// Owner.
-	owner: T::AccountId,
+	pub owner: T::AccountId,
 
 	/// Liquidity pool ID where the position is opened in.
-	pool: LiquidityPoolId,
+	pub pool: LiquidityPoolId,
 
 	/// Trader pair.
-	pair: TradingPair,
+	pub pair: TradingPair,
 
 	/// Leverage.
-	leverage: Leverage,
+	pub leverage: Leverage,
 
 	/// Leveraged held amount.
 	///
 	/// Positive value if long position, negative if short.
-	leveraged_held: FixedI128,
+	pub leveraged_held: FixedI128,
 
 	/// Leveraged debits amount.
 	///
 	/// Negative value if long position, positive if short.
-	leveraged_debits: FixedI128,
+	pub leveraged_debits: FixedI128,
 
 	/// Accumulated swap rate on open position.
-	open_accumulated_swap_rate: FixedI128,
+	pub open_accumulated_swap_rate: FixedI128,
 
 	/// Margin held.
-	margin_held: FixedI128,
+	pub margin_held: FixedI128,
+}
+
+impl<T: Config> Position<T> {
+	/// open_price = abs(leveraged_debits / leveraged_held)
+	pub fn open_price(&self) -> FixedI128 {
+		self.leveraged_debits
+			.checked_div(&self.leveraged_held)
+			.expect("ensured safe on open position")
+			.saturating_abs()
+	}
 }
 
 /// Positions snapshot.
@@ -185,6 +206,36 @@ impl TradingPairRiskThreshold {
 	}
 }
 
+/// Type of risk threshold of a trading pair.
+#[derive(Encode, Decode, Copy, Clone, RuntimeDebug, Eq, PartialEq)]
+pub enum RiskThresholdType {
+	/// Trader risk threshold.
+	Trader,
+
+	/// Liquidity pool ENP threshold.
+	Enp,
+
+	/// Liquidity pool ELL threshold.
+	Ell,
+}
+
+/// Storage version of the module, for storage migrations.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Releases {
+	/// Risk thresholds of each trading pair stored together in `RiskThresholds`.
+	V1,
+	/// Risk thresholds stored in `MarginCallThresholds` and `StopOutThresholds`.
+	V2,
+	/// Positions snapshots recomputed, after short amounts were derived from long ones.
+	V3,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
 decl_storage! {
 	trait Store for Module<T: Config> as MarginProtocol {
 		/// Next available position ID.
@@ -223,20 +274,35 @@ decl_storage! {
 		/// New positions may only be opened in a pool if which not in margin called state.
 		MarginCalledPools get(fn margin_called_pools): map hasher(twox_64_concat) LiquidityPoolId => Option<()>;
 
-		/// Risk thresholds of a trading pair, including trader risk threshold, pool ENP and ELL risk threshold.
+		/// Margin call thresholds of a trading pair, including trader risk threshold, pool ENP and ELL risk threshold.
+		///
+		/// Thresholds are stored as plain values, so that they could be gradually updated.
 		///
-		/// DEFAULT-NOTE: `trader`, `enp`, and `ell` are all `None` by default.
-		RiskThresholds get(fn risk_thresholds): map hasher(twox_64_concat) TradingPair => TradingPairRiskThreshold;
+		/// DEFAULT-NOTE: `None` by default.
+		pub MarginCallThresholds: double_map hasher(twox_64_concat) TradingPair, hasher(twox_64_concat) RiskThresholdType => Option<Permill>;
+
+		/// Stop out thresholds of a trading pair, including trader risk threshold, pool ENP and ELL risk threshold.
+		///
+		/// DEFAULT-NOTE: `None` by default.
+		pub StopOutThresholds: double_map hasher(twox_64_concat) TradingPair, hasher(twox_64_concat) RiskThresholdType => Option<Permill>;
+
+		/// Price routes of trading pairs.
+		///
+		/// DEFAULT-NOTE: `None` by default, and the pair would be priced from USD prices of its
+		/// base and quote currency.
+		pub PriceRoutes get(fn price_routes): map hasher(twox_64_concat) TradingPair => Option<PriceRoute>;
+
+		/// Storage version of the module. New chains start with the latest version.
+		StorageVersion get(fn storage_version) build(|_: &GenesisConfig| Releases::V3): Releases;
 	}
 
 	add_extra_genesis {
 		config(risk_thresholds): Vec<(TradingPair, RiskThreshold, RiskThreshold, RiskThreshold)>;
 		build(|config: &GenesisConfig| {
 			config.risk_thresholds.iter().for_each(|(pair, trader, enp, ell)| {
-				RiskThresholds::insert(
-					pair,
-					TradingPairRiskThreshold::new(Some(*trader), Some(*enp), Some(*ell)),
-				);
+				Module::<T>::set_risk_threshold(*pair, RiskThresholdType::Trader, *trader);
+				Module::<T>::set_risk_threshold(*pair, RiskThresholdType::Enp, *enp);
+				Module::<T>::set_risk_threshold(*pair, RiskThresholdType::Ell, *ell);
 			})
 		})
 	}
@@ -281,6 +347,9 @@ decl_event! {
 
 		/// Trading pair risk threshold set: \[pair, trader_risk_threshold, liquidity_pool_enp_threshold, liquidity_pool_ell_threshold\]
 		TradingPairRiskThresholdSet(TradingPair, Option<RiskThreshold>, Option<RiskThreshold>, Option<RiskThreshold>),
+
+		/// Price route of a trading pair set: \[pair, route\]
+		PriceRouteSet(TradingPair, Option<PriceRoute>),
 	}
 }
 
@@ -289,6 +358,12 @@ decl_error! {
 		/// No price from provider.
 		NoPrice,
 
+		/// Price is too old to be used.
+		StalePrice,
+
+		/// Price is not reliable enough to be used.
+		UnreliablePrice,
+
 		/// Ask spread not set.
 		NoAskSpread,
 
@@ -357,6 +432,9 @@ decl_error! {
 
 		/// Risk threshold not set.
 		NoRiskThreshold,
+
+		/// Price route legs don't chain from base to quote currency, or too many legs.
+		InvalidPriceRoute,
 	}
 }
 
@@ -394,7 +472,7 @@ decl_module! {
 		) {
 			with_transaction_result(|| {
 				let who = ensure_signed(origin)?;
-				Self::do_open_position(&who, pool_id, pair, leverage, leveraged_amount, price)?;
+				Self::do_open_position(&who, pool_id, pair, leverage, leveraged_amount, Some(price))?;
 				Ok(())
 			})?;
 		}
@@ -444,7 +522,7 @@ decl_module! {
 				ensure_none(origin)?;
 				let who = T::Lookup::lookup(who)?;
 
-				Self::do_trader_margin_call(&who, pool_id)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_trader_margin_call(&who, pool_id))?;
 				Self::deposit_event(RawEvent::TraderMarginCalled(who));
 
 				Ok(())
@@ -464,7 +542,7 @@ decl_module! {
 				ensure_none(origin)?;
 				let who = T::Lookup::lookup(who)?;
 
-				Self::do_trader_become_safe(&who, pool_id)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_trader_become_safe(&who, pool_id))?;
 				Self::deposit_event(RawEvent::TraderBecameSafe(who));
 
 				Ok(())
@@ -484,7 +562,7 @@ decl_module! {
 				ensure_none(origin)?;
 				let who = T::Lookup::lookup(who)?;
 
-				Self::do_trader_stop_out(&who, pool_id)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_trader_stop_out(&who, pool_id))?;
 				Self::deposit_event(RawEvent::TraderStoppedOut(who));
 
 				Ok(())
@@ -498,7 +576,7 @@ decl_module! {
 		pub fn liquidity_pool_margin_call(origin, #[compact] pool: LiquidityPoolId) {
 			with_transaction_result(|| {
 				ensure_none(origin)?;
-				Self::do_liquidity_pool_margin_call(pool)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_liquidity_pool_margin_call(pool))?;
 				Self::deposit_event(RawEvent::LiquidityPoolMarginCalled(pool));
 				Ok(())
 			})?;
@@ -511,7 +589,7 @@ decl_module! {
 		pub fn liquidity_pool_become_safe(origin, #[compact] pool: LiquidityPoolId) {
 			with_transaction_result(|| {
 				ensure_none(origin)?;
-				Self::do_liquidity_pool_become_safe(pool)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_liquidity_pool_become_safe(pool))?;
 				Self::deposit_event(RawEvent::LiquidityPoolBecameSafe(pool));
 				Ok(())
 			})?;
@@ -524,7 +602,7 @@ decl_module! {
 		pub fn liquidity_pool_force_close(origin, #[compact] pool: LiquidityPoolId) {
 			with_transaction_result(|| {
 				ensure_none(origin)?;
-				Self::do_liquidity_pool_force_close(pool)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_liquidity_pool_force_close(pool))?;
 				Self::deposit_event(RawEvent::LiquidityPoolForceClosed(pool));
 				Ok(())
 			})?;
@@ -544,17 +622,15 @@ decl_module! {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
 
-				RiskThresholds::mutate(pair, |r| {
-					if trader.is_some() {
-						r.trader = trader;
-					}
-					if enp.is_some() {
-						r.enp = enp;
-					}
-					if ell.is_some() {
-						r.ell = ell;
-					}
-				});
+				if let Some(t) = trader {
+					Self::set_risk_threshold(pair, RiskThresholdType::Trader, t);
+				}
+				if let Some(t) = enp {
+					Self::set_risk_threshold(pair, RiskThresholdType::Enp, t);
+				}
+				if let Some(t) = ell {
+					Self::set_risk_threshold(pair, RiskThresholdType::Ell, t);
+				}
 
 				Self::deposit_event(RawEvent::TradingPairRiskThresholdSet(pair, trader, enp, ell));
 
@@ -562,8 +638,28 @@ decl_module! {
 			})?;
 		}
 
+		/// Set the price route of a trading pair, or remove it if `None`.
+		///
+		/// Legs of a route are priced by their own direct quote if any, or else from USD prices.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_price_route()]
+		pub fn set_price_route(origin, pair: TradingPair, route: Option<PriceRoute>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				Self::do_set_price_route(pair, route.clone())?;
+				Self::deposit_event(RawEvent::PriceRouteSet(pair, route));
+				Ok(())
+			})?;
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_risk_thresholds().saturating_add(Self::migrate_positions_snapshots())
+		}
+
 		fn offchain_worker(block_number: T::BlockNumber) {
-			if let Err(error) = Self::offchain_worker(block_number) {
+			let result = with_price_usage(PriceUsage::Liquidation, || Self::offchain_worker(block_number));
+			if let Err(error) = result {
 				match error {
 					OffchainErr::NotValidator | OffchainErr::OffchainLock => {
 						debug::native::info!(
@@ -589,16 +685,108 @@ decl_module! {
 
 // Storage getters
 impl<T: Config> Module<T> {
+	/// Risk thresholds of a trading pair, including trader risk threshold, pool ENP and ELL risk
+	/// threshold.
+	pub fn risk_thresholds(pair: TradingPair) -> TradingPairRiskThreshold {
+		TradingPairRiskThreshold::new(
+			Self::risk_threshold(pair, RiskThresholdType::Trader),
+			Self::risk_threshold(pair, RiskThresholdType::Enp),
+			Self::risk_threshold(pair, RiskThresholdType::Ell),
+		)
+	}
+
+	/// Risk threshold of `threshold_type` of a trading pair, `None` if not set.
+	pub fn risk_threshold(pair: TradingPair, threshold_type: RiskThresholdType) -> Option<RiskThreshold> {
+		Some(RiskThreshold {
+			margin_call: MarginCallThresholds::get(pair, threshold_type)?,
+			stop_out: StopOutThresholds::get(pair, threshold_type)?,
+		})
+	}
+
 	pub fn trader_risk_threshold(pair: TradingPair) -> Option<RiskThreshold> {
-		Self::risk_thresholds(pair).trader
+		Self::risk_threshold(pair, RiskThresholdType::Trader)
 	}
 
 	pub fn liquidity_pool_enp_threshold(pair: TradingPair) -> Option<RiskThreshold> {
-		Self::risk_thresholds(pair).enp
+		Self::risk_threshold(pair, RiskThresholdType::Enp)
 	}
 
 	pub fn liquidity_pool_ell_threshold(pair: TradingPair) -> Option<RiskThreshold> {
-		Self::risk_thresholds(pair).ell
+		Self::risk_threshold(pair, RiskThresholdType::Ell)
+	}
+
+	fn set_risk_threshold(pair: TradingPair, threshold_type: RiskThresholdType, threshold: RiskThreshold) {
+		MarginCallThresholds::insert(pair, threshold_type, threshold.margin_call);
+		StopOutThresholds::insert(pair, threshold_type, threshold.stop_out);
+	}
+
+	/// The price route of a trading pair. Defaults to a single leg of the pair itself, priced from
+	/// USD prices of its base and quote currency.
+	pub fn price_route(pair: TradingPair) -> PriceRoute {
+		Self::price_routes(pair).unwrap_or_else(|| PriceRoute::Legs(vec![pair]))
+	}
+}
+
+// Storage migrations
+impl<T: Config> Module<T> {
+	/// Split legacy `RiskThresholds` of each trading pair into `MarginCallThresholds` and
+	/// `StopOutThresholds`.
+	fn migrate_risk_thresholds() -> Weight {
+		if Self::storage_version() >= Releases::V2 {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let mut count = 0;
+		for (key, thresholds) in
+			StorageIterator::<TradingPairRiskThreshold>::new(b"MarginProtocol", b"RiskThresholds").drain()
+		{
+			// `twox_64_concat` hashed key: 8 bytes hash followed by the encoded trading pair
+			if let Some(pair) = key.get(8..).and_then(|mut k| TradingPair::decode(&mut k).ok()) {
+				let types = [
+					RiskThresholdType::Trader,
+					RiskThresholdType::Enp,
+					RiskThresholdType::Ell,
+				];
+				let values = [thresholds.trader, thresholds.enp, thresholds.ell];
+				for (threshold_type, threshold) in types.iter().zip(values.iter()) {
+					if let Some(threshold) = threshold {
+						Self::set_risk_threshold(pair, *threshold_type, *threshold);
+					}
+				}
+				count += 1;
+			}
+		}
+
+		StorageVersion::put(Releases::V2);
+
+		T::DbWeight::get().reads_writes(count + 2, count * 7 + 1)
+	}
+
+	/// Recompute `PositionsSnapshots` from `Positions`, as short amounts of snapshots stored before
+	/// `Releases::V3` were derived from long ones.
+	fn migrate_positions_snapshots() -> Weight {
+		if Self::storage_version() >= Releases::V3 {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let removed = PositionsSnapshots::drain().count() as Weight;
+		let mut count = 0;
+		for (_, position) in <Positions<T>>::iter() {
+			PositionsSnapshots::mutate(position.pool, position.pair, |snapshot| {
+				snapshot.positions_count += 1;
+				let amounts = if position.leverage.is_long() {
+					&mut snapshot.long
+				} else {
+					&mut snapshot.short
+				};
+				amounts.held = amounts.held.saturating_add(position.leveraged_held);
+				amounts.debits = amounts.debits.saturating_add(position.leveraged_debits);
+			});
+			count += 1;
+		}
+		StorageVersion::put(Releases::V3);
+
+		T::DbWeight::get().reads_writes(removed + count * 2 + 1, removed + count + 1)
 	}
 }
 
@@ -610,8 +798,8 @@ impl<T: Config> Module<T> {
 		pair: TradingPair,
 		leverage: Leverage,
 		leveraged_amount: Balance,
-		price: Price,
-	) -> DispatchResult {
+		price: Option<Price>,
+	) -> result::Result<PositionId, DispatchError> {
 		Self::ensure_can_open_more_position(who, pool_id, pair)?;
 		ensure!(
 			Self::margin_called_traders(who, pool_id).is_none(),
@@ -626,9 +814,9 @@ impl<T: Config> Module<T> {
 		let leveraged_held = fixed_i128_from_u128(leveraged_amount);
 		let debits_price = {
 			if leverage.is_long() {
-				Self::ask_price(pool_id, pair, Some(price))?
+				Self::ask_price(pool_id, pair, price)?
 			} else {
-				Self::bid_price(pool_id, pair, Some(price))?
+				Self::bid_price(pool_id, pair, price)?
 			}
 		};
 		let leveraged_debits = leveraged_held
@@ -678,7 +866,7 @@ impl<T: Config> Module<T> {
 			Price::from_inner(u128_from_fixed_i128(debits_price)),
 		));
 
-		Ok(())
+		Ok(id)
 	}
 
 	fn do_close_position(who: &T::AccountId, position_id: PositionId, price: Option<Price>) -> DispatchResult {
@@ -865,6 +1053,25 @@ impl<T: Config> Module<T> {
 		Ok(())
 	}
 
+	fn do_set_price_route(pair: TradingPair, route: Option<PriceRoute>) -> DispatchResult {
+		if let Some(PriceRoute::Legs(legs)) = &route {
+			let (first, last) = match (legs.first(), legs.last()) {
+				(Some(first), Some(last)) => (first, last),
+				_ => return Err(Error::<T>::InvalidPriceRoute.into()),
+			};
+			ensure!(
+				legs.len() <= MAX_PRICE_ROUTE_LEGS
+					&& first.base == pair.base
+					&& last.quote == pair.quote
+					&& legs.windows(2).all(|w| w[0].quote == w[1].base),
+				Error::<T>::InvalidPriceRoute
+			);
+		}
+
+		PriceRoutes::mutate_exists(pair, |r| *r = route);
+		Ok(())
+	}
+
 	fn do_liquidity_pool_force_close(pool: LiquidityPoolId) -> DispatchResult {
 		match Self::check_pool(pool, Action::None) {
 			Ok(Risk::StopOut) => {
@@ -913,12 +1120,12 @@ impl<T: Config> Module<T> {
 			} else {
 				snapshot.positions_count += 1;
 				snapshot.short.held = snapshot
-					.long
+					.short
 					.held
 					.checked_add(&position.leveraged_held)
 					.ok_or(Error::<T>::NumOutOfBound)?;
 				snapshot.short.debits = snapshot
-					.long
+					.short
 					.debits
 					.checked_add(&position.leveraged_debits)
 					.ok_or(Error::<T>::NumOutOfBound)?;
@@ -1012,14 +1219,52 @@ type DoubleFixedI128Result = result::Result<(FixedI128, FixedI128), DispatchErro
 
 // Price helpers
 impl<T: Config> Module<T> {
-	/// The price from oracle.
+	/// The price from oracle, or from risk price provider in risk checks. Would fail if any of
+	/// `base` and `quote` prices is stale or unreliable for current price usage.
 	fn price(base: CurrencyId, quote: CurrencyId) -> PriceResult {
-		T::PriceProvider::get_price(base, quote).ok_or_else(|| Error::<T>::NoPrice.into())
+		Self::ensure_price_usable(base)?;
+		Self::ensure_price_usable(quote)?;
+		let price = if is_risk_pricing() {
+			T::RiskPriceProvider::get_price(base, quote)
+		} else {
+			T::PriceProvider::get_price(base, quote)
+		};
+		price.ok_or_else(|| Error::<T>::NoPrice.into())
+	}
+
+	/// The price of `pair` through its price route. Every leg is checked for stale or unreliable
+	/// prices.
+	fn pair_price(pair: TradingPair) -> PriceResult {
+		match Self::price_route(pair) {
+			PriceRoute::Direct(key) => Self::price(key, CurrencyId::AUSD),
+			PriceRoute::Legs(legs) => legs.into_iter().try_fold(Price::one(), |acc, leg| {
+				let leg_price = match Self::price_routes(leg) {
+					Some(PriceRoute::Direct(key)) => Self::price(key, CurrencyId::AUSD)?,
+					_ => Self::price(leg.base, leg.quote)?,
+				};
+				acc.checked_mul(&leg_price)
+					.ok_or_else(|| Error::<T>::NumOutOfBound.into())
+			}),
+		}
+	}
+
+	/// Ensure the price of `currency_id` is neither stale nor unreliable for current price usage.
+	fn ensure_price_usable(currency_id: CurrencyId) -> DispatchResult {
+		let usage = price_usage();
+		ensure!(!T::PriceGuard::is_stale(currency_id, usage), Error::<T>::StalePrice);
+		ensure!(
+			!T::PriceGuard::is_unreliable(currency_id, usage),
+			Error::<T>::UnreliablePrice
+		);
+		Ok(())
 	}
 
 	/// ask_price = price + ask_spread
+	///
+	/// `price` is the price of `pair` through its price route. Pools quote spreads of the traded
+	/// pair, not of the legs of its route, so the spread of `pair` applies once to the routed price.
 	fn ask_price(pool: LiquidityPoolId, pair: TradingPair, max: Option<Price>) -> FixedI128Result {
-		let price = Self::price(pair.base, pair.quote)?;
+		let price = Self::pair_price(pair)?;
 		let spread = T::LiquidityPools::ask_spread(pool, pair).ok_or(Error::<T>::NoAskSpread)?;
 		let ask_price: Price = price.saturating_add(spread);
 
@@ -1033,8 +1278,10 @@ impl<T: Config> Module<T> {
 	}
 
 	/// bid_price = price - bid_spread
+	///
+	/// The spread of `pair` applies once to the routed price, as in `ask_price`.
 	fn bid_price(pool: LiquidityPoolId, pair: TradingPair, min: Option<Price>) -> FixedI128Result {
-		let price = Self::price(pair.base, pair.quote)?;
+		let price = Self::pair_price(pair)?;
 		let spread = T::LiquidityPools::bid_spread(pool, pair).ok_or(Error::<T>::NoBidSpread)?;
 		let bid_price = price.saturating_sub(spread);
 
@@ -1048,9 +1295,14 @@ impl<T: Config> Module<T> {
 	}
 
 	/// usd_value = amount * price
+	///
+	/// The price is through the price route of `currency_id/AUSD`.
 	fn usd_value(currency_id: CurrencyId, amount: FixedI128) -> FixedI128Result {
 		let price = {
-			let p = Self::price(currency_id, CurrencyId::AUSD)?;
+			let p = Self::pair_price(TradingPair {
+				base: currency_id,
+				quote: CurrencyId::AUSD,
+			})?;
 			fixed_i128_from_fixed_u128(p)
 		};
 		amount
@@ -1071,16 +1323,11 @@ impl<T: Config> Module<T> {
 
 	/// Returns `Ok((unrealized_pl, market_price))` of a given position. If `price`, market price
 	/// must fit this bound, else returns `None`.
-	fn unrealized_pl_and_market_price_of_position(
+	pub fn unrealized_pl_and_market_price_of_position(
 		position: &Position<T>,
 		price: Option<Price>,
 	) -> result::Result<(FixedI128, FixedI128), DispatchError> {
-		// open_price = abs(leveraged_debits / leveraged_held)
-		let open_price = position
-			.leveraged_debits
-			.checked_div(&position.leveraged_held)
-			.expect("ensured safe on open position")
-			.saturating_abs();
+		let open_price = position.open_price();
 		let curr_price = {
 			if position.leverage.is_long() {
 				Self::bid_price(position.pool, position.pair, price)?
@@ -1150,6 +1397,37 @@ impl<T: Config> Module<T> {
 			})
 	}
 
+	/// IDs of open positions of a given trader, in `pool_id` if specified, or else in all pools.
+	pub fn trader_position_ids(who: &T::AccountId, pool_id: Option<LiquidityPoolId>) -> Vec<PositionId> {
+		let mut ids: Vec<PositionId> = <PositionsByTrader<T>>::iter_prefix(who)
+			.filter(|((p, _), _)| pool_id.map_or(true, |pool_id| *p == pool_id))
+			.map(|((_, position_id), _)| position_id)
+			.collect();
+		ids.sort_unstable();
+		ids
+	}
+
+	/// Open a position as a dry run at current market price, and apply `f` on the opened position.
+	/// All storage changes are reverted afterwards.
+	///
+	/// Returns the result of `f`, or the error opening the position would fail with.
+	pub fn dry_run_open_position<R>(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		pair: TradingPair,
+		leverage: Leverage,
+		leveraged_amount: Balance,
+		f: impl FnOnce(&Position<T>) -> R,
+	) -> result::Result<R, DispatchError> {
+		with_transaction(|| {
+			let result = Self::do_open_position(who, pool_id, pair, leverage, leveraged_amount, None).map(|id| {
+				let position = Self::positions(id).expect("position just opened; qed");
+				f(&position)
+			});
+			TransactionOutcome::Rollback(result)
+		})
+	}
+
 	/// Sum of all margin held of a given trader in a pool.
 	pub fn margin_held(who: &T::AccountId, pool_id: LiquidityPoolId) -> FixedI128 {
 		<PositionsByTrader<T>>::iter_prefix(who)
@@ -1165,7 +1443,7 @@ impl<T: Config> Module<T> {
 	///
 	/// accumulated_swap_rate_of_position =
 	///   (current_accumulated - open_accumulated) * leveraged_held
-	fn accumulated_swap_rate_of_position(position: &Position<T>) -> FixedI128Result {
+	pub fn accumulated_swap_rate_of_position(position: &Position<T>) -> FixedI128Result {
 		let rate = T::LiquidityPools::accumulated_swap_rate(position.pool, position.pair, position.leverage.is_long())
 			.checked_sub(&position.open_accumulated_swap_rate)
 			.ok_or(Error::<T>::NumOutOfBound)?;
@@ -1243,7 +1521,7 @@ impl<T: Config> Module<T> {
 	///
 	/// Return `Ok(Risk)`, or `Err` if check fails.
 	fn check_trader(who: &T::AccountId, pool_id: LiquidityPoolId, action: Action<T>) -> Result<Risk, DispatchError> {
-		let margin_level = Self::margin_level(who, pool_id)?;
+		let margin_level = with_risk_prices(|| Self::margin_level(who, pool_id))?;
 
 		let new_pair_risk_threshold = match action {
 			Action::OpenPosition(p) => Self::trader_risk_threshold(p.pair).unwrap_or_default(),
@@ -1251,15 +1529,44 @@ impl<T: Config> Module<T> {
 		};
 
 		let trader_threshold = Self::risk_threshold_of_trader(who, pool_id);
-		let risk = if margin_level <= cmp::max(trader_threshold.stop_out, new_pair_risk_threshold.stop_out).into() {
-			Risk::StopOut
-		} else if margin_level <= cmp::max(trader_threshold.margin_call, new_pair_risk_threshold.margin_call).into() {
-			Risk::MarginCall
-		} else {
-			Risk::None
+		let threshold = RiskThreshold {
+			margin_call: cmp::max(trader_threshold.margin_call, new_pair_risk_threshold.margin_call),
+			stop_out: cmp::max(trader_threshold.stop_out, new_pair_risk_threshold.stop_out),
 		};
 
-		Ok(risk)
+		Ok(Risk::of(margin_level, threshold))
+	}
+
+	/// Risk of a trader in `pool_id` with liquidation prices, along with margin level and risk
+	/// threshold.
+	pub fn trader_risk(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+	) -> result::Result<(Risk, FixedI128, RiskThreshold), DispatchError> {
+		let margin_level = with_price_usage(PriceUsage::Liquidation, || {
+			with_risk_prices(|| Self::margin_level(who, pool_id))
+		})?;
+		let threshold = Self::risk_threshold_of_trader(who, pool_id);
+		Ok((Risk::of(margin_level, threshold), margin_level, threshold))
+	}
+
+	/// Traders at risk of margin call or stop out, with liquidation prices, along with margin level
+	/// and risk threshold.
+	///
+	/// Sorted by severity: stop out first, and then by margin level, the lowest first.
+	pub fn traders_at_risk() -> Vec<(T::AccountId, LiquidityPoolId, Risk, FixedI128, RiskThreshold)> {
+		let mut traders: Vec<_> = Self::get_traders()
+			.into_iter()
+			.filter_map(|(who, pool_id)| {
+				let (risk, margin_level, threshold) = Self::trader_risk(&who, pool_id).ok()?;
+				if risk == Risk::None {
+					return None;
+				}
+				Some((who, pool_id, risk, margin_level, threshold))
+			})
+			.collect();
+		traders.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.3.cmp(&b.3)));
+		traders
 	}
 }
 
@@ -1270,13 +1577,27 @@ enum Action<T: Config> {
 	OpenPosition(Position<T>),
 }
 
-#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
-enum Risk {
+/// Risk of a trader or a liquidity pool, ordered by severity.
+#[derive(Encode, Decode, Copy, Clone, RuntimeDebug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Risk {
 	None,
 	MarginCall,
 	StopOut,
 }
 
+impl Risk {
+	/// Risk of a margin level, ENP or ELL `level` against `threshold`.
+	fn of(level: FixedI128, threshold: RiskThreshold) -> Self {
+		if level <= threshold.stop_out.into() {
+			Risk::StopOut
+		} else if level <= threshold.margin_call.into() {
+			Risk::MarginCall
+		} else {
+			Risk::None
+		}
+	}
+}
+
 // Liquidity pool helpers
 impl<T: Config> Module<T> {
 	/// equity_of_pool = liquidity - all_unrealized_pl - all_accumulated_swap_rate
@@ -1399,18 +1720,60 @@ impl<T: Config> Module<T> {
 			_ => (RiskThreshold::default(), RiskThreshold::default()),
 		};
 		let (enp_threshold, ell_threshold) = Self::enp_and_ell_risk_threshold_of_pool(pool_id);
+		let enp_threshold = RiskThreshold {
+			margin_call: cmp::max(enp_threshold.margin_call, new_pair_enp_threshold.margin_call),
+			stop_out: cmp::max(enp_threshold.stop_out, new_pair_enp_threshold.stop_out),
+		};
+		let ell_threshold = RiskThreshold {
+			margin_call: cmp::max(ell_threshold.margin_call, new_pair_ell_threshold.margin_call),
+			stop_out: cmp::max(ell_threshold.stop_out, new_pair_ell_threshold.stop_out),
+		};
+
+		let (enp, ell) = with_risk_prices(|| Self::enp_and_ell_with_action(pool_id, action))?;
+		Ok(cmp::max(Risk::of(enp, enp_threshold), Risk::of(ell, ell_threshold)))
+	}
+
+	/// Risk of a liquidity pool with liquidation prices, along with ENP and ELL, and their risk
+	/// thresholds.
+	#[allow(clippy::type_complexity)]
+	pub fn pool_risk(
+		pool_id: LiquidityPoolId,
+	) -> result::Result<(Risk, (FixedI128, FixedI128), (RiskThreshold, RiskThreshold)), DispatchError> {
+		let (enp, ell) = with_price_usage(PriceUsage::Liquidation, || {
+			with_risk_prices(|| Self::enp_and_ell_with_action(pool_id, Action::None))
+		})?;
+		let (enp_threshold, ell_threshold) = Self::enp_and_ell_risk_threshold_of_pool(pool_id);
+		let risk = cmp::max(Risk::of(enp, enp_threshold), Risk::of(ell, ell_threshold));
+		Ok((risk, (enp, ell), (enp_threshold, ell_threshold)))
+	}
 
-		let (enp, ell) = Self::enp_and_ell_with_action(pool_id, action)?;
-		if enp <= cmp::max(enp_threshold.stop_out, new_pair_enp_threshold.stop_out).into()
-			|| ell <= cmp::max(ell_threshold.stop_out, new_pair_ell_threshold.stop_out).into()
-		{
-			return Ok(Risk::StopOut);
-		} else if enp <= cmp::max(enp_threshold.margin_call, new_pair_enp_threshold.margin_call).into()
-			|| ell <= cmp::max(ell_threshold.margin_call, new_pair_ell_threshold.margin_call).into()
-		{
-			return Ok(Risk::MarginCall);
-		}
-		Ok(Risk::None)
+	/// Liquidity pools at risk of margin call or force close, with liquidation prices, along with
+	/// ENP and ELL, and their risk thresholds.
+	///
+	/// Sorted by severity: stop out first, and then by the lower one of ENP and ELL, the lowest
+	/// first.
+	#[allow(clippy::type_complexity)]
+	pub fn pools_at_risk() -> Vec<(
+		LiquidityPoolId,
+		Risk,
+		(FixedI128, FixedI128),
+		(RiskThreshold, RiskThreshold),
+	)> {
+		let mut pools: Vec<_> = Self::get_pools()
+			.into_iter()
+			.filter_map(|pool_id| {
+				let (risk, enp_and_ell, thresholds) = Self::pool_risk(pool_id).ok()?;
+				if risk == Risk::None {
+					return None;
+				}
+				Some((pool_id, risk, enp_and_ell, thresholds))
+			})
+			.collect();
+		pools.sort_by(|a, b| {
+			let lower = |(enp, ell): (FixedI128, FixedI128)| cmp::min(enp, ell);
+			b.1.cmp(&a.1).then_with(|| lower(a.2).cmp(&lower(b.2)))
+		});
+		pools
 	}
 
 	/// Force closure position to liquidate liquidity pool based on opened positions.
@@ -1585,6 +1948,12 @@ impl<T: Config> MarginProtocolLiquidityPoolsManager for Module<T> {
 		}
 		Ok(())
 	}
+
+	fn net_exposure(pool_id: LiquidityPoolId, pair: TradingPair) -> FixedI128 {
+		let snapshot = Self::pool_positions_snapshots(pool_id, pair);
+		// short held amount is negative
+		snapshot.long.held.saturating_add(snapshot.short.held)
+	}
 }
 
 /// Error which may occur while executing the off-chain code.
@@ -1745,23 +2114,32 @@ impl<T: Config> Module<T> {
 		// drop `guard` and unlock implicitly at end of scope.
 	}
 
-	fn is_trader_margin_called(who: &T::AccountId, pool_id: LiquidityPoolId) -> bool {
+	pub fn is_trader_margin_called(who: &T::AccountId, pool_id: LiquidityPoolId) -> bool {
 		<MarginCalledTraders<T>>::contains_key(&who, pool_id)
 	}
 
-	fn is_pool_margin_called(pool_id: &LiquidityPoolId) -> bool {
+	pub fn is_pool_margin_called(pool_id: &LiquidityPoolId) -> bool {
 		MarginCalledPools::contains_key(pool_id)
 	}
 
+	/// If the trader reached stop out threshold in `pool_id`, with liquidation prices.
+	pub fn trader_reached_stop_out(who: &T::AccountId, pool_id: LiquidityPoolId) -> bool {
+		Self::should_stop_out_trader(who, pool_id).unwrap_or(false)
+	}
+
 	fn should_stop_out_trader(who: &T::AccountId, pool_id: LiquidityPoolId) -> Result<bool, OffchainErr> {
-		match Self::check_trader(who, pool_id, Action::None).map_err(|_| OffchainErr::CheckFail)? {
+		let risk = with_price_usage(PriceUsage::Liquidation, || {
+			Self::check_trader(who, pool_id, Action::None)
+		});
+		match risk.map_err(|_| OffchainErr::CheckFail)? {
 			Risk::StopOut => Ok(true),
 			_ => Ok(false),
 		}
 	}
 
 	fn should_liquidate_pool(pool_id: LiquidityPoolId) -> Result<bool, OffchainErr> {
-		match Self::check_pool(pool_id, Action::None).map_err(|_| OffchainErr::CheckFail)? {
+		let risk = with_price_usage(PriceUsage::Liquidation, || Self::check_pool(pool_id, Action::None));
+		match risk.map_err(|_| OffchainErr::CheckFail)? {
 			Risk::StopOut => Ok(true),
 			_ => Ok(false),
 		}
@@ -156,6 +156,57 @@ impl DataProvider<CurrencyId, Price> for MockPrices {
 	}
 }
 
+thread_local! {
+	static RISK_PRICES: RefCell<BTreeMap<CurrencyId, Price>> = RefCell::new(BTreeMap::new());
+}
+
+/// Prices for risk checks. Same as `MockPrices` if not set.
+pub struct MockRiskPrices;
+impl MockRiskPrices {
+	pub fn set_mock_price(currency_id: CurrencyId, price: Price) {
+		RISK_PRICES.with(|v| v.borrow_mut().insert(currency_id, price));
+	}
+}
+
+impl DataProvider<CurrencyId, Price> for MockRiskPrices {
+	fn get(key: &CurrencyId) -> Option<Price> {
+		RISK_PRICES
+			.with(|v| v.borrow().get(key).cloned())
+			.or_else(|| MockPrices::prices(*key))
+	}
+}
+
+thread_local! {
+	static STALE_PRICES: RefCell<Vec<(CurrencyId, PriceUsage)>> = RefCell::new(vec![]);
+	static UNRELIABLE_PRICES: RefCell<Vec<(CurrencyId, PriceUsage)>> = RefCell::new(vec![]);
+}
+
+pub struct MockPriceGuard;
+impl MockPriceGuard {
+	pub fn set_stale(currency_id: CurrencyId, usage: PriceUsage) {
+		STALE_PRICES.with(|v| v.borrow_mut().push((currency_id, usage)));
+	}
+
+	pub fn set_unreliable(currency_id: CurrencyId, usage: PriceUsage) {
+		UNRELIABLE_PRICES.with(|v| v.borrow_mut().push((currency_id, usage)));
+	}
+
+	pub fn reset() {
+		STALE_PRICES.with(|v| v.borrow_mut().clear());
+		UNRELIABLE_PRICES.with(|v| v.borrow_mut().clear());
+	}
+}
+
+impl PriceGuard for MockPriceGuard {
+	fn is_stale(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		STALE_PRICES.with(|v| v.borrow().contains(&(currency_id, usage)))
+	}
+
+	fn is_unreliable(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		UNRELIABLE_PRICES.with(|v| v.borrow().contains(&(currency_id, usage)))
+	}
+}
+
 thread_local! {
 	static SPREAD: RefCell<Price> = RefCell::new(Price::zero());
 	static ACC_SWAP_RATES: RefCell<BTreeMap<TradingPair, FixedI128>> = RefCell::new(BTreeMap::new());
@@ -274,6 +325,8 @@ impl Config for Runtime {
 	type LiquidityCurrency = LiquidityCurrency;
 	type LiquidityPools = MockLiquidityPools;
 	type PriceProvider = DefaultPriceProvider<CurrencyId, MockPrices>;
+	type RiskPriceProvider = DefaultPriceProvider<CurrencyId, MockRiskPrices>;
+	type PriceGuard = MockPriceGuard;
 	type GetTreasuryAccountId = GetTreasuryAccountId;
 	type GetTraderMaxOpenPositions = GetTraderMaxOpenPositions;
 	type GetPoolMaxOpenPositions = GetPoolMaxOpenPositions;
@@ -304,6 +357,16 @@ pub const EUR_JPY_PAIR: TradingPair = TradingPair {
 	quote: CurrencyId::FJPY,
 };
 
+pub const USD_EUR_PAIR: TradingPair = TradingPair {
+	base: CurrencyId::AUSD,
+	quote: CurrencyId::FEUR,
+};
+
+pub const USD_JPY_PAIR: TradingPair = TradingPair {
+	base: CurrencyId::AUSD,
+	quote: CurrencyId::FJPY,
+};
+
 /// Print status of a trader, only for unit tests debugging purpose.
 pub fn print_trader_summary(who: &AccountId, pool_id: LiquidityPoolId, name: Option<&'static str>) {
 	println!("------------------------------");
@@ -5,12 +5,18 @@
 use super::*;
 use mock::*;
 
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::{get_storage_value, put_storage_value},
+	traits::OnRuntimeUpgrade,
+	StorageHasher, Twox64Concat,
+};
 use primitives::Leverage;
 use sp_core::offchain::{
 	testing::{TestOffchainExt, TestTransactionPoolExt},
 	OffchainExt, TransactionPoolExt,
 };
+use sp_runtime::traits::BadOrigin;
 
 // `n` is a natural currency amount by cent, with 2 fractional digits precision
 fn fixedi128_saturating_from_integer_currency_cent(n: i128) -> FixedI128 {
@@ -171,6 +177,21 @@ fn margin_held_sums_all_margin_held() {
 	});
 }
 
+#[test]
+fn trader_position_ids_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		<PositionsByTrader<Runtime>>::insert(ALICE, (MOCK_POOL, 2), ());
+		<PositionsByTrader<Runtime>>::insert(ALICE, (MOCK_POOL_1, 1), ());
+		<PositionsByTrader<Runtime>>::insert(ALICE, (MOCK_POOL, 0), ());
+		<PositionsByTrader<Runtime>>::insert(BOB, (MOCK_POOL, 3), ());
+
+		assert_eq!(MarginProtocol::trader_position_ids(&ALICE, None), vec![0, 1, 2]);
+		assert_eq!(MarginProtocol::trader_position_ids(&ALICE, Some(MOCK_POOL)), vec![0, 2]);
+		assert_eq!(MarginProtocol::trader_position_ids(&ALICE, Some(MOCK_POOL_1)), vec![1]);
+		assert!(MarginProtocol::trader_position_ids(&BOB, Some(MOCK_POOL_1)).is_empty());
+	});
+}
+
 fn eur_usd_long_1() -> Position<Runtime> {
 	let open_rate =
 		FixedI128::saturating_from_integer(1).saturating_add(FixedI128::saturating_from_rational(36_87, 100_000_00));
@@ -837,6 +858,7 @@ fn trader_stop_out_should_work() {
 				MarginProtocol::margin_level(&ALICE, MOCK_POOL),
 				Ok(FixedI128::saturating_from_integer(1))
 			);
+			assert!(!MarginProtocol::trader_reached_stop_out(&ALICE, MOCK_POOL));
 
 			// trader_stop_out without trader_margin_call
 			MockPrices::set_mock_price(CurrencyId::FEUR, Some(Price::saturating_from_rational(3, 100)));
@@ -844,6 +866,8 @@ fn trader_stop_out_should_work() {
 				MarginProtocol::margin_level(&ALICE, MOCK_POOL),
 				Ok(FixedI128::saturating_from_rational(3, 100))
 			);
+			assert!(MarginProtocol::trader_reached_stop_out(&ALICE, MOCK_POOL));
+			assert!(!MarginProtocol::is_trader_margin_called(&ALICE, MOCK_POOL));
 
 			assert_ok!(MarginProtocol::trader_stop_out(Origin::none(), ALICE, MOCK_POOL));
 
@@ -852,6 +876,80 @@ fn trader_stop_out_should_work() {
 		});
 }
 
+#[test]
+fn trader_stop_out_uses_liquidation_price_policy() {
+	ExtBuilder::default()
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100))
+		.spread(Price::zero())
+		.accumulated_swap_rate(EUR_USD_PAIR, FixedI128::saturating_from_integer(1))
+		.price(CurrencyId::FEUR, (3, 100))
+		.build()
+		.execute_with(|| {
+			set_trader_risk_threshold(EUR_USD_PAIR, risk_threshold(5, 3));
+			<Balances<Runtime>>::insert(ALICE, MOCK_POOL, fixedi128_saturating_from_integer_currency_cent(100));
+			let position: Position<Runtime> = Position {
+				owner: ALICE,
+				pool: MOCK_POOL,
+				pair: EUR_USD_PAIR,
+				leverage: Leverage::LongTwo,
+				leveraged_held: fixedi128_saturating_from_integer_currency_cent(100),
+				leveraged_debits: fixedi128_saturating_from_integer_currency_cent(-100),
+				open_accumulated_swap_rate: FixedI128::saturating_from_integer(1),
+				margin_held: fixedi128_saturating_from_integer_currency_cent(100),
+			};
+			<Positions<Runtime>>::insert(0, position);
+			<PositionsByTrader<Runtime>>::insert(ALICE, (MOCK_POOL, 0), ());
+
+			MockPriceGuard::set_stale(CurrencyId::FEUR, PriceUsage::Liquidation);
+			assert_noop!(
+				MarginProtocol::trader_stop_out(Origin::none(), ALICE, MOCK_POOL),
+				Error::<Runtime>::StalePrice
+			);
+
+			// stale for trading only, not blocking stop out
+			MockPriceGuard::reset();
+			MockPriceGuard::set_stale(CurrencyId::FEUR, PriceUsage::Trading);
+			assert_ok!(MarginProtocol::trader_stop_out(Origin::none(), ALICE, MOCK_POOL));
+		});
+}
+
+#[test]
+fn trader_stop_out_uses_risk_prices() {
+	ExtBuilder::default()
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100))
+		.spread(Price::zero())
+		.accumulated_swap_rate(EUR_USD_PAIR, FixedI128::saturating_from_integer(1))
+		.price(CurrencyId::FEUR, (1, 1))
+		.build()
+		.execute_with(|| {
+			set_trader_risk_threshold(EUR_USD_PAIR, risk_threshold(5, 3));
+			<Balances<Runtime>>::insert(ALICE, MOCK_POOL, fixedi128_saturating_from_integer_currency_cent(100));
+			let position: Position<Runtime> = Position {
+				owner: ALICE,
+				pool: MOCK_POOL,
+				pair: EUR_USD_PAIR,
+				leverage: Leverage::LongTwo,
+				leveraged_held: fixedi128_saturating_from_integer_currency_cent(100),
+				leveraged_debits: fixedi128_saturating_from_integer_currency_cent(-100),
+				open_accumulated_swap_rate: FixedI128::saturating_from_integer(1),
+				margin_held: fixedi128_saturating_from_integer_currency_cent(100),
+			};
+			<Positions<Runtime>>::insert(0, position);
+			<PositionsByTrader<Runtime>>::insert(ALICE, (MOCK_POOL, 0), ());
+
+			// spot price spike doesn't stop out
+			MockRiskPrices::set_mock_price(CurrencyId::FEUR, Price::saturating_from_integer(1));
+			MockPrices::set_mock_price(CurrencyId::FEUR, Some(Price::saturating_from_rational(3, 100)));
+			assert_noop!(
+				MarginProtocol::trader_stop_out(Origin::none(), ALICE, MOCK_POOL),
+				Error::<Runtime>::NotReachedRiskThreshold
+			);
+
+			MockRiskPrices::set_mock_price(CurrencyId::FEUR, Price::saturating_from_rational(3, 100));
+			assert_ok!(MarginProtocol::trader_stop_out(Origin::none(), ALICE, MOCK_POOL));
+		});
+}
+
 #[test]
 fn trader_stop_out_close_bigger_loss_position() {
 	ExtBuilder::default()
@@ -913,6 +1011,65 @@ fn trader_stop_out_close_bigger_loss_position() {
 		});
 }
 
+#[test]
+fn traders_at_risk_works() {
+	ExtBuilder::default()
+		.spread(Price::zero())
+		.accumulated_swap_rate(EUR_USD_PAIR, FixedI128::saturating_from_integer(1))
+		.price(CurrencyId::FEUR, (1, 1))
+		.build()
+		.execute_with(|| {
+			set_trader_risk_threshold(EUR_USD_PAIR, risk_threshold(5, 3));
+			<Balances<Runtime>>::insert(ALICE, MOCK_POOL, fixedi128_saturating_from_integer_currency_cent(100));
+			<Balances<Runtime>>::insert(BOB, MOCK_POOL, fixedi128_saturating_from_integer_currency_cent(99));
+			for (id, who) in vec![ALICE, BOB].into_iter().enumerate() {
+				let position: Position<Runtime> = Position {
+					owner: who,
+					pool: MOCK_POOL,
+					pair: EUR_USD_PAIR,
+					leverage: Leverage::LongTwo,
+					leveraged_held: fixedi128_saturating_from_integer_currency_cent(100),
+					leveraged_debits: fixedi128_saturating_from_integer_currency_cent(-100),
+					open_accumulated_swap_rate: FixedI128::saturating_from_integer(1),
+					margin_held: fixedi128_saturating_from_integer_currency_cent(100),
+				};
+				<Positions<Runtime>>::insert(id as PositionId, position);
+				<PositionsByTrader<Runtime>>::insert(who, (MOCK_POOL, id as PositionId), ());
+			}
+			assert_eq!(MarginProtocol::traders_at_risk(), vec![]);
+
+			// alice margin level 4%, bob 3%
+			MockPrices::set_mock_price(CurrencyId::FEUR, Some(Price::saturating_from_rational(4, 100)));
+			assert_eq!(
+				MarginProtocol::trader_risk(&ALICE, MOCK_POOL),
+				Ok((
+					Risk::MarginCall,
+					FixedI128::saturating_from_rational(4, 100),
+					risk_threshold(5, 3)
+				))
+			);
+			assert_eq!(
+				MarginProtocol::traders_at_risk(),
+				vec![
+					(
+						BOB,
+						MOCK_POOL,
+						Risk::StopOut,
+						FixedI128::saturating_from_rational(3, 100),
+						risk_threshold(5, 3)
+					),
+					(
+						ALICE,
+						MOCK_POOL,
+						Risk::MarginCall,
+						FixedI128::saturating_from_rational(4, 100),
+						risk_threshold(5, 3)
+					),
+				]
+			);
+		});
+}
+
 #[test]
 fn liquidity_pool_margin_call_and_become_safe_work() {
 	ExtBuilder::default()
@@ -960,8 +1117,22 @@ fn liquidity_pool_margin_call_and_become_safe_work() {
 			);
 			assert_ok!(MarginProtocol::liquidity_pool_become_safe(Origin::none(), MOCK_POOL));
 
+			assert_eq!(MarginProtocol::pools_at_risk(), vec![]);
+
 			// ENP 100% == 100%, unsafe
 			set_enp_risk_threshold(EUR_USD_PAIR, risk_threshold(100, 0));
+			assert_eq!(
+				MarginProtocol::pools_at_risk(),
+				vec![(
+					MOCK_POOL,
+					Risk::MarginCall,
+					(
+						FixedI128::saturating_from_integer(1),
+						FixedI128::saturating_from_integer(1)
+					),
+					(risk_threshold(100, 0), risk_threshold(99, 0))
+				)]
+			);
 			assert_ok!(MarginProtocol::liquidity_pool_margin_call(Origin::none(), MOCK_POOL));
 			let event = TestEvent::margin_protocol(RawEvent::LiquidityPoolMarginCalled(MOCK_POOL));
 			assert!(System::events().iter().any(|record| record.event == event));
@@ -1093,6 +1264,98 @@ fn open_long_position_works() {
 		});
 }
 
+#[test]
+fn dry_run_open_position_works() {
+	ExtBuilder::default()
+		.price(CurrencyId::FJPY, (1, 107))
+		.price(CurrencyId::FEUR, (1409, 1070))
+		.accumulated_swap_rate(EUR_JPY_PAIR, FixedI128::saturating_from_integer(1))
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100_000_00))
+		.build()
+		.execute_with(|| {
+			<Balances<Runtime>>::insert(
+				ALICE,
+				MOCK_POOL,
+				fixedi128_saturating_from_integer_currency_cent(10_000_00),
+			);
+			let position = {
+				let mut p = eur_jpy_long();
+				p.leveraged_debits = FixedI128::from_inner(-14104090_000000000732500000);
+				p.margin_held = FixedI128::from_inner(6590_696261682242990228);
+				p
+			};
+			assert_eq!(
+				MarginProtocol::dry_run_open_position(
+					&ALICE,
+					MOCK_POOL,
+					EUR_JPY_PAIR,
+					Leverage::LongTwenty,
+					balance_saturating_from_integer_currency_cent(100_000_00),
+					|p| (p.clone(), MarginProtocol::margin_held(&ALICE, MOCK_POOL))
+				),
+				Ok((position.clone(), position.margin_held))
+			);
+
+			// storage changes reverted
+			assert_eq!(MarginProtocol::positions(0), None);
+			assert_eq!(MarginProtocol::next_position_id(), 0);
+			assert_eq!(MarginProtocol::margin_held(&ALICE, MOCK_POOL), FixedI128::zero());
+			assert!(System::events().is_empty());
+
+			assert_eq!(
+				MarginProtocol::dry_run_open_position(
+					&BOB,
+					MOCK_POOL,
+					EUR_JPY_PAIR,
+					Leverage::LongTwenty,
+					balance_saturating_from_integer_currency_cent(100_000_00),
+					|_| ()
+				),
+				Err(Error::<Runtime>::InsufficientFreeMargin.into())
+			);
+		});
+}
+
+#[test]
+fn open_position_with_arbitrary_leverage_works() {
+	ExtBuilder::default()
+		// USD/JPY = 107
+		.price(CurrencyId::FJPY, (1, 107))
+		// EUR/JPY = 140.9 => EUR/USD = 140.9/107
+		.price(CurrencyId::FEUR, (1409, 1070))
+		.accumulated_swap_rate(EUR_JPY_PAIR, FixedI128::saturating_from_integer(1))
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100_000_00))
+		.build()
+		.execute_with(|| {
+			<Balances<Runtime>>::insert(
+				ALICE,
+				MOCK_POOL,
+				fixedi128_saturating_from_integer_currency_cent(10_000_00),
+			);
+			let leverage = Leverage::long(25).unwrap();
+			assert_ok!(MarginProtocol::open_position(
+				Origin::signed(ALICE),
+				MOCK_POOL,
+				EUR_JPY_PAIR,
+				leverage,
+				balance_saturating_from_integer_currency_cent(100_000_00),
+				Price::saturating_from_integer(142)
+			));
+
+			let position = MarginProtocol::positions(0).unwrap();
+			assert_eq!(position.leverage, leverage);
+			// margin held of 25x is 20/25 of the one of 20x in `open_long_position_works`
+			let expected_margin_held = FixedI128::from_inner(6590_696261682242990228 * 20 / 25);
+			assert!(
+				position
+					.margin_held
+					.saturating_sub(expected_margin_held)
+					.saturating_abs()
+					<= FixedI128::from_inner(1)
+			);
+		});
+}
+
 #[test]
 fn open_short_position_works() {
 	ExtBuilder::default()
@@ -1125,10 +1388,72 @@ fn open_short_position_works() {
 				p.margin_held = FixedI128::from_inner(6686_702830188679240668);
 				p
 			};
-			assert_eq!(MarginProtocol::positions(0), Some(position));
+			assert_eq!(MarginProtocol::positions(0), Some(position.clone()));
+			assert_eq!(
+				MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_JPY_PAIR),
+				positions_snapshot(
+					1,
+					FixedI128::zero(),
+					FixedI128::zero(),
+					position.leveraged_held,
+					position.leveraged_debits,
+				)
+			);
+			assert_eq!(
+				<MarginProtocol as MarginProtocolLiquidityPoolsManager>::net_exposure(MOCK_POOL, EUR_JPY_PAIR),
+				position.leveraged_held
+			);
 		});
 }
 
+#[test]
+fn positions_snapshot_accumulates_long_and_short_separately() {
+	ExtBuilder::default().build().execute_with(|| {
+		let long = eur_usd_long_1();
+		let short = eur_usd_short_1();
+		assert_ok!(MarginProtocol::insert_position(
+			&ALICE,
+			MOCK_POOL,
+			EUR_USD_PAIR,
+			long.clone()
+		));
+		assert_ok!(MarginProtocol::insert_position(
+			&ALICE,
+			MOCK_POOL,
+			EUR_USD_PAIR,
+			short.clone()
+		));
+		assert_eq!(
+			MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_USD_PAIR),
+			positions_snapshot(
+				2,
+				long.leveraged_held,
+				long.leveraged_debits,
+				short.leveraged_held,
+				short.leveraged_debits,
+			)
+		);
+
+		assert_ok!(MarginProtocol::remove_position(&ALICE, 1, &short));
+		assert_eq!(
+			MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_USD_PAIR),
+			positions_snapshot(
+				1,
+				long.leveraged_held,
+				long.leveraged_debits,
+				FixedI128::zero(),
+				FixedI128::zero(),
+			)
+		);
+
+		assert_ok!(MarginProtocol::remove_position(&ALICE, 0, &long));
+		assert_eq!(
+			MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_USD_PAIR),
+			PositionsSnapshot::default()
+		);
+	});
+}
+
 #[test]
 fn open_position_fails_if_trader_margin_called() {
 	ExtBuilder::default()
@@ -1218,6 +1543,64 @@ fn open_position_fails_if_no_base_price() {
 		});
 }
 
+#[test]
+fn open_position_fails_if_stale_price() {
+	ExtBuilder::default()
+		.price(CurrencyId::FJPY, (1, 107))
+		.price(CurrencyId::FEUR, (1409, 1070))
+		.accumulated_swap_rate(EUR_JPY_PAIR, FixedI128::saturating_from_integer(1))
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100_000_00))
+		.build()
+		.execute_with(|| {
+			<Balances<Runtime>>::insert(
+				ALICE,
+				MOCK_POOL,
+				fixedi128_saturating_from_integer_currency_cent(10_000_00),
+			);
+			MockPriceGuard::set_stale(CurrencyId::FJPY, PriceUsage::Trading);
+			assert_noop!(
+				MarginProtocol::open_position(
+					Origin::signed(ALICE),
+					MOCK_POOL,
+					EUR_JPY_PAIR,
+					Leverage::LongTwenty,
+					balance_saturating_from_integer_currency_cent(100_000_00),
+					Price::saturating_from_integer(142)
+				),
+				Error::<Runtime>::StalePrice
+			);
+		});
+}
+
+#[test]
+fn open_position_fails_if_unreliable_price() {
+	ExtBuilder::default()
+		.price(CurrencyId::FJPY, (1, 107))
+		.price(CurrencyId::FEUR, (1409, 1070))
+		.accumulated_swap_rate(EUR_JPY_PAIR, FixedI128::saturating_from_integer(1))
+		.pool_liquidity(MOCK_POOL, balance_saturating_from_integer_currency_cent(100_000_00))
+		.build()
+		.execute_with(|| {
+			<Balances<Runtime>>::insert(
+				ALICE,
+				MOCK_POOL,
+				fixedi128_saturating_from_integer_currency_cent(10_000_00),
+			);
+			MockPriceGuard::set_unreliable(CurrencyId::FEUR, PriceUsage::Trading);
+			assert_noop!(
+				MarginProtocol::open_position(
+					Origin::signed(ALICE),
+					MOCK_POOL,
+					EUR_JPY_PAIR,
+					Leverage::LongTwenty,
+					balance_saturating_from_integer_currency_cent(100_000_00),
+					Price::saturating_from_integer(142)
+				),
+				Error::<Runtime>::UnreliablePrice
+			);
+		});
+}
+
 #[test]
 fn open_position_fails_if_no_quote_price() {
 	ExtBuilder::default()
@@ -2516,6 +2899,158 @@ fn set_trading_pair_risk_threshold_works() {
 		});
 }
 
+#[test]
+fn set_price_route_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		let route = PriceRoute::Legs(vec![EUR_USD_PAIR, USD_JPY_PAIR]);
+		assert_eq!(
+			MarginProtocol::price_route(EUR_JPY_PAIR),
+			PriceRoute::Legs(vec![EUR_JPY_PAIR])
+		);
+
+		assert_noop!(
+			MarginProtocol::set_price_route(Origin::signed(BOB), EUR_JPY_PAIR, Some(route.clone())),
+			BadOrigin
+		);
+		assert_noop!(
+			MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Legs(vec![]))
+			),
+			Error::<Runtime>::InvalidPriceRoute
+		);
+		assert_noop!(
+			MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Legs(vec![EUR_USD_PAIR, JPY_USD_PAIR]))
+			),
+			Error::<Runtime>::InvalidPriceRoute
+		);
+		assert_noop!(
+			MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Legs(vec![
+					EUR_USD_PAIR,
+					USD_EUR_PAIR,
+					EUR_USD_PAIR,
+					USD_EUR_PAIR,
+					EUR_USD_PAIR,
+					USD_JPY_PAIR
+				]))
+			),
+			Error::<Runtime>::InvalidPriceRoute
+		);
+
+		assert_ok!(MarginProtocol::set_price_route(
+			Origin::signed(UpdateOrigin::get()),
+			EUR_JPY_PAIR,
+			Some(route.clone())
+		));
+		assert_eq!(MarginProtocol::price_route(EUR_JPY_PAIR), route.clone());
+		let event = TestEvent::margin_protocol(RawEvent::PriceRouteSet(EUR_JPY_PAIR, Some(route)));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(MarginProtocol::set_price_route(
+			Origin::signed(UpdateOrigin::get()),
+			EUR_JPY_PAIR,
+			None
+		));
+		assert_eq!(MarginProtocol::price_routes(EUR_JPY_PAIR), None);
+	});
+}
+
+#[test]
+fn pair_price_follows_price_route() {
+	ExtBuilder::default()
+		// USD/JPY = 107
+		.price(CurrencyId::FJPY, (1, 107))
+		.price(CurrencyId::FEUR, (1409, 1070))
+		// direct quotes: USD/JPY = 108, EUR/JPY = 141
+		.price(CurrencyId::FAUD, (108, 1))
+		.price(CurrencyId::FCAD, (141, 1))
+		.build()
+		.execute_with(|| {
+			let eur_jpy = Price::saturating_from_rational(1409, 1070)
+				.checked_div(&Price::saturating_from_rational(1, 107))
+				.unwrap();
+			assert_eq!(MarginProtocol::pair_price(EUR_JPY_PAIR), Ok(eur_jpy));
+
+			// cross through USD, with the USD/JPY leg quoted directly
+			assert_ok!(MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				USD_JPY_PAIR,
+				Some(PriceRoute::Direct(CurrencyId::FAUD))
+			));
+			assert_ok!(MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Legs(vec![EUR_USD_PAIR, USD_JPY_PAIR]))
+			));
+			assert_eq!(
+				MarginProtocol::pair_price(EUR_JPY_PAIR),
+				Ok(Price::saturating_from_rational(1409, 1070).saturating_mul(Price::saturating_from_integer(108)))
+			);
+
+			MockPriceGuard::set_stale(CurrencyId::FAUD, PriceUsage::Trading);
+			assert_eq!(
+				MarginProtocol::pair_price(EUR_JPY_PAIR),
+				Err(Error::<Runtime>::StalePrice.into())
+			);
+			MockPriceGuard::reset();
+
+			assert_ok!(MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Direct(CurrencyId::FCAD))
+			));
+			assert_eq!(
+				MarginProtocol::pair_price(EUR_JPY_PAIR),
+				Ok(Price::saturating_from_integer(141))
+			);
+		});
+}
+
+#[test]
+fn routed_pair_price_applies_spread_once() {
+	ExtBuilder::default()
+		.spread(Price::saturating_from_rational(1, 100))
+		// USD/JPY = 107
+		.price(CurrencyId::FJPY, (1, 107))
+		.price(CurrencyId::FEUR, (1409, 1070))
+		// direct quote: USD/JPY = 108
+		.price(CurrencyId::FAUD, (108, 1))
+		.build()
+		.execute_with(|| {
+			assert_ok!(MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				USD_JPY_PAIR,
+				Some(PriceRoute::Direct(CurrencyId::FAUD))
+			));
+			assert_ok!(MarginProtocol::set_price_route(
+				Origin::signed(UpdateOrigin::get()),
+				EUR_JPY_PAIR,
+				Some(PriceRoute::Legs(vec![EUR_USD_PAIR, USD_JPY_PAIR]))
+			));
+			let price = Price::saturating_from_rational(1409, 1070).saturating_mul(Price::saturating_from_integer(108));
+			assert_eq!(MarginProtocol::pair_price(EUR_JPY_PAIR), Ok(price));
+
+			let ask_spread = MockLiquidityPools::ask_spread(MOCK_POOL, EUR_JPY_PAIR).unwrap();
+			let bid_spread = MockLiquidityPools::bid_spread(MOCK_POOL, EUR_JPY_PAIR).unwrap();
+			assert!(!ask_spread.is_zero());
+			assert_eq!(
+				MarginProtocol::ask_price(MOCK_POOL, EUR_JPY_PAIR, None),
+				Ok(fixed_i128_from_fixed_u128(price.saturating_add(ask_spread)))
+			);
+			assert_eq!(
+				MarginProtocol::bid_price(MOCK_POOL, EUR_JPY_PAIR, None),
+				Ok(fixed_i128_from_fixed_u128(price.saturating_sub(bid_spread)))
+			);
+		});
+}
+
 #[test]
 fn ensure_can_enable_trading_pair_works() {
 	ExtBuilder::default()
@@ -2698,3 +3233,97 @@ fn risk_threshold_of_trader_is_per_pool() {
 			);
 		});
 }
+
+#[test]
+fn migrate_risk_thresholds_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		StorageVersion::put(Releases::V1);
+		let thresholds = TradingPairRiskThreshold::new(Some(risk_threshold(5, 3)), None, Some(risk_threshold(40, 20)));
+		let key = Twox64Concat::hash(&JPY_USD_PAIR.encode());
+		put_storage_value(b"MarginProtocol", b"RiskThresholds", &key, thresholds);
+
+		MarginProtocol::on_runtime_upgrade();
+
+		assert_eq!(MarginProtocol::risk_thresholds(JPY_USD_PAIR), thresholds);
+		assert_eq!(MarginCallThresholds::get(JPY_USD_PAIR, RiskThresholdType::Enp), None);
+		assert_eq!(
+			get_storage_value::<TradingPairRiskThreshold>(b"MarginProtocol", b"RiskThresholds", &key),
+			None
+		);
+		assert_eq!(MarginProtocol::storage_version(), Releases::V3);
+	});
+}
+
+#[test]
+fn migrate_positions_snapshots_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		let long = eur_usd_long_1();
+		let short = eur_usd_short_1();
+		assert_ok!(MarginProtocol::insert_position(
+			&ALICE,
+			MOCK_POOL,
+			EUR_USD_PAIR,
+			long.clone()
+		));
+		assert_ok!(MarginProtocol::insert_position(
+			&ALICE,
+			MOCK_POOL,
+			EUR_USD_PAIR,
+			short.clone()
+		));
+		let expected = MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_USD_PAIR);
+
+		// legacy snapshots: short amounts derived from long ones, and a stale one without positions
+		StorageVersion::put(Releases::V2);
+		PositionsSnapshots::insert(
+			MOCK_POOL,
+			EUR_USD_PAIR,
+			positions_snapshot(
+				2,
+				long.leveraged_held,
+				long.leveraged_debits,
+				long.leveraged_held.saturating_add(short.leveraged_held),
+				long.leveraged_debits.saturating_add(short.leveraged_debits),
+			),
+		);
+		PositionsSnapshots::insert(
+			MOCK_POOL,
+			JPY_USD_PAIR,
+			positions_snapshot(
+				1,
+				FixedI128::saturating_from_integer(1),
+				FixedI128::saturating_from_integer(-1),
+				FixedI128::zero(),
+				FixedI128::zero(),
+			),
+		);
+
+		MarginProtocol::on_runtime_upgrade();
+
+		assert_eq!(
+			MarginProtocol::pool_positions_snapshots(MOCK_POOL, EUR_USD_PAIR),
+			expected
+		);
+		assert_eq!(
+			MarginProtocol::pool_positions_snapshots(MOCK_POOL, JPY_USD_PAIR),
+			PositionsSnapshot::default()
+		);
+		assert_eq!(MarginProtocol::storage_version(), Releases::V3);
+	});
+}
+
+#[test]
+fn migrate_risk_thresholds_skipped_if_migrated() {
+	ExtBuilder::default().build().execute_with(|| {
+		let thresholds = TradingPairRiskThreshold::new(Some(risk_threshold(5, 3)), None, None);
+		let key = Twox64Concat::hash(&JPY_USD_PAIR.encode());
+		put_storage_value(b"MarginProtocol", b"RiskThresholds", &key, thresholds);
+
+		MarginProtocol::on_runtime_upgrade();
+
+		assert_eq!(
+			get_storage_value::<TradingPairRiskThreshold>(b"MarginProtocol", b"RiskThresholds", &key),
+			Some(thresholds)
+		);
+	});
+}
@@ -0,0 +1,52 @@
+//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 2.0.0
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(clippy::unnecessary_cast)]
+
+use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
+
+impl crate::WeightInfo for () {
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_max_price_age() -> Weight {
+		(65_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_liquidation_max_price_age() -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_max_divergence() -> Weight {
+		(65_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_liquidation_fallback() -> Weight {
+		(65_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_circuit_breaker() -> Weight {
+		(75_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn reset_circuit_breaker() -> Weight {
+		(90_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+}
@@ -0,0 +1,527 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_event, decl_module, decl_storage,
+	traits::{EnsureOrigin, Get, UnixTime},
+	weights::Weight,
+};
+use laminar_primitives::{CurrencyId, Moment, Price};
+use module_traits::{PriceGuard, PriceUsage};
+use orml_oracle::TimestampedValue;
+use orml_traits::{DataProviderExtended, OnNewData};
+use orml_utilities::with_transaction_result;
+use sp_runtime::{
+	traits::{CheckedDiv, Convert, SaturatedConversion, Saturating, Zero},
+	FixedPointNumber, Permill, RuntimeDebug,
+};
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+mod default_weight;
+mod mock;
+mod tests;
+
+pub trait WeightInfo {
+	fn set_max_price_age() -> Weight;
+	fn set_liquidation_max_price_age() -> Weight;
+	fn set_max_divergence() -> Weight;
+	fn set_liquidation_fallback() -> Weight;
+	fn set_circuit_breaker() -> Weight;
+	fn reset_circuit_breaker() -> Weight;
+}
+
+pub type TimestampedPrice = TimestampedValue<Price, Moment>;
+
+/// Prices, price policies and circuit breakers are keyed by oracle keys, as new oracle data is.
+/// `PriceGuard` is queried with currency IDs, which are converted to oracle keys by `OracleKey`.
+pub trait Config: frame_system::Config {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Config>::Event>;
+
+	/// Converts a currency ID to the key to query its price from oracle.
+	type OracleKey: Convert<CurrencyId, CurrencyId>;
+
+	/// The oracle prices, with timestamps of when they were fed.
+	type Source: DataProviderExtended<CurrencyId, TimestampedPrice>;
+
+	/// Prices of the Laminar oracle provider, cross-checked with `BandSource`.
+	type LaminarSource: DataProviderExtended<CurrencyId, TimestampedPrice>;
+
+	/// Prices of the Band oracle provider, cross-checked with `LaminarSource`.
+	type BandSource: DataProviderExtended<CurrencyId, TimestampedPrice>;
+
+	/// Time used to compute price ages. Should be in the same unit as oracle timestamps.
+	type UnixTime: UnixTime;
+
+	/// Time window in milliseconds of time-weighted average prices.
+	type TwapWindow: Get<Moment>;
+
+	/// Max number of price observations kept for each currency to compute time-weighted average
+	/// prices.
+	type MaxTwapObservations: Get<u32>;
+
+	/// Required origin for updating price policies.
+	type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Weight information for the extrinsics in this module.
+	type WeightInfo: WeightInfo;
+}
+
+/// What margin calls, stop outs and liquidations do if oracle providers diverge.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq)]
+pub enum LiquidationFallback {
+	/// Pause them until providers converge.
+	Pause,
+	/// Continue with the aggregated price.
+	Continue,
+}
+
+impl Default for LiquidationFallback {
+	fn default() -> Self {
+		LiquidationFallback::Pause
+	}
+}
+
+/// Circuit breaker parameters.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq)]
+pub struct CircuitBreakerParams {
+	/// Max relative move of price from the last accepted one within `window`.
+	pub band: Permill,
+
+	/// Time window in milliseconds. Also how long prices need to stay in band to stabilize after
+	/// a trip.
+	pub window: Moment,
+}
+
+/// A price observation for time-weighted average prices.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq)]
+pub struct TwapObservation {
+	/// When the price was observed.
+	pub timestamp: Moment,
+
+	/// The price observed, in effect until the next observation.
+	pub price: Price,
+
+	/// Sum of `price * elapsed_milliseconds` of previous observations.
+	pub price_cumulative: Price,
+}
+
+decl_storage! {
+	trait Store for Module<T: Config> as Prices {
+		/// Max price age in milliseconds for each currency, for trading.
+		///
+		/// DEFAULT-NOTE: `None`, no max age.
+		pub MaxPriceAges get(fn max_price_age): map hasher(twox_64_concat) CurrencyId => Option<Moment>;
+
+		/// Max price age in milliseconds for each currency, for margin calls, stop outs and liquidations.
+		///
+		/// Liquidations have their own policy, so that they would not be blocked by, or be less strict
+		/// than, the trading one.
+		///
+		/// DEFAULT-NOTE: `None`, no max age.
+		pub LiquidationMaxPriceAges get(fn liquidation_max_price_age): map hasher(twox_64_concat) CurrencyId => Option<Moment>;
+
+		/// Max relative deviation between oracle providers for each currency. The aggregated price
+		/// is unreliable if exceeded.
+		///
+		/// DEFAULT-NOTE: `None`, no cross-check.
+		pub MaxDivergences get(fn max_divergence): map hasher(twox_64_concat) CurrencyId => Option<Permill>;
+
+		/// What liquidations do with unreliable prices for each currency.
+		pub LiquidationFallbacks get(fn liquidation_fallback): map hasher(twox_64_concat) CurrencyId => LiquidationFallback;
+
+		/// Currencies of which oracle providers diverged, as of the last new oracle data.
+		pub Diverged get(fn diverged): map hasher(twox_64_concat) CurrencyId => bool;
+
+		/// Circuit breaker parameters for each currency.
+		///
+		/// DEFAULT-NOTE: `None`, no circuit breaker.
+		pub CircuitBreakers get(fn circuit_breaker): map hasher(twox_64_concat) CurrencyId => Option<CircuitBreakerParams>;
+
+		/// The last price accepted by circuit breaker for each currency, and when it was accepted.
+		///
+		/// New prices are checked against it, and only accepted once it is older than window.
+		pub LastAcceptedPrices get(fn last_accepted_price): map hasher(twox_64_concat) CurrencyId => Option<TimestampedPrice>;
+
+		/// Tripped circuit breakers for each currency: the price new prices need to stay in band of
+		/// to stabilize, and since when.
+		pub TrippedPrices get(fn tripped_price): map hasher(twox_64_concat) CurrencyId => Option<TimestampedPrice>;
+
+		/// Price observations for each currency, oldest first. Observations older than the TWAP
+		/// window are pruned, except the last one of them.
+		pub TwapObservations get(fn twap_observations): map hasher(twox_64_concat) CurrencyId => Vec<TwapObservation>;
+	}
+}
+
+decl_event! {
+	pub enum Event {
+		/// Max price age updated: \[currency_id, max_age\]
+		MaxPriceAgeUpdated(CurrencyId, Option<Moment>),
+
+		/// Liquidation max price age updated: \[currency_id, max_age\]
+		LiquidationMaxPriceAgeUpdated(CurrencyId, Option<Moment>),
+
+		/// Max divergence updated: \[currency_id, max_divergence\]
+		MaxDivergenceUpdated(CurrencyId, Option<Permill>),
+
+		/// Liquidation fallback updated: \[currency_id, fallback\]
+		LiquidationFallbackUpdated(CurrencyId, LiquidationFallback),
+
+		/// Oracle providers diverged beyond max divergence: \[currency_id, divergence\]
+		PriceDiverged(CurrencyId, Price),
+
+		/// Oracle providers converged within max divergence: \[currency_id\]
+		PriceConverged(CurrencyId),
+
+		/// Circuit breaker updated: \[currency_id, params\]
+		CircuitBreakerUpdated(CurrencyId, Option<CircuitBreakerParams>),
+
+		/// Circuit breaker tripped: \[currency_id, last_accepted_price, new_price\]
+		CircuitBreakerTripped(CurrencyId, Price, Price),
+
+		/// Circuit breaker reset, by stabilized prices or governance: \[currency_id\]
+		CircuitBreakerReset(CurrencyId),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Config> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		/// Set max price age of `currency_id` for trading. `None` for no max age.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_max_price_age()]
+		pub fn set_max_price_age(origin, currency_id: CurrencyId, max_age: Option<Moment>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				MaxPriceAges::mutate(currency_id, |a| *a = max_age);
+				Self::deposit_event(Event::MaxPriceAgeUpdated(currency_id, max_age));
+				Ok(())
+			})?;
+		}
+
+		/// Set max price age of `currency_id` for liquidations. `None` for no max age.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_liquidation_max_price_age()]
+		pub fn set_liquidation_max_price_age(origin, currency_id: CurrencyId, max_age: Option<Moment>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				LiquidationMaxPriceAges::mutate(currency_id, |a| *a = max_age);
+				Self::deposit_event(Event::LiquidationMaxPriceAgeUpdated(currency_id, max_age));
+				Ok(())
+			})?;
+		}
+
+		/// Set max relative deviation between oracle providers of `currency_id`. `None` for no
+		/// cross-check.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_max_divergence()]
+		pub fn set_max_divergence(origin, currency_id: CurrencyId, max_divergence: Option<Permill>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				MaxDivergences::mutate(currency_id, |d| *d = max_divergence);
+				Self::deposit_event(Event::MaxDivergenceUpdated(currency_id, max_divergence));
+				Ok(())
+			})?;
+		}
+
+		/// Set what liquidations of `currency_id` do if oracle providers diverge.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_liquidation_fallback()]
+		pub fn set_liquidation_fallback(origin, currency_id: CurrencyId, fallback: LiquidationFallback) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				LiquidationFallbacks::insert(currency_id, fallback);
+				Self::deposit_event(Event::LiquidationFallbackUpdated(currency_id, fallback));
+				Ok(())
+			})?;
+		}
+
+		/// Set circuit breaker parameters of `currency_id`. `None` to remove the circuit breaker.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_circuit_breaker()]
+		pub fn set_circuit_breaker(origin, currency_id: CurrencyId, params: Option<CircuitBreakerParams>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				CircuitBreakers::mutate(currency_id, |p| *p = params);
+				if params.is_none() {
+					LastAcceptedPrices::remove(currency_id);
+					TrippedPrices::remove(currency_id);
+				}
+				Self::deposit_event(Event::CircuitBreakerUpdated(currency_id, params));
+				Ok(())
+			})?;
+		}
+
+		/// Reset circuit breaker of `currency_id`, accepting current price.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::reset_circuit_breaker()]
+		pub fn reset_circuit_breaker(origin, currency_id: CurrencyId) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				Self::do_reset_circuit_breaker(currency_id);
+				Ok(())
+			})?;
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	/// Age of the price of `currency_id` in milliseconds, `None` if no price.
+	pub fn price_age(currency_id: CurrencyId) -> Option<Moment> {
+		let price = T::Source::get_no_op(&currency_id)?;
+		Some(Self::now().saturating_sub(price.timestamp))
+	}
+
+	/// Relative deviation between oracle providers of `currency_id`, `None` if any of them has no
+	/// price.
+	///
+	/// divergence = abs(laminar_price - band_price) / min(laminar_price, band_price)
+	pub fn divergence(currency_id: CurrencyId) -> Option<Price> {
+		let laminar_price = T::LaminarSource::get_no_op(&currency_id)?.value;
+		let band_price = T::BandSource::get_no_op(&currency_id)?.value;
+		let (low, high) = if laminar_price < band_price {
+			(laminar_price, band_price)
+		} else {
+			(band_price, laminar_price)
+		};
+		if low.is_zero() {
+			return None;
+		}
+		high.saturating_sub(low).checked_div(&low)
+	}
+
+	/// Returns `true` if oracle providers of `currency_id` diverged beyond max divergence.
+	pub fn is_diverged(currency_id: CurrencyId) -> bool {
+		match (Self::max_divergence(currency_id), Self::divergence(currency_id)) {
+			(Some(max_divergence), Some(divergence)) => divergence > Price::from(max_divergence),
+			_ => false,
+		}
+	}
+
+	/// Returns `true` if circuit breaker of `currency_id` is tripped.
+	pub fn is_tripped(currency_id: CurrencyId) -> bool {
+		TrippedPrices::contains_key(currency_id)
+	}
+
+	/// Time-weighted average price of `currency_id` in TWAP window, or since the first
+	/// observation if it's later than window start. `None` if no observations.
+	///
+	/// twap = (price_cumulative_now - price_cumulative_window_start) / elapsed
+	pub fn twap(currency_id: CurrencyId) -> Option<Price> {
+		let observations = Self::twap_observations(currency_id);
+		let first = observations.first()?;
+		let last = observations.last()?;
+
+		let now = Self::now();
+		let window_start = now.saturating_sub(T::TwapWindow::get());
+		let (start, price_cumulative_start) = match observations.iter().rev().find(|o| o.timestamp <= window_start) {
+			Some(o) => (window_start, Self::price_cumulative_at(o, window_start)),
+			None => (first.timestamp, first.price_cumulative),
+		};
+
+		let elapsed = now.saturating_sub(start);
+		if elapsed.is_zero() {
+			return Some(last.price);
+		}
+		Self::price_cumulative_at(last, now)
+			.saturating_sub(price_cumulative_start)
+			.checked_div(&Price::saturating_from_integer(elapsed))
+	}
+}
+
+// Private methods
+impl<T: Config> Module<T> {
+	fn now() -> Moment {
+		T::UnixTime::now().as_millis().saturated_into()
+	}
+
+	/// Returns `true` if `price` is within `band` of `reference`.
+	///
+	/// abs(price - reference) / reference <= band
+	fn is_in_band(reference: Price, price: Price, band: Permill) -> bool {
+		let diff = if price > reference {
+			price.saturating_sub(reference)
+		} else {
+			reference.saturating_sub(price)
+		};
+		diff.checked_div(&reference)
+			.map_or(false, |deviation| deviation <= Price::from(band))
+	}
+
+	fn accept_price(currency_id: CurrencyId, price: Price) {
+		LastAcceptedPrices::insert(
+			currency_id,
+			TimestampedValue {
+				value: price,
+				timestamp: Self::now(),
+			},
+		);
+	}
+
+	fn do_reset_circuit_breaker(currency_id: CurrencyId) {
+		match T::Source::get_no_op(&currency_id) {
+			Some(price) => Self::accept_price(currency_id, price.value),
+			None => LastAcceptedPrices::remove(currency_id),
+		}
+		TrippedPrices::remove(currency_id);
+		Self::deposit_event(Event::CircuitBreakerReset(currency_id));
+	}
+
+	/// price_cumulative_at = observation.price_cumulative + observation.price * (timestamp -
+	/// observation.timestamp)
+	fn price_cumulative_at(observation: &TwapObservation, timestamp: Moment) -> Price {
+		let elapsed = timestamp.saturating_sub(observation.timestamp);
+		observation.price_cumulative.saturating_add(
+			observation
+				.price
+				.saturating_mul(Price::saturating_from_integer(elapsed)),
+		)
+	}
+
+	/// Record new aggregated price of `currency_id` for time-weighted average prices.
+	fn update_twap(currency_id: CurrencyId) {
+		let price = match T::Source::get_no_op(&currency_id) {
+			Some(price) => price.value,
+			None => return,
+		};
+		let now = Self::now();
+
+		TwapObservations::mutate(currency_id, |observations| {
+			let price_cumulative = observations
+				.last()
+				.map_or_else(Zero::zero, |last| Self::price_cumulative_at(last, now));
+			// only the last price of the same time is in effect
+			if observations.last().map_or(false, |last| last.timestamp == now) {
+				observations.pop();
+			}
+			observations.push(TwapObservation {
+				timestamp: now,
+				price,
+				price_cumulative,
+			});
+
+			let window_start = now.saturating_sub(T::TwapWindow::get());
+			let keep_from = observations
+				.iter()
+				.rposition(|o| o.timestamp <= window_start)
+				.unwrap_or_default();
+			let max_len = T::MaxTwapObservations::get().max(1) as usize;
+			let keep_from = keep_from.max(observations.len().saturating_sub(max_len));
+			observations.drain(..keep_from);
+		});
+	}
+
+	fn update_divergence(currency_id: CurrencyId) {
+		let is_diverged = Self::is_diverged(currency_id);
+		if is_diverged == Self::diverged(currency_id) {
+			return;
+		}
+
+		if is_diverged {
+			Diverged::insert(currency_id, true);
+			let divergence = Self::divergence(currency_id).unwrap_or_default();
+			Self::deposit_event(Event::PriceDiverged(currency_id, divergence));
+		} else {
+			Diverged::remove(currency_id);
+			Self::deposit_event(Event::PriceConverged(currency_id));
+		}
+	}
+
+	/// Check new aggregated price of `currency_id` against its circuit breaker.
+	///
+	/// The last accepted price is the reference, and is only refreshed once it gets older than
+	/// window. A price moving out of band from the reference within window trips the circuit
+	/// breaker. Tripped circuit breakers reset once prices stay in band for a whole window.
+	fn update_circuit_breaker(currency_id: CurrencyId) {
+		let params = match Self::circuit_breaker(currency_id) {
+			Some(params) => params,
+			None => return,
+		};
+		let price = match T::Source::get_no_op(&currency_id) {
+			Some(price) => price.value,
+			None => return,
+		};
+		let now = Self::now();
+
+		if let Some(tripped) = Self::tripped_price(currency_id) {
+			if !Self::is_in_band(tripped.value, price, params.band) {
+				TrippedPrices::insert(
+					currency_id,
+					TimestampedValue {
+						value: price,
+						timestamp: now,
+					},
+				);
+			} else if now.saturating_sub(tripped.timestamp) >= params.window {
+				Self::accept_price(currency_id, price);
+				TrippedPrices::remove(currency_id);
+				Self::deposit_event(Event::CircuitBreakerReset(currency_id));
+			}
+			return;
+		}
+
+		match Self::last_accepted_price(currency_id) {
+			Some(last) if now.saturating_sub(last.timestamp) < params.window => {
+				// keep the reference for the whole window, so that stepwise moves add up
+				if !Self::is_in_band(last.value, price, params.band) {
+					TrippedPrices::insert(
+						currency_id,
+						TimestampedValue {
+							value: price,
+							timestamp: now,
+						},
+					);
+					Self::deposit_event(Event::CircuitBreakerTripped(currency_id, last.value, price));
+				}
+			}
+			_ => Self::accept_price(currency_id, price),
+		}
+	}
+}
+
+impl<T: Config> PriceGuard for Module<T> {
+	fn is_stale(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		let currency_id = T::OracleKey::convert(currency_id);
+		let max_age = match usage {
+			PriceUsage::Trading => Self::max_price_age(currency_id),
+			PriceUsage::Liquidation => Self::liquidation_max_price_age(currency_id),
+		};
+		match (max_age, Self::price_age(currency_id)) {
+			(Some(max_age), Some(age)) => age > max_age,
+			_ => false,
+		}
+	}
+
+	fn is_unreliable(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		let currency_id = T::OracleKey::convert(currency_id);
+		if Self::is_tripped(currency_id) {
+			return true;
+		}
+		match usage {
+			PriceUsage::Trading => Self::is_diverged(currency_id),
+			PriceUsage::Liquidation => {
+				Self::liquidation_fallback(currency_id) == LiquidationFallback::Pause && Self::is_diverged(currency_id)
+			}
+		}
+	}
+}
+
+impl<T: Config, AccountId> OnNewData<AccountId, CurrencyId, Price> for Module<T> {
+	fn on_new_data(_who: &AccountId, currency_id: &CurrencyId, _price: &Price) {
+		Self::update_divergence(*currency_id);
+		Self::update_circuit_breaker(*currency_id);
+		Self::update_twap(*currency_id);
+	}
+}
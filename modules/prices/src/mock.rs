@@ -0,0 +1,187 @@
+//! Mocks for the prices module.
+
+#![cfg(test)]
+
+use frame_support::{impl_outer_event, impl_outer_origin, ord_parameter_types, parameter_types};
+use frame_system::EnsureSignedBy;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{Convert, IdentityLookup},
+	Perbill,
+};
+use sp_std::{cell::RefCell, collections::btree_map::BTreeMap};
+
+use super::*;
+
+impl_outer_origin! {
+	pub enum Origin for Runtime {}
+}
+
+mod prices {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Runtime {
+		frame_system<T>,
+		prices,
+	}
+}
+
+ord_parameter_types! {
+	pub const UpdateOrigin: AccountId = 0;
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Runtime;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: u32 = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+pub type AccountId = u64;
+impl frame_system::Config for Runtime {
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = ::sp_runtime::traits::BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type AccountData = ();
+	type BaseCallFilter = ();
+	type SystemWeightInfo = ();
+}
+pub type System = frame_system::Module<Runtime>;
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 5;
+}
+impl pallet_timestamp::Config for Runtime {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+pub type Timestamp = pallet_timestamp::Module<Runtime>;
+
+macro_rules! mock_oracle {
+	($name:ident, $prices:ident) => {
+		thread_local! {
+			static $prices: RefCell<BTreeMap<CurrencyId, TimestampedPrice>> = RefCell::new(BTreeMap::new());
+		}
+
+		pub struct $name;
+		impl $name {
+			/// Feed `price` of `currency_id` at `timestamp`.
+			pub fn feed(currency_id: CurrencyId, price: Price, timestamp: Moment) {
+				$prices.with(|v| {
+					v.borrow_mut().insert(
+						currency_id,
+						TimestampedValue {
+							value: price,
+							timestamp,
+						},
+					)
+				});
+			}
+
+			fn clear() {
+				$prices.with(|v| v.borrow_mut().clear());
+			}
+		}
+		impl DataProviderExtended<CurrencyId, TimestampedPrice> for $name {
+			fn get_no_op(key: &CurrencyId) -> Option<TimestampedPrice> {
+				$prices.with(|v| v.borrow().get(key).cloned())
+			}
+
+			fn get_all_values() -> Vec<(CurrencyId, Option<TimestampedPrice>)> {
+				$prices.with(|v| v.borrow().iter().map(|(k, p)| (*k, Some(p.clone()))).collect())
+			}
+		}
+	};
+}
+
+mock_oracle!(MockOracle, PRICES);
+mock_oracle!(MockLaminarOracle, LAMINAR_PRICES);
+mock_oracle!(MockBandOracle, BAND_PRICES);
+
+parameter_types! {
+	pub const TwapWindow: Moment = 60_000;
+	pub const MaxTwapObservations: u32 = 5;
+}
+
+/// `Asset(0)` is priced by the oracle key of FEUR.
+pub struct MockOracleKey;
+impl Convert<CurrencyId, CurrencyId> for MockOracleKey {
+	fn convert(currency_id: CurrencyId) -> CurrencyId {
+		match currency_id {
+			CurrencyId::Asset(0) => CurrencyId::FEUR,
+			_ => currency_id,
+		}
+	}
+}
+
+impl Config for Runtime {
+	type Event = TestEvent;
+	type OracleKey = MockOracleKey;
+	type Source = MockOracle;
+	type LaminarSource = MockLaminarOracle;
+	type BandSource = MockBandOracle;
+	type UnixTime = Timestamp;
+	type TwapWindow = TwapWindow;
+	type MaxTwapObservations = MaxTwapObservations;
+	type UpdateOrigin = EnsureSignedBy<UpdateOrigin, AccountId>;
+	type WeightInfo = ();
+}
+
+pub type Prices = Module<Runtime>;
+
+const ALICE_ACC_ID: AccountId = 0;
+pub fn alice() -> Origin {
+	Origin::signed(ALICE_ACC_ID)
+}
+
+const BOB_ACC_ID: AccountId = 1;
+pub fn bob() -> Origin {
+	Origin::signed(BOB_ACC_ID)
+}
+
+#[derive(Default)]
+pub struct ExtBuilder;
+
+impl ExtBuilder {
+	pub fn build(self) -> sp_io::TestExternalities {
+		let t = frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into();
+
+		MockOracle::clear();
+		MockLaminarOracle::clear();
+		MockBandOracle::clear();
+
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+}
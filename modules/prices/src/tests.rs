@@ -0,0 +1,490 @@
+//! Unit tests for the prices module.
+
+#![cfg(test)]
+
+use super::*;
+use mock::*;
+
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::{traits::BadOrigin, FixedPointNumber};
+
+fn new_data(currency_id: CurrencyId) {
+	<Prices as OnNewData<AccountId, CurrencyId, Price>>::on_new_data(&0, &currency_id, &Price::zero());
+}
+
+fn feed(currency_id: CurrencyId, price: Price) {
+	MockOracle::feed(currency_id, price, Timestamp::now());
+	new_data(currency_id);
+}
+
+fn ten_percent_in_a_minute() -> Option<CircuitBreakerParams> {
+	Some(CircuitBreakerParams {
+		band: Permill::from_percent(10),
+		window: 60_000,
+	})
+}
+
+#[test]
+fn set_max_price_age_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Prices::set_max_price_age(bob(), CurrencyId::FEUR, Some(60_000)),
+			BadOrigin
+		);
+
+		assert_ok!(Prices::set_max_price_age(alice(), CurrencyId::FEUR, Some(60_000)));
+		assert_eq!(Prices::max_price_age(CurrencyId::FEUR), Some(60_000));
+		assert_eq!(Prices::liquidation_max_price_age(CurrencyId::FEUR), None);
+
+		let event = TestEvent::prices(Event::MaxPriceAgeUpdated(CurrencyId::FEUR, Some(60_000)));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(Prices::set_max_price_age(alice(), CurrencyId::FEUR, None));
+		assert_eq!(Prices::max_price_age(CurrencyId::FEUR), None);
+	});
+}
+
+#[test]
+fn set_liquidation_max_price_age_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Prices::set_liquidation_max_price_age(bob(), CurrencyId::FEUR, Some(60_000)),
+			BadOrigin
+		);
+
+		assert_ok!(Prices::set_liquidation_max_price_age(
+			alice(),
+			CurrencyId::FEUR,
+			Some(60_000)
+		));
+		assert_eq!(Prices::liquidation_max_price_age(CurrencyId::FEUR), Some(60_000));
+		assert_eq!(Prices::max_price_age(CurrencyId::FEUR), None);
+
+		let event = TestEvent::prices(Event::LiquidationMaxPriceAgeUpdated(CurrencyId::FEUR, Some(60_000)));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn is_stale_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		Timestamp::set_timestamp(100_000);
+		MockOracle::feed(CurrencyId::FEUR, Price::saturating_from_integer(1), 30_000);
+		assert_eq!(Prices::price_age(CurrencyId::FEUR), Some(70_000));
+		assert_eq!(Prices::price_age(CurrencyId::FJPY), None);
+
+		// no max age
+		assert!(!Prices::is_stale(CurrencyId::FEUR, PriceUsage::Trading));
+		assert!(!Prices::is_stale(CurrencyId::FEUR, PriceUsage::Liquidation));
+
+		assert_ok!(Prices::set_max_price_age(alice(), CurrencyId::FEUR, Some(60_000)));
+		assert!(Prices::is_stale(CurrencyId::FEUR, PriceUsage::Trading));
+		assert!(!Prices::is_stale(CurrencyId::FEUR, PriceUsage::Liquidation));
+
+		assert_ok!(Prices::set_liquidation_max_price_age(
+			alice(),
+			CurrencyId::FEUR,
+			Some(70_000)
+		));
+		assert!(!Prices::is_stale(CurrencyId::FEUR, PriceUsage::Liquidation));
+		Timestamp::set_timestamp(100_001);
+		assert!(Prices::is_stale(CurrencyId::FEUR, PriceUsage::Liquidation));
+
+		// no price is not stale
+		assert_ok!(Prices::set_max_price_age(alice(), CurrencyId::FJPY, Some(60_000)));
+		assert!(!Prices::is_stale(CurrencyId::FJPY, PriceUsage::Trading));
+	});
+}
+
+#[test]
+fn set_max_divergence_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Prices::set_max_divergence(bob(), CurrencyId::FEUR, Some(Permill::from_percent(5))),
+			BadOrigin
+		);
+
+		assert_ok!(Prices::set_max_divergence(
+			alice(),
+			CurrencyId::FEUR,
+			Some(Permill::from_percent(5))
+		));
+		assert_eq!(Prices::max_divergence(CurrencyId::FEUR), Some(Permill::from_percent(5)));
+
+		let event = TestEvent::prices(Event::MaxDivergenceUpdated(
+			CurrencyId::FEUR,
+			Some(Permill::from_percent(5)),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn set_liquidation_fallback_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			Prices::liquidation_fallback(CurrencyId::FEUR),
+			LiquidationFallback::Pause
+		);
+		assert_noop!(
+			Prices::set_liquidation_fallback(bob(), CurrencyId::FEUR, LiquidationFallback::Continue),
+			BadOrigin
+		);
+
+		assert_ok!(Prices::set_liquidation_fallback(
+			alice(),
+			CurrencyId::FEUR,
+			LiquidationFallback::Continue
+		));
+		assert_eq!(
+			Prices::liquidation_fallback(CurrencyId::FEUR),
+			LiquidationFallback::Continue
+		);
+
+		let event = TestEvent::prices(Event::LiquidationFallbackUpdated(
+			CurrencyId::FEUR,
+			LiquidationFallback::Continue,
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn is_unreliable_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		MockLaminarOracle::feed(CurrencyId::FEUR, Price::saturating_from_rational(100, 100), 0);
+		MockBandOracle::feed(CurrencyId::FEUR, Price::saturating_from_rational(110, 100), 0);
+		assert_eq!(
+			Prices::divergence(CurrencyId::FEUR),
+			Some(Price::saturating_from_rational(10, 100))
+		);
+		assert_eq!(Prices::divergence(CurrencyId::FJPY), None);
+
+		// no max divergence
+		assert!(!Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+
+		assert_ok!(Prices::set_max_divergence(
+			alice(),
+			CurrencyId::FEUR,
+			Some(Permill::from_percent(10))
+		));
+		assert!(!Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+
+		assert_ok!(Prices::set_max_divergence(
+			alice(),
+			CurrencyId::FEUR,
+			Some(Permill::from_percent(5))
+		));
+		assert!(Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+		assert!(Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Liquidation));
+
+		assert_ok!(Prices::set_liquidation_fallback(
+			alice(),
+			CurrencyId::FEUR,
+			LiquidationFallback::Continue
+		));
+		assert!(Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+		assert!(!Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Liquidation));
+	});
+}
+
+#[test]
+fn on_new_data_emits_divergence_events() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Prices::set_max_divergence(
+			alice(),
+			CurrencyId::FEUR,
+			Some(Permill::from_percent(5))
+		));
+		MockLaminarOracle::feed(CurrencyId::FEUR, Price::saturating_from_integer(1), 0);
+		MockBandOracle::feed(CurrencyId::FEUR, Price::saturating_from_rational(110, 100), 0);
+		new_data(CurrencyId::FEUR);
+		assert!(Prices::diverged(CurrencyId::FEUR));
+
+		let event = TestEvent::prices(Event::PriceDiverged(
+			CurrencyId::FEUR,
+			Price::saturating_from_rational(10, 100),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		MockBandOracle::feed(CurrencyId::FEUR, Price::saturating_from_rational(101, 100), 0);
+		new_data(CurrencyId::FEUR);
+		assert!(!Prices::diverged(CurrencyId::FEUR));
+
+		let event = TestEvent::prices(Event::PriceConverged(CurrencyId::FEUR));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn set_circuit_breaker_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Prices::set_circuit_breaker(bob(), CurrencyId::FEUR, ten_percent_in_a_minute()),
+			BadOrigin
+		);
+
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+		assert_eq!(Prices::circuit_breaker(CurrencyId::FEUR), ten_percent_in_a_minute());
+
+		let event = TestEvent::prices(Event::CircuitBreakerUpdated(
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute(),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(1));
+		assert!(Prices::last_accepted_price(CurrencyId::FEUR).is_some());
+
+		assert_ok!(Prices::set_circuit_breaker(alice(), CurrencyId::FEUR, None));
+		assert_eq!(Prices::circuit_breaker(CurrencyId::FEUR), None);
+		assert_eq!(Prices::last_accepted_price(CurrencyId::FEUR), None);
+	});
+}
+
+#[test]
+fn circuit_breaker_trips_and_stabilizes() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+
+		Timestamp::set_timestamp(10_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		// in band
+		Timestamp::set_timestamp(20_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(109));
+		assert!(!Prices::is_tripped(CurrencyId::FEUR));
+		// the reference is kept within window
+		assert_eq!(
+			Prices::last_accepted_price(CurrencyId::FEUR).map(|p| p.value),
+			Some(Price::saturating_from_integer(100))
+		);
+
+		// out of band, trips
+		Timestamp::set_timestamp(30_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(65));
+		assert!(Prices::is_tripped(CurrencyId::FEUR));
+		assert!(Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+		assert!(Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Liquidation));
+
+		let event = TestEvent::prices(Event::CircuitBreakerTripped(
+			CurrencyId::FEUR,
+			Price::saturating_from_integer(100),
+			Price::saturating_from_integer(65),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// moving out of band again restarts stabilization
+		Timestamp::set_timestamp(60_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(50));
+		Timestamp::set_timestamp(100_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(52));
+		assert!(Prices::is_tripped(CurrencyId::FEUR));
+
+		// in band for a whole window
+		Timestamp::set_timestamp(120_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(51));
+		assert!(!Prices::is_tripped(CurrencyId::FEUR));
+		assert!(!Prices::is_unreliable(CurrencyId::FEUR, PriceUsage::Trading));
+		assert_eq!(
+			Prices::last_accepted_price(CurrencyId::FEUR).map(|p| p.value),
+			Some(Price::saturating_from_integer(51))
+		);
+
+		let event = TestEvent::prices(Event::CircuitBreakerReset(CurrencyId::FEUR));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// out of band after window is not a trip
+		Timestamp::set_timestamp(200_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(80));
+		assert!(!Prices::is_tripped(CurrencyId::FEUR));
+	});
+}
+
+#[test]
+fn circuit_breaker_trips_on_stepwise_moves() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+
+		Timestamp::set_timestamp(10_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		// each step is in band of the previous price
+		Timestamp::set_timestamp(20_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(92));
+		Timestamp::set_timestamp(30_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(85));
+		assert!(Prices::is_tripped(CurrencyId::FEUR));
+
+		let event = TestEvent::prices(Event::CircuitBreakerTripped(
+			CurrencyId::FEUR,
+			Price::saturating_from_integer(100),
+			Price::saturating_from_integer(85),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn circuit_breaker_refreshes_reference_after_window() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+
+		Timestamp::set_timestamp(10_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		Timestamp::set_timestamp(70_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(92));
+		assert_eq!(
+			Prices::last_accepted_price(CurrencyId::FEUR),
+			Some(TimestampedValue {
+				value: Price::saturating_from_integer(92),
+				timestamp: 70_000,
+			})
+		);
+
+		Timestamp::set_timestamp(80_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(85));
+		assert!(!Prices::is_tripped(CurrencyId::FEUR));
+	});
+}
+
+#[test]
+fn reset_circuit_breaker_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+		Timestamp::set_timestamp(10_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(140));
+		assert!(Prices::is_tripped(CurrencyId::FEUR));
+
+		assert_noop!(Prices::reset_circuit_breaker(bob(), CurrencyId::FEUR), BadOrigin);
+
+		assert_ok!(Prices::reset_circuit_breaker(alice(), CurrencyId::FEUR));
+		assert!(!Prices::is_tripped(CurrencyId::FEUR));
+		assert_eq!(
+			Prices::last_accepted_price(CurrencyId::FEUR).map(|p| p.value),
+			Some(Price::saturating_from_integer(140))
+		);
+
+		let event = TestEvent::prices(Event::CircuitBreakerReset(CurrencyId::FEUR));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn twap_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Prices::twap(CurrencyId::FEUR), None);
+
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(100))
+		);
+
+		Timestamp::set_timestamp(30_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(200));
+		// history shorter than window
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(100))
+		);
+
+		Timestamp::set_timestamp(60_000);
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(150))
+		);
+
+		Timestamp::set_timestamp(90_000);
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(200))
+		);
+
+		// observations before window start are pruned, except the last one
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(300));
+		assert_eq!(
+			Prices::twap_observations(CurrencyId::FEUR)
+				.iter()
+				.map(|o| o.timestamp)
+				.collect::<Vec<_>>(),
+			vec![30_000, 90_000]
+		);
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(200))
+		);
+
+		Timestamp::set_timestamp(120_000);
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_integer(250))
+		);
+	});
+}
+
+#[test]
+fn twap_observations_are_limited() {
+	ExtBuilder::default().build().execute_with(|| {
+		for i in 1..=10 {
+			Timestamp::set_timestamp(i * 1_000);
+			feed(CurrencyId::FEUR, Price::saturating_from_integer(i));
+		}
+		assert_eq!(Prices::twap_observations(CurrencyId::FEUR).len(), 5);
+		assert_eq!(Prices::twap_observations(CurrencyId::FEUR)[0].timestamp, 6_000);
+
+		// feeds at the same time replace each other
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(20));
+		assert_eq!(Prices::twap_observations(CurrencyId::FEUR).len(), 5);
+		assert_eq!(
+			Prices::twap(CurrencyId::FEUR),
+			Some(Price::saturating_from_rational(15, 2))
+		);
+	});
+}
+
+#[test]
+fn price_guard_queries_by_oracle_key() {
+	ExtBuilder::default().build().execute_with(|| {
+		// `Asset(0)` is priced by the FEUR oracle key
+		assert_ok!(Prices::set_circuit_breaker(
+			alice(),
+			CurrencyId::FEUR,
+			ten_percent_in_a_minute()
+		));
+		assert_ok!(Prices::set_max_price_age(alice(), CurrencyId::FEUR, Some(60_000)));
+
+		Timestamp::set_timestamp(10_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(100));
+		assert!(!Prices::is_unreliable(CurrencyId::Asset(0), PriceUsage::Trading));
+
+		Timestamp::set_timestamp(20_000);
+		feed(CurrencyId::FEUR, Price::saturating_from_integer(50));
+		assert!(Prices::is_tripped(CurrencyId::FEUR));
+		assert!(Prices::is_unreliable(CurrencyId::Asset(0), PriceUsage::Trading));
+		assert!(Prices::is_unreliable(CurrencyId::Asset(0), PriceUsage::Liquidation));
+
+		assert!(!Prices::is_stale(CurrencyId::Asset(0), PriceUsage::Trading));
+		Timestamp::set_timestamp(90_000);
+		assert!(Prices::is_stale(CurrencyId::Asset(0), PriceUsage::Trading));
+	});
+}
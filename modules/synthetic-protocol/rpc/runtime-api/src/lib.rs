@@ -7,9 +7,10 @@
 #![allow(clippy::unnecessary_mut_passed)]
 
 use codec::{Codec, Decode, Encode};
-use laminar_primitives::{CurrencyId, LiquidityPoolId};
+use laminar_primitives::{Balance, CurrencyId, LiquidityPoolId};
 use sp_arithmetic::FixedU128;
 use sp_core::RuntimeDebug;
+use sp_runtime::DispatchError;
 use sp_std::prelude::*;
 
 #[cfg(feature = "std")]
@@ -22,10 +23,40 @@ pub struct SyntheticPoolState {
 	pub is_safe: bool,
 }
 
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct SyntheticMintQuote {
+	pub ask_price: FixedU128,
+	pub synthetic_amount: Balance,
+	pub fee: Balance,
+	pub additional_collateral: Balance,
+	pub has_enough_liquidity: bool,
+}
+
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct SyntheticRedeemQuote {
+	pub bid_price: FixedU128,
+	pub collateral_amount: Balance,
+	pub fee: Balance,
+	pub pool_refund_collateral: Balance,
+	pub has_enough_liquidity: bool,
+}
+
 sp_api::decl_runtime_apis! {
 	pub trait SyntheticProtocolApi<AccountId> where
 		AccountId: Codec,
 	{
 		fn pool_state(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<SyntheticPoolState>;
+		fn mint_quote(
+			pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			collateral_amount: Balance,
+		) -> Result<SyntheticMintQuote, DispatchError>;
+		fn redeem_quote(
+			pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			synthetic_amount: Balance,
+		) -> Result<SyntheticRedeemQuote, DispatchError>;
 	}
 }
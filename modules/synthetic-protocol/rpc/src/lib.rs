@@ -1,12 +1,14 @@
 use codec::Codec;
 use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
 use jsonrpc_derive::rpc;
-use laminar_primitives::{CurrencyId, LiquidityPoolId};
+use laminar_primitives::{Balance, CurrencyId, LiquidityPoolId};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT, DispatchError};
 use std::sync::Arc;
-pub use synthetic_protocol_rpc_runtime_api::{SyntheticPoolState, SyntheticProtocolApi as SyntheticProtocolRuntimeApi};
+pub use synthetic_protocol_rpc_runtime_api::{
+	SyntheticMintQuote, SyntheticPoolState, SyntheticProtocolApi as SyntheticProtocolRuntimeApi, SyntheticRedeemQuote,
+};
 
 #[rpc]
 pub trait SyntheticProtocolApi<BlockHash, AccountId> {
@@ -17,6 +19,24 @@ pub trait SyntheticProtocolApi<BlockHash, AccountId> {
 		currency_id: CurrencyId,
 		at: Option<BlockHash>,
 	) -> Result<Option<SyntheticPoolState>>;
+
+	#[rpc(name = "synthetic_mintQuote")]
+	fn mint_quote(
+		&self,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_amount: Balance,
+		at: Option<BlockHash>,
+	) -> Result<std::result::Result<SyntheticMintQuote, DispatchError>>;
+
+	#[rpc(name = "synthetic_redeemQuote")]
+	fn redeem_quote(
+		&self,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic_amount: Balance,
+		at: Option<BlockHash>,
+	) -> Result<std::result::Result<SyntheticRedeemQuote, DispatchError>>;
 }
 
 /// A struct that implements the [`SyntheticProtocolApi`].
@@ -70,4 +90,42 @@ where
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
+
+	fn mint_quote(
+		&self,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_amount: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<std::result::Result<SyntheticMintQuote, DispatchError>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		api.mint_quote(&at, pool_id, currency_id, collateral_amount)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to get mint quote.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+
+	fn redeem_quote(
+		&self,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic_amount: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<std::result::Result<SyntheticRedeemQuote, DispatchError>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+		api.redeem_quote(&at, pool_id, currency_id, synthetic_amount)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to get redeem quote.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
 }
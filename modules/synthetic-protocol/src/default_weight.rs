@@ -9,27 +9,137 @@ use frame_support::weights::{constants::RocksDbWeight as DbWeight, Weight};
 impl crate::WeightInfo for () {
 	fn mint() -> Weight {
 		(506_992_000 as Weight)
-			.saturating_add(DbWeight::get().reads(22 as Weight))
-			.saturating_add(DbWeight::get().writes(9 as Weight))
+			.saturating_add(DbWeight::get().reads(28 as Weight))
+			.saturating_add(DbWeight::get().writes(12 as Weight))
 	}
 	fn redeem() -> Weight {
 		(661_365_000 as Weight)
-			.saturating_add(DbWeight::get().reads(22 as Weight))
-			.saturating_add(DbWeight::get().writes(9 as Weight))
+			.saturating_add(DbWeight::get().reads(28 as Weight))
+			.saturating_add(DbWeight::get().writes(12 as Weight))
+	}
+	fn swap() -> Weight {
+		(1_141_208_000 as Weight)
+			.saturating_add(DbWeight::get().reads(40 as Weight))
+			.saturating_add(DbWeight::get().writes(18 as Weight))
+	}
+	// Per pool terms are not benchmarked yet, conservatively estimated as one mint or redeem in
+	// each pool.
+	fn routed_mint(p: u32) -> Weight {
+		(1_320_574_000 as Weight)
+			.saturating_add((560_000_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(DbWeight::get().reads(52 as Weight))
+			.saturating_add(DbWeight::get().reads((32 as Weight).saturating_mul(p as Weight)))
+			.saturating_add(DbWeight::get().writes(20 as Weight))
+			.saturating_add(DbWeight::get().writes((12 as Weight).saturating_mul(p as Weight)))
+	}
+	// Per pool terms are not benchmarked yet, as `routed_mint`.
+	fn routed_redeem(p: u32) -> Weight {
+		(1_486_219_000 as Weight)
+			.saturating_add((700_000_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add(DbWeight::get().reads(52 as Weight))
+			.saturating_add(DbWeight::get().reads((32 as Weight).saturating_mul(p as Weight)))
+			.saturating_add(DbWeight::get().writes(20 as Weight))
+			.saturating_add(DbWeight::get().writes((12 as Weight).saturating_mul(p as Weight)))
 	}
 	fn liquidate() -> Weight {
 		(567_526_000 as Weight)
-			.saturating_add(DbWeight::get().reads(20 as Weight))
-			.saturating_add(DbWeight::get().writes(8 as Weight))
+			.saturating_add(DbWeight::get().reads(24 as Weight))
+			.saturating_add(DbWeight::get().writes(10 as Weight))
 	}
 	fn add_collateral() -> Weight {
 		(271_474_000 as Weight)
-			.saturating_add(DbWeight::get().reads(9 as Weight))
-			.saturating_add(DbWeight::get().writes(7 as Weight))
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
 	}
 	fn withdraw_collateral() -> Weight {
 		(411_939_000 as Weight)
-			.saturating_add(DbWeight::get().reads(20 as Weight))
-			.saturating_add(DbWeight::get().writes(7 as Weight))
+			.saturating_add(DbWeight::get().reads(24 as Weight))
+			.saturating_add(DbWeight::get().writes(9 as Weight))
+	}
+	fn vault_mint() -> Weight {
+		(312_416_000 as Weight)
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn vault_burn() -> Weight {
+		(287_903_000 as Weight)
+			.saturating_add(DbWeight::get().reads(8 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	fn vault_add_collateral() -> Weight {
+		(154_627_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn vault_withdraw_collateral() -> Weight {
+		(236_185_000 as Weight)
+			.saturating_add(DbWeight::get().reads(9 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn vault_liquidate() -> Weight {
+		(395_248_000 as Weight)
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	fn force_add_collateral() -> Weight {
+		(264_713_000 as Weight)
+			.saturating_add(DbWeight::get().reads(16 as Weight))
+			.saturating_add(DbWeight::get().writes(6 as Weight))
+	}
+	fn mark_under_liquidation() -> Weight {
+		(98_352_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn position_become_safe() -> Weight {
+		(97_846_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn start_collateral_auction() -> Weight {
+		(126_480_000 as Weight)
+			.saturating_add(DbWeight::get().reads(10 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_collateral_currency() -> Weight {
+		(75_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn add_extra_collateral() -> Weight {
+		(290_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn withdraw_extra_collateral() -> Weight {
+		(465_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(15 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_fee_rates() -> Weight {
+		(75_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn set_stability_fee_rate() -> Weight {
+		(36_220_000 as Weight).saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn on_initialize(p: u32, n: u32) -> Weight {
+		(12_430_000 as Weight)
+			.saturating_add((1_874_000 as Weight).saturating_mul(p as Weight))
+			.saturating_add((98_615_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().reads((1 as Weight).saturating_mul(p as Weight)))
+			.saturating_add(DbWeight::get().reads((9 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+			.saturating_add(DbWeight::get().writes((5 as Weight).saturating_mul(n as Weight)))
 	}
 }
@@ -1,22 +1,40 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
 use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage, ensure,
-	traits::Get,
+	debug, decl_error, decl_event, decl_module, decl_storage, ensure,
+	storage::IterableStorageDoubleMap,
+	traits::{EnsureOrigin, Get, UnixTime},
 	weights::{DispatchClass, Weight},
 };
-use frame_system::ensure_signed;
+use frame_system::{
+	ensure_none, ensure_signed,
+	offchain::{SendTransactionTypes, SubmitTransaction},
+};
 use sp_runtime::{
+	offchain::{
+		storage_lock::{StorageLock, Time},
+		Duration,
+	},
 	traits::{CheckedAdd, CheckedDiv, CheckedSub, Saturating, Zero},
-	DispatchError, DispatchResult, FixedPointNumber, FixedU128,
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity, ValidTransaction,
+	},
+	DispatchError, DispatchResult, FixedPointNumber, FixedU128, Permill, RuntimeDebug,
 };
-use sp_std::result;
+use sp_std::{prelude::*, result};
 
-use orml_traits::{BasicCurrency, MultiCurrency, PriceProvider};
+use orml_traits::{
+	auction::{Auction, AuctionHandler, OnNewBidResult},
+	BasicCurrency, Change, MultiCurrency, PriceProvider,
+};
 use orml_utilities::with_transaction_result;
 
 use laminar_primitives::{Balance, CurrencyId, LiquidityPoolId, Price};
-use module_traits::{LiquidityPools, SyntheticProtocolLiquidityPools};
+use module_traits::{
+	is_risk_pricing, price_usage, with_price_usage, with_risk_prices, LiquidityPools, PriceGuard, PriceUsage,
+	SyntheticProtocolLiquidityPools,
+};
 
 mod default_weight;
 mod mock;
@@ -25,12 +43,30 @@ mod tests;
 pub trait WeightInfo {
 	fn mint() -> Weight;
 	fn redeem() -> Weight;
+	fn swap() -> Weight;
+	fn routed_mint(p: u32) -> Weight;
+	fn routed_redeem(p: u32) -> Weight;
 	fn liquidate() -> Weight;
 	fn add_collateral() -> Weight;
 	fn withdraw_collateral() -> Weight;
+	fn vault_mint() -> Weight;
+	fn vault_burn() -> Weight;
+	fn vault_add_collateral() -> Weight;
+	fn vault_withdraw_collateral() -> Weight;
+	fn vault_liquidate() -> Weight;
+	fn force_add_collateral() -> Weight;
+	fn mark_under_liquidation() -> Weight;
+	fn position_become_safe() -> Weight;
+	fn start_collateral_auction() -> Weight;
+	fn set_collateral_currency() -> Weight;
+	fn add_extra_collateral() -> Weight;
+	fn withdraw_extra_collateral() -> Weight;
+	fn set_fee_rates() -> Weight;
+	fn set_stability_fee_rate() -> Weight;
+	fn on_initialize(p: u32, n: u32) -> Weight;
 }
 
-pub trait Config: module_synthetic_tokens::Config {
+pub trait Config: module_synthetic_tokens::Config + SendTransactionTypes<Call<Self>> {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Config>::Event>;
 
@@ -43,32 +79,173 @@ pub trait Config: module_synthetic_tokens::Config {
 	/// `Get` implementation of collateral currency ID.
 	type GetCollateralCurrencyId: Get<CurrencyId>;
 
+	/// The account ID of treasury, which receives mint and redeem fees.
+	type GetTreasuryAccountId: Get<Self::AccountId>;
+
 	/// Provides market prices.
 	type PriceProvider: PriceProvider<CurrencyId, Price>;
 
+	/// Provides prices for risk checks, i.e. if positions are safe or below extreme ratio.
+	type RiskPriceProvider: PriceProvider<CurrencyId, Price>;
+
+	/// Rejects stale prices.
+	type PriceGuard: PriceGuard;
+
 	/// The basic liquidity pools.
 	type LiquidityPools: LiquidityPools<Self::AccountId>;
 
 	/// The synthetic protocol liquidity pools.
 	type SyntheticProtocolLiquidityPools: SyntheticProtocolLiquidityPools<Self::AccountId>;
 
+	/// Auction to sell collateral of positions below extreme ratio for synthetic.
+	type Auction: Auction<Self::AccountId, Self::BlockNumber, Balance = Balance>;
+
+	/// Duration of a collateral auction.
+	type AuctionDuration: Get<Self::BlockNumber>;
+
+	/// Minimum duration left of a collateral auction after a new bid. The auction end would be
+	/// postponed if needed.
+	type AuctionTimeToClose: Get<Self::BlockNumber>;
+
+	/// Time used for stability fee accrual.
+	type UnixTime: UnixTime;
+
+	/// Interval in seconds of charging stability fees.
+	type StabilityFeeInterval: Get<u64>;
+
+	/// Max number of stability fee records kept for each position.
+	type StabilityFeeHistoryLength: Get<u32>;
+
+	/// Max number of positions checked for stability fees in one block. Charging is resumed on
+	/// later blocks if not finished.
+	type MaxStabilityFeeChecksPerBlock: Get<u32>;
+
+	/// A configuration for base priority of unsigned transactions.
+	///
+	/// This is exposed so that it can be tuned for particular runtime, when
+	/// multiple pallets send unsigned transactions.
+	type UnsignedPriority: Get<TransactionPriority>;
+
 	/// Weight information for the extrinsics in this module.
 	type WeightInfo: WeightInfo;
 }
 
+pub type AuctionIdOf<T> = <<T as Config>::Auction as Auction<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::BlockNumber,
+>>::AuctionId;
+
+/// Collateral auction of a pool position below extreme ratio.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+pub struct CollateralAuctionItem {
+	/// The pool of the auctioned position.
+	pub pool_id: LiquidityPoolId,
+	/// The synthetic currency of the auctioned position.
+	pub currency_id: CurrencyId,
+	/// Collateral for sale.
+	pub collateral: Balance,
+	/// Synthetic amount to be burned to clear the position.
+	pub target: Balance,
+}
+
+/// Quote of minting or redeeming synthetic in a pool.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, Default)]
+pub struct Quote {
+	/// The price used, ask price for minting, or bid price for redeeming.
+	pub price: Price,
+	/// Synthetic amount minted, or collateral amount redeemed, fee excluded.
+	pub amount: Balance,
+	/// Mint or redeem fee.
+	pub fee: Balance,
+	/// Additional collateral from the pool on minting, or collateral refunded to the pool on
+	/// redeeming.
+	pub pool_collateral: Balance,
+	/// If the pool has enough liquidity for the additional collateral on minting, or enough
+	/// synthetic and collateral in position on redeeming.
+	pub is_sufficient: bool,
+}
+
+/// Progress of charging stability fees, resumed on later blocks if not finished.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, Default)]
+pub struct StabilityFeeCursor {
+	/// Time in seconds of this charging.
+	pub time: u64,
+	/// The pool to resume charging from.
+	pub pool_id: LiquidityPoolId,
+	/// Index in `SyntheticCurrencyIds` of the currency to resume charging from.
+	pub currency_index: u32,
+}
+
 decl_storage! {
-	trait Store for Module<T: Config> as SyntheticProtocol {}
+	trait Store for Module<T: Config> as SyntheticProtocol {
+		/// Pool positions under liquidation. Minting and collateral withdrawal are disabled for
+		/// positions under liquidation.
+		pub PositionsUnderLiquidation get(fn positions_under_liquidation): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => Option<()>;
+
+		/// Collateral auctions in progress.
+		pub CollateralAuctions get(fn collateral_auctions): map hasher(twox_64_concat) AuctionIdOf<T> => Option<CollateralAuctionItem>;
+
+		/// Auction ID of pool positions in collateral auction. Positions are frozen until the
+		/// auction ends.
+		pub PositionsInAuction get(fn positions_in_auction): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => Option<AuctionIdOf<T>>;
+
+		/// Synthetic left in circulation after collateral auctions, not backed by any position.
+		pub SystemDebits get(fn system_debits): map hasher(twox_64_concat) CurrencyId => Balance;
+
+		/// Whitelisted extra collateral currencies and their haircut.
+		pub CollateralHaircuts get(fn collateral_haircuts): map hasher(twox_64_concat) CurrencyId => Option<Permill>;
+
+		/// Fee rate of minting synthetic currency, charged on collateral paid.
+		pub MintFeeRates get(fn mint_fee_rates): map hasher(twox_64_concat) CurrencyId => Permill;
+
+		/// Fee rate of redeeming synthetic currency, charged on collateral redeemed.
+		pub RedeemFeeRates get(fn redeem_fee_rates): map hasher(twox_64_concat) CurrencyId => Permill;
+
+		/// Annualized stability fee rate of synthetic currency, charged on synthetic value of
+		/// pool positions.
+		pub StabilityFeeRates get(fn stability_fee_rates): map hasher(twox_64_concat) CurrencyId => Permill;
+
+		/// Last time in seconds stability fees were charged.
+		pub LastStabilityFeeTime get(fn last_stability_fee_time): u64;
+
+		/// Stability fee charging not finished yet.
+		pub StabilityFeeCharging get(fn stability_fee_charging): Option<StabilityFeeCursor>;
+
+		/// Stability fees of pool positions accrued and not paid yet. Accrued on each position change,
+		/// and paid from pool liquidity on charging.
+		pub UnpaidStabilityFees get(fn unpaid_stability_fees): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => Balance;
+
+		/// Last time in seconds stability fees of pool positions accrued.
+		pub LastStabilityFeeAccrualTimes get(fn last_stability_fee_accrual_time): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => u64;
+
+		/// Recent stability fee charges of pool positions, as `(time_in_seconds, charged_amount)`.
+		pub StabilityFeeHistory get(fn stability_fee_history): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => Vec<(u64, Balance)>;
+	}
 }
 
 decl_event! {
 	pub enum Event<T> where
 		<T as frame_system::Config>::AccountId,
+		AuctionId = AuctionIdOf<T>,
 	{
-		/// Synthetic token minted: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
-		Minted(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
+		/// Synthetic token minted: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount, fee\]
+		Minted(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance, Balance),
+
+		/// Synthetic token redeemed: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount, fee\]
+		Redeemed(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance, Balance),
+
+		/// Synthetic token swapped: \[who, from_currency_id, from_pool_id, from_synthetic_amount, to_currency_id, to_pool_id, to_synthetic_amount, fee\]
+		Swapped(AccountId, CurrencyId, LiquidityPoolId, Balance, CurrencyId, LiquidityPoolId, Balance, Balance),
 
-		/// Synthetic token redeemed: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
-		Redeemed(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
+		/// Synthetic token minted across pools: \[who, synthetic_currency_id, collateral_amount, synthetic_amount, fee, fills\]
+		///
+		/// Fills are `(pool_id, collateral_amount, synthetic_amount)` of each pool.
+		RoutedMinted(AccountId, CurrencyId, Balance, Balance, Balance, Vec<(LiquidityPoolId, Balance, Balance)>),
+
+		/// Synthetic token redeemed across pools: \[who, synthetic_currency_id, collateral_amount, synthetic_amount, fee, fills\]
+		///
+		/// Fills are `(pool_id, collateral_amount, synthetic_amount)` of each pool.
+		RoutedRedeemed(AccountId, CurrencyId, Balance, Balance, Balance, Vec<(LiquidityPoolId, Balance, Balance)>),
 
 		/// Synthetic token liquidated: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
 		Liquidated(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
@@ -78,6 +255,63 @@ decl_event! {
 
 		/// Collateral withdrew: \[who, synthetic_currency_id, pool_id, collateral_amount\]
 		CollateralWithdrew(AccountId, CurrencyId, LiquidityPoolId, Balance),
+
+		/// Synthetic token minted from vault: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
+		VaultMinted(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
+
+		/// Synthetic token burned to vault: \[who, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
+		VaultBurned(AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
+
+		/// Vault collateral added: \[who, synthetic_currency_id, pool_id, collateral_amount\]
+		VaultCollateralAdded(AccountId, CurrencyId, LiquidityPoolId, Balance),
+
+		/// Vault collateral withdrew: \[who, synthetic_currency_id, pool_id, collateral_amount\]
+		VaultCollateralWithdrew(AccountId, CurrencyId, LiquidityPoolId, Balance),
+
+		/// Vault liquidated: \[liquidator, vault_owner, synthetic_currency_id, pool_id, collateral_amount, synthetic_amount\]
+		VaultLiquidated(AccountId, AccountId, CurrencyId, LiquidityPoolId, Balance, Balance),
+
+		/// Collateral force added from pool liquidity: \[synthetic_currency_id, pool_id, collateral_amount\]
+		CollateralForceAdded(CurrencyId, LiquidityPoolId, Balance),
+
+		/// Position marked as under liquidation: \[synthetic_currency_id, pool_id\]
+		PositionUnderLiquidation(CurrencyId, LiquidityPoolId),
+
+		/// Position became safe: \[synthetic_currency_id, pool_id\]
+		PositionBecameSafe(CurrencyId, LiquidityPoolId),
+
+		/// Collateral auction started: \[auction_id, synthetic_currency_id, pool_id, collateral_amount, target_synthetic_amount\]
+		CollateralAuctionStarted(AuctionId, CurrencyId, LiquidityPoolId, Balance, Balance),
+
+		/// Collateral auction dealt: \[auction_id, winner, collateral_amount, synthetic_amount\]
+		CollateralAuctionDealt(AuctionId, AccountId, Balance, Balance),
+
+		/// Collateral auction aborted, and the position is left unchanged: \[auction_id\]
+		CollateralAuctionAborted(AuctionId),
+
+		/// System debit increased: \[synthetic_currency_id, synthetic_amount\]
+		SystemDebitIncreased(CurrencyId, Balance),
+
+		/// Failed to return the locked synthetic of an aborted collateral auction to the bidder: \[auction_id, bidder, synthetic_currency_id, synthetic_amount\]
+		CollateralAuctionRefundFailed(AuctionId, AccountId, CurrencyId, Balance),
+
+		/// Extra collateral currency updated, `None` if removed from whitelist: \[collateral_currency_id, haircut\]
+		CollateralCurrencyUpdated(CurrencyId, Option<Permill>),
+
+		/// Extra collateral added: \[who, synthetic_currency_id, pool_id, collateral_currency_id, amount\]
+		ExtraCollateralAdded(AccountId, CurrencyId, LiquidityPoolId, CurrencyId, Balance),
+
+		/// Extra collateral withdrew: \[who, synthetic_currency_id, pool_id, collateral_currency_id, amount\]
+		ExtraCollateralWithdrew(AccountId, CurrencyId, LiquidityPoolId, CurrencyId, Balance),
+
+		/// Fee rates updated: \[synthetic_currency_id, mint_fee_rate, redeem_fee_rate\]
+		FeeRatesUpdated(CurrencyId, Permill, Permill),
+
+		/// Stability fee rate updated: \[synthetic_currency_id, rate\]
+		StabilityFeeRateUpdated(CurrencyId, Permill),
+
+		/// Stability fee charged from pool liquidity: \[synthetic_currency_id, pool_id, charged_amount, unpaid_amount\]
+		StabilityFeeCharged(CurrencyId, LiquidityPoolId, Balance, Balance),
 	}
 }
 
@@ -88,6 +322,11 @@ decl_module! {
 		fn deposit_event() = default;
 
 		const GetCollateralCurrencyId: CurrencyId = T::GetCollateralCurrencyId::get();
+		const GetTreasuryAccountId: T::AccountId = T::GetTreasuryAccountId::get();
+		const StabilityFeeInterval: u64 = T::StabilityFeeInterval::get();
+		const StabilityFeeHistoryLength: u32 = T::StabilityFeeHistoryLength::get();
+		const MaxStabilityFeeChecksPerBlock: u32 = T::MaxStabilityFeeChecksPerBlock::get();
+		const UnsignedPriority: TransactionPriority = T::UnsignedPriority::get();
 
 		/// Mint synthetic tokens.
 		#[weight = <T as Config>::WeightInfo::mint()]
@@ -100,8 +339,8 @@ decl_module! {
 		) {
 			with_transaction_result(|| {
 				let who = ensure_signed(origin)?;
-				let synthetic_amount = Self::do_mint(&who, pool_id, currency_id, collateral_amount, max_price)?;
-				Self::deposit_event(RawEvent::Minted(who, currency_id, pool_id, collateral_amount, synthetic_amount));
+				let (synthetic_amount, fee) = Self::do_mint(&who, pool_id, currency_id, collateral_amount, max_price)?;
+				Self::deposit_event(RawEvent::Minted(who, currency_id, pool_id, collateral_amount, synthetic_amount, fee));
 				Ok(())
 			})?;
 		}
@@ -117,8 +356,89 @@ decl_module! {
 		) {
 			with_transaction_result(|| {
 				let who = ensure_signed(origin)?;
-				let collateral_amount = Self::do_redeem(&who, pool_id, currency_id, synthetic_amount, min_price)?;
-				Self::deposit_event(RawEvent::Redeemed(who, currency_id, pool_id, collateral_amount, synthetic_amount));
+				let (collateral_amount, fee) = Self::do_redeem(&who, pool_id, currency_id, synthetic_amount, min_price)?;
+				Self::deposit_event(RawEvent::Redeemed(who, currency_id, pool_id, collateral_amount, synthetic_amount, fee));
+				Ok(())
+			})?;
+		}
+
+		/// Mint synthetic tokens from pools with best ask price, split across pools if needed.
+		///
+		/// `max_pools` is the max number of pools to route through, and the call is weighted by
+		/// it. Would fail if there are more pools.
+		#[weight = <T as Config>::WeightInfo::routed_mint(*max_pools)]
+		pub fn routed_mint(
+			origin,
+			currency_id: CurrencyId,
+			#[compact] collateral_amount: Balance,
+			max_price: Price,
+			#[compact] max_pools: u32,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let (synthetic_amount, fee, fills) =
+					Self::do_routed_mint(&who, currency_id, collateral_amount, max_price, max_pools)?;
+				Self::deposit_event(RawEvent::RoutedMinted(who, currency_id, collateral_amount, synthetic_amount, fee, fills));
+				Ok(())
+			})?;
+		}
+
+		/// Redeem collateral from pools with best bid price, split across pools if needed.
+		///
+		/// `max_pools` is the max number of pools to route through, and the call is weighted by
+		/// it. Would fail if there are more pools.
+		#[weight = <T as Config>::WeightInfo::routed_redeem(*max_pools)]
+		pub fn routed_redeem(
+			origin,
+			currency_id: CurrencyId,
+			#[compact] synthetic_amount: Balance,
+			min_price: Price,
+			#[compact] max_pools: u32,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let (collateral_amount, fee, fills) =
+					Self::do_routed_redeem(&who, currency_id, synthetic_amount, min_price, max_pools)?;
+				Self::deposit_event(RawEvent::RoutedRedeemed(who, currency_id, collateral_amount, synthetic_amount, fee, fills));
+				Ok(())
+			})?;
+		}
+
+		/// Swap `from_amount` of `from_currency_id` in `from_pool_id` to `to_currency_id` in
+		/// `to_pool_id`, at the bid price of `from_pool_id` and the ask price of `to_pool_id`.
+		///
+		/// Would fail if less than `min_to_amount` of `to_currency_id` is received.
+		#[weight = <T as Config>::WeightInfo::swap()]
+		pub fn swap(
+			origin,
+			#[compact] from_pool_id: LiquidityPoolId,
+			from_currency_id: CurrencyId,
+			#[compact] from_amount: Balance,
+			#[compact] to_pool_id: LiquidityPoolId,
+			to_currency_id: CurrencyId,
+			#[compact] min_to_amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let (to_amount, fee) = Self::do_swap(
+					&who,
+					from_pool_id,
+					from_currency_id,
+					from_amount,
+					to_pool_id,
+					to_currency_id,
+					min_to_amount,
+				)?;
+				Self::deposit_event(RawEvent::Swapped(
+					who,
+					from_currency_id,
+					from_pool_id,
+					from_amount,
+					to_currency_id,
+					to_pool_id,
+					to_amount,
+					fee,
+				));
 				Ok(())
 			})?;
 		}
@@ -133,7 +453,9 @@ decl_module! {
 		) {
 			with_transaction_result(|| {
 				let who = ensure_signed(origin)?;
-				let collateral_amount = Self::do_liquidate(&who, pool_id, currency_id, synthetic_amount)?;
+				let collateral_amount = with_price_usage(PriceUsage::Liquidation, || {
+					Self::do_liquidate(&who, pool_id, currency_id, synthetic_amount)
+				})?;
 				Self::deposit_event(RawEvent::Liquidated(who, currency_id, pool_id, collateral_amount, synthetic_amount));
 				Ok(())
 			})?;
@@ -171,6 +493,290 @@ decl_module! {
 				Ok(())
 			})?;
 		}
+
+		/// Whitelist `currency_id` as extra collateral with `haircut`, or remove it from whitelist
+		/// if `None`.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = <T as Config>::WeightInfo::set_collateral_currency()]
+		pub fn set_collateral_currency(origin, currency_id: CurrencyId, haircut: Option<Permill>) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				ensure!(
+					currency_id != T::GetCollateralCurrencyId::get(),
+					Error::<T>::InvalidCollateralCurrency
+				);
+				CollateralHaircuts::mutate(currency_id, |h| *h = haircut);
+				Self::deposit_event(RawEvent::CollateralCurrencyUpdated(currency_id, haircut));
+				Ok(())
+			})?;
+		}
+
+		/// Add `amount` of whitelisted `collateral_currency_id` to `currency_id` in `pool_id`.
+		#[weight = <T as Config>::WeightInfo::add_extra_collateral()]
+		pub fn add_extra_collateral(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			collateral_currency_id: CurrencyId,
+			#[compact] amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				Self::do_add_extra_collateral(&who, pool_id, currency_id, collateral_currency_id, amount)?;
+				Self::deposit_event(RawEvent::ExtraCollateralAdded(who, currency_id, pool_id, collateral_currency_id, amount));
+				Ok(())
+			})?;
+		}
+
+		/// Withdraw `amount` of extra `collateral_currency_id` from `currency_id` in `pool_id`.
+		///
+		/// May only be called from the pool owner. The position must stay above collateral ratio.
+		#[weight = <T as Config>::WeightInfo::withdraw_extra_collateral()]
+		pub fn withdraw_extra_collateral(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			collateral_currency_id: CurrencyId,
+			#[compact] amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				Self::do_withdraw_extra_collateral(&who, pool_id, currency_id, collateral_currency_id, amount)?;
+				Self::deposit_event(RawEvent::ExtraCollateralWithdrew(who, currency_id, pool_id, collateral_currency_id, amount));
+				Ok(())
+			})?;
+		}
+
+		/// Set mint and redeem fee rates of `currency_id`.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = <T as Config>::WeightInfo::set_fee_rates()]
+		pub fn set_fee_rates(origin, currency_id: CurrencyId, mint_fee_rate: Permill, redeem_fee_rate: Permill) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				ensure!(
+					T::SyntheticCurrencyIds::get().contains(&currency_id),
+					Error::<T>::NotValidSyntheticCurrencyId
+				);
+				MintFeeRates::insert(currency_id, mint_fee_rate);
+				RedeemFeeRates::insert(currency_id, redeem_fee_rate);
+				Self::deposit_event(RawEvent::FeeRatesUpdated(currency_id, mint_fee_rate, redeem_fee_rate));
+				Ok(())
+			})?;
+		}
+
+		/// Set annualized stability fee rate of `currency_id`.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = <T as Config>::WeightInfo::set_stability_fee_rate()]
+		pub fn set_stability_fee_rate(origin, currency_id: CurrencyId, rate: Permill) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				ensure!(
+					T::SyntheticCurrencyIds::get().contains(&currency_id),
+					Error::<T>::NotValidSyntheticCurrencyId
+				);
+				StabilityFeeRates::insert(currency_id, rate);
+				Self::deposit_event(RawEvent::StabilityFeeRateUpdated(currency_id, rate));
+				Ok(())
+			})?;
+		}
+
+		/// Mint synthetic tokens against caller's own vault collateral, with chosen collateral `ratio`.
+		///
+		/// `ratio` may not be lower than the currency's collateral ratio.
+		#[weight = <T as Config>::WeightInfo::vault_mint()]
+		pub fn vault_mint(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			#[compact] collateral_amount: Balance,
+			#[compact] ratio: Permill,
+			max_price: Price,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let synthetic_amount = Self::do_vault_mint(&who, pool_id, currency_id, collateral_amount, ratio, max_price)?;
+				Self::deposit_event(RawEvent::VaultMinted(who, currency_id, pool_id, collateral_amount, synthetic_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Burn synthetic tokens to caller's vault, and release collateral proportionally.
+		#[weight = <T as Config>::WeightInfo::vault_burn()]
+		pub fn vault_burn(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			#[compact] synthetic_amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let collateral_amount = Self::do_vault_burn(&who, pool_id, currency_id, synthetic_amount)?;
+				Self::deposit_event(RawEvent::VaultBurned(who, currency_id, pool_id, collateral_amount, synthetic_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Add collateral to caller's vault.
+		#[weight = <T as Config>::WeightInfo::vault_add_collateral()]
+		pub fn vault_add_collateral(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			#[compact] collateral_amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				Self::do_vault_add_collateral(&who, pool_id, currency_id, collateral_amount)?;
+				Self::deposit_event(RawEvent::VaultCollateralAdded(who, currency_id, pool_id, collateral_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Withdraw collateral from caller's vault.
+		///
+		/// The vault must stay above the currency's collateral ratio after withdrawal.
+		#[weight = <T as Config>::WeightInfo::vault_withdraw_collateral()]
+		pub fn vault_withdraw_collateral(
+			origin,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			#[compact] collateral_amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				Self::do_vault_withdraw_collateral(&who, pool_id, currency_id, collateral_amount)?;
+				Self::deposit_event(RawEvent::VaultCollateralWithdrew(who, currency_id, pool_id, collateral_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Liquidate `owner`'s vault of `currency_id` in `pool_id` by `synthetic_amount`.
+		#[weight = (<T as Config>::WeightInfo::vault_liquidate(), DispatchClass::Operational)]
+		pub fn vault_liquidate(
+			origin,
+			owner: T::AccountId,
+			#[compact] pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			#[compact] synthetic_amount: Balance,
+		) {
+			with_transaction_result(|| {
+				let who = ensure_signed(origin)?;
+				let collateral_amount = with_price_usage(PriceUsage::Liquidation, || {
+					Self::do_vault_liquidate(&who, &owner, pool_id, currency_id, synthetic_amount)
+				})?;
+				Self::deposit_event(RawEvent::VaultLiquidated(who, owner, currency_id, pool_id, collateral_amount, synthetic_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Force `pool_id` to add collateral to its `currency_id` position from free liquidity,
+		/// to restore the position to collateral ratio.
+		///
+		/// May only be called from none origin. Would fail if the position is still safe.
+		#[weight = (<T as Config>::WeightInfo::force_add_collateral(), DispatchClass::Operational)]
+		pub fn force_add_collateral(origin, #[compact] pool_id: LiquidityPoolId, currency_id: CurrencyId) {
+			with_transaction_result(|| {
+				ensure_none(origin)?;
+				let collateral_amount =
+					with_price_usage(PriceUsage::Liquidation, || Self::do_force_add_collateral(pool_id, currency_id))?;
+				Self::deposit_event(RawEvent::CollateralForceAdded(currency_id, pool_id, collateral_amount));
+				Ok(())
+			})?;
+		}
+
+		/// Mark `currency_id` position of `pool_id` as under liquidation.
+		///
+		/// May only be called from none origin. Would fail if the position is still safe.
+		#[weight = (<T as Config>::WeightInfo::mark_under_liquidation(), DispatchClass::Operational)]
+		pub fn mark_under_liquidation(origin, #[compact] pool_id: LiquidityPoolId, currency_id: CurrencyId) {
+			with_transaction_result(|| {
+				ensure_none(origin)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_mark_under_liquidation(pool_id, currency_id))?;
+				Self::deposit_event(RawEvent::PositionUnderLiquidation(currency_id, pool_id));
+				Ok(())
+			})?;
+		}
+
+		/// Remove under liquidation status of `currency_id` position of `pool_id`.
+		///
+		/// May only be called from none origin. Would fail if the position is not safe yet.
+		#[weight = <T as Config>::WeightInfo::position_become_safe()]
+		pub fn position_become_safe(origin, #[compact] pool_id: LiquidityPoolId, currency_id: CurrencyId) {
+			with_transaction_result(|| {
+				ensure_none(origin)?;
+				with_price_usage(PriceUsage::Liquidation, || Self::do_position_become_safe(pool_id, currency_id))?;
+				Self::deposit_event(RawEvent::PositionBecameSafe(currency_id, pool_id));
+				Ok(())
+			})?;
+		}
+
+		/// Start a collateral auction for `currency_id` position of `pool_id`.
+		///
+		/// May only be called from none origin. Would fail if the position is not below extreme ratio.
+		#[weight = (<T as Config>::WeightInfo::start_collateral_auction(), DispatchClass::Operational)]
+		pub fn start_collateral_auction(origin, #[compact] pool_id: LiquidityPoolId, currency_id: CurrencyId) {
+			with_transaction_result(|| {
+				ensure_none(origin)?;
+				let (auction_id, collateral, synthetic) =
+					with_price_usage(PriceUsage::Liquidation, || Self::do_start_collateral_auction(pool_id, currency_id))?;
+				Self::deposit_event(RawEvent::CollateralAuctionStarted(auction_id, currency_id, pool_id, collateral, synthetic));
+				Ok(())
+			})?;
+		}
+
+		fn on_initialize() -> Weight {
+			let cursor = match Self::stability_fee_charging() {
+				Some(cursor) => cursor,
+				None => {
+					let now = T::UnixTime::now().as_secs();
+					let last = Self::last_stability_fee_time();
+					if last.is_zero() {
+						LastStabilityFeeTime::put(now);
+						return <T as Config>::WeightInfo::on_initialize(0, 0);
+					}
+
+					if now.saturating_sub(last) < T::StabilityFeeInterval::get() {
+						return <T as Config>::WeightInfo::on_initialize(0, 0);
+					}
+
+					LastStabilityFeeTime::put(now);
+					StabilityFeeCursor {
+						time: now,
+						..Default::default()
+					}
+				}
+			};
+
+			let (pools_count, checked_count) = Self::charge_stability_fees(cursor);
+			<T as Config>::WeightInfo::on_initialize(pools_count, checked_count)
+		}
+
+		fn offchain_worker(block_number: T::BlockNumber) {
+			let result = with_price_usage(PriceUsage::Liquidation, || Self::offchain_worker(block_number));
+			if let Err(error) = result {
+				match error {
+					OffchainErr::NotValidator | OffchainErr::OffchainLock => {
+						debug::native::info!(
+							target: TAG,
+							"{:?} [block_number = {:?}]",
+							error,
+							block_number,
+						);
+					},
+					_ => {
+						debug::native::error!(
+							target: TAG,
+							"{:?} [block_number = {:?}]",
+							error,
+							block_number,
+						);
+					}
+				};
+			}
+		}
 	}
 }
 
@@ -194,6 +800,12 @@ decl_error! {
 		/// No price from provider.
 		NoPrice,
 
+		/// Price is too old to be used.
+		StalePrice,
+
+		/// Price is not reliable enough to be used.
+		UnreliablePrice,
+
 		/// Negative required additional amount from pool.
 		///
 		/// May caused by wrong spread and ratio config of pool
@@ -222,49 +834,74 @@ decl_error! {
 
 		/// The currency is not enabled in synthetic protocol.
 		NotValidSyntheticCurrencyId,
+
+		/// Collateral ratio is lower than required.
+		CollateralRatioTooLow,
+
+		/// The position is under liquidation.
+		UnderLiquidation,
+
+		/// The position is not under liquidation.
+		NotUnderLiquidation,
+
+		/// The position is not safe.
+		UnsafePosition,
+
+		/// The position is in collateral auction.
+		InAuction,
+
+		/// Swapped amount is lower than required.
+		SwapAmountTooLow,
+
+		/// Not enough pools with acceptable price and liquidity to route minting.
+		InsufficientLiquidityInPools,
+
+		/// Not enough pools with acceptable price and synthetic position to route redeeming.
+		InsufficientSyntheticInPools,
+
+		/// More pools than `max_pools` to route through.
+		TooManyPools,
+
+		/// The currency cannot be used as extra collateral.
+		InvalidCollateralCurrency,
+
+		/// The collateral currency is not whitelisted.
+		CollateralCurrencyNotWhitelisted,
+
+		/// Collateral ratio is not below extreme ratio.
+		AboveExtremeRatio,
+
+		/// The collateral auction doesn't exist.
+		NoCollateralAuction,
 	}
 }
 
 type SyntheticTokens<T> = module_synthetic_tokens::Module<T>;
+
+/// Seconds of a year, for annualized stability fee rate.
+const ONE_YEAR: u64 = 365 * 24 * 60 * 60;
 type BalanceResult = result::Result<Balance, DispatchError>;
+type RoutedResult = result::Result<(Balance, Balance, Vec<(LiquidityPoolId, Balance, Balance)>), DispatchError>;
+type WithFeeResult = result::Result<(Balance, Balance), DispatchError>;
 
 // Dispatchable calls implementation
 impl<T: Config> Module<T> {
+	/// Mint with `collateral` paid, fee included. If `Ok`, return with `(synthetic, fee)`.
 	fn do_mint(
 		who: &T::AccountId,
 		pool_id: LiquidityPoolId,
 		currency_id: CurrencyId,
 		collateral: Balance,
 		max_price: Price,
-	) -> BalanceResult {
-		ensure!(
-			T::SyntheticCurrencyIds::get().contains(&currency_id),
-			Error::<T>::NotValidSyntheticCurrencyId
-		);
-
-		ensure!(
-			T::SyntheticProtocolLiquidityPools::can_mint(pool_id, currency_id),
-			Error::<T>::CannotMintInPool
-		);
-
-		let price =
-			T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get()).ok_or(Error::<T>::NoPrice)?;
-		let ask_price = Self::ask_price(pool_id, currency_id, price, max_price)?;
-
-		// synthetic = collateral / ask_price
-		let synthetic = Price::from_inner(collateral)
-			.checked_div(&ask_price)
-			.map(|x| x.into_inner())
-			.ok_or(Error::<T>::NumOverflow)?;
+	) -> WithFeeResult {
+		Self::ensure_can_mint(pool_id, currency_id)?;
 
-		// synthetic_value = synthetic * price
-		// `synthetic_value` is how much `synthetic` values in collateral unit.
-		let synthetic_value = price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
-		// additional_collateral = synthetic_value * (1 + ratio) - collateral
-		let additional_collateral =
-			Self::additional_collateral_amount(pool_id, currency_id, collateral, synthetic_value)?;
+		let (synthetic, fee, _, additional_collateral) =
+			Self::mint_amounts(pool_id, currency_id, collateral, max_price)?;
+		let collateral = collateral.checked_sub(fee).ok_or(Error::<T>::NumOverflow)?;
 
 		// collateralise
+		T::CollateralCurrency::transfer(who, &T::GetTreasuryAccountId::get(), fee)?;
 		T::CollateralCurrency::transfer(who, &<SyntheticTokens<T>>::account_id(), collateral)?;
 		T::LiquidityPools::withdraw_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, additional_collateral)
 			.map_err(|_| Error::<T>::InsufficientLiquidityInPool)?;
@@ -273,45 +910,243 @@ impl<T: Config> Module<T> {
 		T::MultiCurrency::deposit(currency_id, who, synthetic)?;
 
 		let total_collateral = collateral + additional_collateral;
-		<SyntheticTokens<T>>::add_position(pool_id, currency_id, total_collateral, synthetic);
+		Self::add_position(pool_id, currency_id, total_collateral, synthetic);
 
-		Ok(synthetic)
+		Ok((synthetic, fee))
 	}
 
+	/// Redeem `synthetic`. If `Ok`, return with `(collateral, fee)`, and the fee is already
+	/// deducted from `collateral`.
 	fn do_redeem(
 		who: &T::AccountId,
 		pool_id: LiquidityPoolId,
 		currency_id: CurrencyId,
 		synthetic: Balance,
 		min_price: Price,
-	) -> BalanceResult {
-		ensure!(
-			T::SyntheticCurrencyIds::get().contains(&currency_id),
-			Error::<T>::NotValidSyntheticCurrencyId
-		);
+	) -> WithFeeResult {
+		Self::ensure_can_redeem(pool_id, currency_id)?;
 
 		// burn synthetic
 		T::MultiCurrency::withdraw(currency_id, who, synthetic)?;
 
-		let price =
-			T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get()).ok_or(Error::<T>::NoPrice)?;
-		// bid_price = price - bid_spread
-		let bid_price = Self::bid_price(pool_id, currency_id, price, Some(min_price))?;
-
-		// collateral = synthetic * bid_price
-		let redeemed_collateral = bid_price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
+		let (price, _, redeemed_collateral, fee) =
+			Self::redeem_amounts(pool_id, currency_id, synthetic, Some(min_price))?;
 		let (collateral_position_delta, pool_refund_collateral) =
 			Self::collateral_change_on_remove_position(pool_id, currency_id, price, synthetic, redeemed_collateral)?;
+		let redeemed_collateral = redeemed_collateral.checked_sub(fee).ok_or(Error::<T>::NumOverflow)?;
 
 		// redeem collateral
 		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, redeemed_collateral)
 			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		T::CollateralCurrency::transfer(
+			&<SyntheticTokens<T>>::account_id(),
+			&T::GetTreasuryAccountId::get(),
+			fee,
+		)
+		.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
 		T::LiquidityPools::deposit_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, pool_refund_collateral)
 			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
 
-		<SyntheticTokens<T>>::remove_position(pool_id, currency_id, collateral_position_delta, synthetic);
+		Self::remove_position(pool_id, currency_id, collateral_position_delta, synthetic);
+
+		Ok((redeemed_collateral, fee))
+	}
+
+	/// Mint in pools ordered by ask price, each up to its liquidity. If `Ok`, return with
+	/// `(synthetic, fee, fills)`.
+	fn do_routed_mint(
+		who: &T::AccountId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+		max_price: Price,
+		max_pools: u32,
+	) -> RoutedResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		let price = Self::price(currency_id)?;
+		let mut pools = Self::pools_to_route(max_pools)?
+			.into_iter()
+			.filter(|&pool_id| {
+				T::SyntheticProtocolLiquidityPools::can_mint(pool_id, currency_id)
+					&& !Self::is_under_liquidation(pool_id, currency_id)
+					&& !Self::is_in_auction(pool_id, currency_id)
+			})
+			.filter_map(|pool_id| {
+				let ask_spread = T::SyntheticProtocolLiquidityPools::ask_spread(pool_id, currency_id)?;
+				let ask_price = price.checked_add(&ask_spread)?;
+				if ask_price <= max_price {
+					Some((pool_id, ask_price))
+				} else {
+					None
+				}
+			})
+			.collect::<Vec<_>>();
+		// lowest ask price first
+		pools.sort_by(|a, b| a.1.cmp(&b.1));
+
+		let mut remaining = collateral;
+		let mut total_synthetic: Balance = Zero::zero();
+		let mut total_fee: Balance = Zero::zero();
+		let mut fills = Vec::new();
+		for (pool_id, ask_price) in pools {
+			if remaining.is_zero() {
+				break;
+			}
+			let amount = match Self::mint_capacity(pool_id, currency_id, price, ask_price)? {
+				Some(capacity) => remaining.min(capacity),
+				None => remaining,
+			};
+			if amount.is_zero() {
+				continue;
+			}
+
+			let (synthetic, fee) = Self::do_mint(who, pool_id, currency_id, amount, max_price)?;
+			remaining = remaining.saturating_sub(amount);
+			total_synthetic = total_synthetic.checked_add(synthetic).ok_or(Error::<T>::NumOverflow)?;
+			total_fee = total_fee.checked_add(fee).ok_or(Error::<T>::NumOverflow)?;
+			fills.push((pool_id, amount, synthetic));
+		}
+		ensure!(remaining.is_zero(), Error::<T>::InsufficientLiquidityInPools);
+
+		Ok((total_synthetic, total_fee, fills))
+	}
+
+	/// Redeem in pools ordered by bid price, each up to its position. If `Ok`, return with
+	/// `(collateral, fee, fills)`, and the fee is already deducted from `collateral`.
+	fn do_routed_redeem(
+		who: &T::AccountId,
+		currency_id: CurrencyId,
+		synthetic: Balance,
+		min_price: Price,
+		max_pools: u32,
+	) -> RoutedResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		let price = Self::price(currency_id)?;
+		let mut pools = Self::pools_to_route(max_pools)?
+			.into_iter()
+			.filter(|&pool_id| !Self::is_in_auction(pool_id, currency_id))
+			.filter_map(|pool_id| {
+				let bid_spread = T::SyntheticProtocolLiquidityPools::bid_spread(pool_id, currency_id)?;
+				let bid_price = price.checked_sub(&bid_spread)?;
+				if bid_price >= min_price {
+					Some((pool_id, bid_price))
+				} else {
+					None
+				}
+			})
+			.collect::<Vec<_>>();
+		// highest bid price first
+		pools.sort_by(|a, b| b.1.cmp(&a.1));
+
+		let mut remaining = synthetic;
+		let mut total_collateral: Balance = Zero::zero();
+		let mut total_fee: Balance = Zero::zero();
+		let mut fills = Vec::new();
+		for (pool_id, bid_price) in pools {
+			if remaining.is_zero() {
+				break;
+			}
+			let amount = remaining.min(Self::redeem_capacity(pool_id, currency_id, bid_price)?);
+			if amount.is_zero() {
+				continue;
+			}
+
+			let (collateral, fee) = Self::do_redeem(who, pool_id, currency_id, amount, min_price)?;
+			remaining = remaining.saturating_sub(amount);
+			total_collateral = total_collateral
+				.checked_add(collateral)
+				.ok_or(Error::<T>::NumOverflow)?;
+			total_fee = total_fee.checked_add(fee).ok_or(Error::<T>::NumOverflow)?;
+			fills.push((pool_id, collateral, amount));
+		}
+		ensure!(remaining.is_zero(), Error::<T>::InsufficientSyntheticInPools);
+
+		Ok((total_collateral, total_fee, fills))
+	}
+
+	/// Swap `from_amount` of `from_currency_id` to `to_currency_id`. `from_amount` is redeemed at
+	/// the bid price of `from_pool_id` with the redeem fee of `from_currency_id` deducted, and the
+	/// collateral is moved from the `from_currency_id` position to the `to_currency_id` position
+	/// directly, minted at the ask price of `to_pool_id` with the mint fee of `to_currency_id`
+	/// deducted. If `Ok`, return with `(to_amount, fee)`, and `fee` includes both fees.
+	///
+	/// to_amount = (from_amount * from_bid_price - redeem_fee - mint_fee) / to_ask_price
+	fn do_swap(
+		who: &T::AccountId,
+		from_pool_id: LiquidityPoolId,
+		from_currency_id: CurrencyId,
+		from_amount: Balance,
+		to_pool_id: LiquidityPoolId,
+		to_currency_id: CurrencyId,
+		min_to_amount: Balance,
+	) -> WithFeeResult {
+		Self::ensure_can_redeem(from_pool_id, from_currency_id)?;
+		Self::ensure_can_mint(to_pool_id, to_currency_id)?;
+
+		let (from_price, _, redeemed_collateral, redeem_fee) =
+			Self::redeem_amounts(from_pool_id, from_currency_id, from_amount, None)?;
+		let to_price = Self::price(to_currency_id)?;
+		let to_ask_price = Self::ask_price(to_pool_id, to_currency_id, to_price, Price::max_value())?;
+
+		let collateral = redeemed_collateral
+			.checked_sub(redeem_fee)
+			.ok_or(Error::<T>::NumOverflow)?;
+		// mint_fee = collateral * mint_fee_rate
+		let mint_fee = Self::mint_fee_rates(to_currency_id).mul_floor(collateral);
+		let collateral = collateral.checked_sub(mint_fee).ok_or(Error::<T>::NumOverflow)?;
+		// to_amount = collateral / to_ask_price
+		let to_amount = Price::from_inner(collateral)
+			.checked_div(&to_ask_price)
+			.map(|x| x.into_inner())
+			.ok_or(Error::<T>::NumOverflow)?;
+		ensure!(to_amount >= min_to_amount, Error::<T>::SwapAmountTooLow);
+		let fee = redeem_fee.checked_add(mint_fee).ok_or(Error::<T>::NumOverflow)?;
+
+		// burn `from_currency_id`, and release its collateral
+		T::MultiCurrency::withdraw(from_currency_id, who, from_amount)?;
+		let (collateral_position_delta, pool_refund_collateral) = Self::collateral_change_on_remove_position(
+			from_pool_id,
+			from_currency_id,
+			from_price,
+			from_amount,
+			redeemed_collateral,
+		)?;
+		T::CollateralCurrency::transfer(
+			&<SyntheticTokens<T>>::account_id(),
+			&T::GetTreasuryAccountId::get(),
+			fee,
+		)
+		.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		T::LiquidityPools::deposit_liquidity(
+			&<SyntheticTokens<T>>::account_id(),
+			from_pool_id,
+			pool_refund_collateral,
+		)
+		.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		Self::remove_position(from_pool_id, from_currency_id, collateral_position_delta, from_amount);
+
+		// collateralise `to_currency_id` with released collateral
+		let synthetic_value = to_price.checked_mul_int(to_amount).ok_or(Error::<T>::NumOverflow)?;
+		let additional_collateral =
+			Self::additional_collateral_amount(to_pool_id, to_currency_id, collateral, synthetic_value)?;
+		T::LiquidityPools::withdraw_liquidity(&<SyntheticTokens<T>>::account_id(), to_pool_id, additional_collateral)
+			.map_err(|_| Error::<T>::InsufficientLiquidityInPool)?;
+		let total_collateral = collateral
+			.checked_add(additional_collateral)
+			.ok_or(Error::<T>::NumOverflow)?;
+		Self::add_position(to_pool_id, to_currency_id, total_collateral, to_amount);
+
+		// mint `to_currency_id`
+		T::MultiCurrency::deposit(to_currency_id, who, to_amount)?;
 
-		Ok(redeemed_collateral)
+		Ok((to_amount, fee))
 	}
 
 	fn do_liquidate(
@@ -324,77 +1159,637 @@ impl<T: Config> Module<T> {
 			T::SyntheticCurrencyIds::get().contains(&currency_id),
 			Error::<T>::NotValidSyntheticCurrencyId
 		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
 
-		let price =
-			T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get()).ok_or(Error::<T>::NoPrice)?;
+		let price = Self::price(currency_id)?;
 		let bid_price = Self::bid_price(pool_id, currency_id, price, None)?;
 		// collateral = synthetic * bid_price
 		let collateral = bid_price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
 
+		// extra collateral included
+		ensure!(
+			!Self::is_safe_position(pool_id, currency_id)?,
+			Error::<T>::StillInSafePosition
+		);
 		let (collateral_position_delta, pool_refund_collateral, incentive) =
 			Self::collateral_change_on_liquidation(pool_id, currency_id, price, synthetic, collateral)?;
 
 		// burn synthetic
-		T::MultiCurrency::withdraw(currency_id, who, synthetic)?;
+		T::MultiCurrency::withdraw(currency_id, who, synthetic)?;
+
+		// Give liquidator collateral and incentive.
+		let collateral_with_incentive = collateral.checked_add(incentive).ok_or(Error::<T>::NumOverflow)?;
+		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, collateral_with_incentive)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		// refund to pool
+		T::LiquidityPools::deposit_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, pool_refund_collateral)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		Self::remove_position(pool_id, currency_id, collateral_position_delta, synthetic);
+
+		Ok(collateral)
+	}
+
+	fn do_add_collateral(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+	) -> DispatchResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+
+		T::LiquidityPools::deposit_liquidity(who, pool_id, collateral)?;
+		T::LiquidityPools::withdraw_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, collateral)?;
+
+		Self::add_position(pool_id, currency_id, collateral, Zero::zero());
+
+		Ok(())
+	}
+
+	fn do_withdraw_collateral(who: &T::AccountId, pool_id: LiquidityPoolId, currency_id: CurrencyId) -> BalanceResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		ensure!(T::LiquidityPools::is_owner(pool_id, who), Error::<T>::NoPermission);
+		ensure!(
+			!Self::is_under_liquidation(pool_id, currency_id),
+			Error::<T>::UnderLiquidation
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+
+		let price = Self::price(currency_id)?;
+		let (collateral_position_delta, pool_refund_collateral) =
+			Self::collateral_change_on_remove_position(pool_id, currency_id, price, Zero::zero(), Zero::zero())?;
+
+		T::LiquidityPools::deposit_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, pool_refund_collateral)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		T::LiquidityPools::withdraw_liquidity(who, pool_id, pool_refund_collateral)?;
+
+		Self::remove_position(pool_id, currency_id, collateral_position_delta, Zero::zero());
+
+		Ok(pool_refund_collateral)
+	}
+
+	fn do_add_extra_collateral(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_currency_id: CurrencyId,
+		amount: Balance,
+	) -> DispatchResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+		ensure!(
+			collateral_currency_id != currency_id,
+			Error::<T>::InvalidCollateralCurrency
+		);
+		ensure!(
+			Self::collateral_haircuts(collateral_currency_id).is_some(),
+			Error::<T>::CollateralCurrencyNotWhitelisted
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+
+		T::MultiCurrency::transfer(collateral_currency_id, who, &<SyntheticTokens<T>>::account_id(), amount)?;
+
+		<SyntheticTokens<T>>::add_extra_collateral(pool_id, currency_id, collateral_currency_id, amount);
+
+		Ok(())
+	}
+
+	fn do_withdraw_extra_collateral(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_currency_id: CurrencyId,
+		amount: Balance,
+	) -> DispatchResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		ensure!(T::LiquidityPools::is_owner(pool_id, who), Error::<T>::NoPermission);
+		ensure!(
+			!Self::is_under_liquidation(pool_id, currency_id),
+			Error::<T>::UnderLiquidation
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+		ensure!(
+			amount <= <SyntheticTokens<T>>::get_extra_collateral(pool_id, currency_id, collateral_currency_id),
+			Error::<T>::InsufficientCollateralInPosition
+		);
+
+		<SyntheticTokens<T>>::remove_extra_collateral(pool_id, currency_id, collateral_currency_id, amount);
+
+		let (_, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		if !synthetic_position.is_zero() {
+			let price = Self::price(currency_id)?;
+			let required_collateral = Self::required_collateral(currency_id, price, synthetic_position)?;
+			let synthetic_value = price
+				.checked_mul_int(synthetic_position)
+				.ok_or(Error::<T>::NumOverflow)?;
+			let total_collateral = Self::counted_collateral(pool_id, currency_id, synthetic_value);
+			ensure!(
+				total_collateral >= required_collateral,
+				Error::<T>::CollateralRatioTooLow
+			);
+		}
+
+		T::MultiCurrency::transfer(collateral_currency_id, &<SyntheticTokens<T>>::account_id(), who, amount)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		Ok(())
+	}
+
+	fn do_vault_mint(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+		ratio: Permill,
+		max_price: Price,
+	) -> BalanceResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+		ensure!(
+			T::SyntheticProtocolLiquidityPools::can_mint(pool_id, currency_id),
+			Error::<T>::CannotMintInPool
+		);
+		ensure!(
+			ratio >= <SyntheticTokens<T>>::collateral_ratio_or_default(currency_id),
+			Error::<T>::CollateralRatioTooLow
+		);
+
+		let price = Self::price(currency_id)?;
+		let ask_price = Self::ask_price(pool_id, currency_id, price, max_price)?;
+
+		// synthetic = collateral / (ask_price * (1 + ratio))
+		let price_with_ratio = ask_price
+			.checked_mul(&Into::<FixedU128>::into(ratio).saturating_add(FixedU128::one()))
+			.ok_or(Error::<T>::NumOverflow)?;
+		let synthetic = Price::from_inner(collateral)
+			.checked_div(&price_with_ratio)
+			.map(|x| x.into_inner())
+			.ok_or(Error::<T>::NumOverflow)?;
+
+		// spread = synthetic * (ask_price - price)
+		let spread = ask_price
+			.saturating_sub(price)
+			.checked_mul_int(synthetic)
+			.ok_or(Error::<T>::NumOverflow)?;
+		let vault_collateral = collateral.checked_sub(spread).ok_or(Error::<T>::NumOverflow)?;
+
+		// pay spread to pool, and collateralise the rest
+		if !spread.is_zero() {
+			T::LiquidityPools::deposit_liquidity(who, pool_id, spread)?;
+		}
+		T::CollateralCurrency::transfer(who, &<SyntheticTokens<T>>::account_id(), vault_collateral)?;
+
+		// mint synthetic
+		T::MultiCurrency::deposit(currency_id, who, synthetic)?;
+
+		<SyntheticTokens<T>>::add_vault(who, pool_id, currency_id, vault_collateral, synthetic);
+
+		Ok(synthetic)
+	}
+
+	fn do_vault_burn(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic: Balance,
+	) -> BalanceResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_vault(who, pool_id, currency_id);
+		ensure!(
+			synthetic <= synthetic_position,
+			Error::<T>::InsufficientSyntheticInPosition
+		);
+
+		// burn synthetic
+		T::MultiCurrency::withdraw(currency_id, who, synthetic)?;
+
+		// released_collateral = collateral_position * synthetic / synthetic_position
+		let released_collateral = if synthetic == synthetic_position {
+			collateral_position
+		} else {
+			FixedU128::checked_from_rational(synthetic, synthetic_position)
+				.and_then(|r| r.checked_mul_int(collateral_position))
+				.ok_or(Error::<T>::NumOverflow)?
+		};
+
+		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, released_collateral)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		<SyntheticTokens<T>>::remove_vault(who, pool_id, currency_id, released_collateral, synthetic);
+
+		Ok(released_collateral)
+	}
+
+	fn do_vault_add_collateral(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+	) -> DispatchResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		T::CollateralCurrency::transfer(who, &<SyntheticTokens<T>>::account_id(), collateral)?;
+
+		<SyntheticTokens<T>>::add_vault(who, pool_id, currency_id, collateral, Zero::zero());
+
+		Ok(())
+	}
+
+	fn do_vault_withdraw_collateral(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+	) -> DispatchResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_vault(who, pool_id, currency_id);
+		let new_collateral_position = collateral_position
+			.checked_sub(collateral)
+			.ok_or(Error::<T>::InsufficientCollateralInPosition)?;
+
+		if !synthetic_position.is_zero() {
+			let price = Self::price(currency_id)?;
+			let required_collateral = Self::required_collateral(currency_id, price, synthetic_position)?;
+			ensure!(
+				new_collateral_position >= required_collateral,
+				Error::<T>::CollateralRatioTooLow
+			);
+		}
+
+		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, collateral)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		<SyntheticTokens<T>>::remove_vault(who, pool_id, currency_id, collateral, Zero::zero());
+
+		Ok(())
+	}
+
+	fn do_vault_liquidate(
+		who: &T::AccountId,
+		owner: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic: Balance,
+	) -> BalanceResult {
+		ensure!(
+			T::SyntheticCurrencyIds::get().contains(&currency_id),
+			Error::<T>::NotValidSyntheticCurrencyId
+		);
+
+		let price = Self::price(currency_id)?;
+		let bid_price = Self::bid_price(pool_id, currency_id, price, None)?;
+		// collateral = synthetic * bid_price
+		let collateral = bid_price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
+
+		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_vault(owner, pool_id, currency_id);
+		// the refund part is kept in vault
+		let (_, incentive) = Self::refund_and_incentive_on_liquidation(
+			currency_id,
+			price,
+			collateral_position,
+			synthetic_position,
+			synthetic,
+			collateral,
+		)?;
+
+		// burn synthetic
+		T::MultiCurrency::withdraw(currency_id, who, synthetic)?;
+
+		// Give liquidator collateral and incentive.
+		let collateral_with_incentive = collateral.checked_add(incentive).ok_or(Error::<T>::NumOverflow)?;
+		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, collateral_with_incentive)
+			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+
+		<SyntheticTokens<T>>::remove_vault(owner, pool_id, currency_id, collateral_with_incentive, synthetic);
+
+		Ok(collateral)
+	}
+
+	fn do_force_add_collateral(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> BalanceResult {
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+		ensure!(
+			!Self::is_safe_position(pool_id, currency_id)?,
+			Error::<T>::StillInSafePosition
+		);
+
+		let collateral = Self::collateral_to_be_safe(pool_id, currency_id)?;
+		T::LiquidityPools::withdraw_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, collateral)
+			.map_err(|_| Error::<T>::InsufficientLiquidityInPool)?;
+
+		Self::add_position(pool_id, currency_id, collateral, Zero::zero());
+		PositionsUnderLiquidation::remove(pool_id, currency_id);
+
+		Ok(collateral)
+	}
+
+	fn do_mark_under_liquidation(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> DispatchResult {
+		ensure!(
+			!Self::is_under_liquidation(pool_id, currency_id),
+			Error::<T>::UnderLiquidation
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+		ensure!(
+			!Self::is_safe_position(pool_id, currency_id)?,
+			Error::<T>::StillInSafePosition
+		);
+
+		PositionsUnderLiquidation::insert(pool_id, currency_id, ());
+
+		Ok(())
+	}
+
+	fn do_position_become_safe(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> DispatchResult {
+		ensure!(
+			Self::is_under_liquidation(pool_id, currency_id),
+			Error::<T>::NotUnderLiquidation
+		);
+		ensure!(
+			Self::is_safe_position(pool_id, currency_id)?,
+			Error::<T>::UnsafePosition
+		);
+
+		PositionsUnderLiquidation::remove(pool_id, currency_id);
+
+		Ok(())
+	}
+
+	/// Start a collateral auction, if `Ok`, return with `(auction_id, collateral, target_synthetic)`.
+	fn do_start_collateral_auction(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+	) -> result::Result<(AuctionIdOf<T>, Balance, Balance), DispatchError> {
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+		ensure!(
+			Self::is_below_extreme_ratio(pool_id, currency_id)?,
+			Error::<T>::AboveExtremeRatio
+		);
+
+		let (collateral, synthetic) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		let now = <frame_system::Module<T>>::block_number();
+		let end = now.saturating_add(T::AuctionDuration::get());
+		let auction_id = T::Auction::new_auction(now, Some(end))?;
+
+		<CollateralAuctions<T>>::insert(
+			auction_id,
+			CollateralAuctionItem {
+				pool_id,
+				currency_id,
+				collateral,
+				target: synthetic,
+			},
+		);
+		<PositionsInAuction<T>>::insert(pool_id, currency_id, auction_id);
+		// the position would be cleared by the auction
+		PositionsUnderLiquidation::remove(pool_id, currency_id);
+
+		Ok((auction_id, collateral, synthetic))
+	}
+
+	/// Release synthetic of `last_bid`, and lock synthetic of `new_bid` in module account.
+	///
+	/// Only synthetic up to the auction target is locked, higher bids are for less collateral.
+	fn do_lock_bid(
+		auction_id: AuctionIdOf<T>,
+		new_bid: &(T::AccountId, Balance),
+		last_bid: Option<(T::AccountId, Balance)>,
+	) -> DispatchResult {
+		let item = Self::collateral_auctions(auction_id).ok_or(Error::<T>::NoCollateralAuction)?;
+		let module_account = <SyntheticTokens<T>>::account_id();
+
+		if let Some((last_bidder, last_amount)) = last_bid {
+			T::MultiCurrency::transfer(
+				item.currency_id,
+				&module_account,
+				&last_bidder,
+				last_amount.min(item.target),
+			)?;
+		}
+		T::MultiCurrency::transfer(
+			item.currency_id,
+			&new_bid.0,
+			&module_account,
+			new_bid.1.min(item.target),
+		)?;
+
+		Ok(())
+	}
+
+	/// Deal a collateral auction: burn synthetic received, give collateral to the winner, and record
+	/// unburned synthetic as system debit.
+	///
+	/// The winner gets `target / bid` of the collateral if bid higher than target, with the surplus
+	/// refunded to pool, or `bid / target` if lower, with the rest backing system debit sent to
+	/// treasury. Extra collaterals are given in the same share, and the rest is kept in the position
+	/// on surplus, or sent to treasury too on deficit.
+	///
+	/// If `Ok`, return with `(collateral_to_winner, burned_synthetic)`.
+	fn do_deal_collateral_auction(
+		item: &CollateralAuctionItem,
+		winner: &T::AccountId,
+		bid: Balance,
+	) -> result::Result<(Balance, Balance), DispatchError> {
+		let module_account = <SyntheticTokens<T>>::account_id();
+		let treasury_account = T::GetTreasuryAccountId::get();
+
+		// burn synthetic
+		let burned_synthetic = bid.min(item.target);
+		T::MultiCurrency::withdraw(item.currency_id, &module_account, burned_synthetic)?;
+		let deficit = item.target.saturating_sub(burned_synthetic);
 
-		// Give liquidator collateral and incentive.
-		let collateral_with_incentive = collateral.checked_add(incentive).ok_or(Error::<T>::NumOverflow)?;
-		T::CollateralCurrency::transfer(&<SyntheticTokens<T>>::account_id(), who, collateral_with_incentive)
+		// winner_share = target / bid if bid > target, or bid / target otherwise
+		let winner_share = if bid > item.target {
+			FixedU128::checked_from_rational(item.target, bid)
+		} else {
+			FixedU128::checked_from_rational(bid, item.target)
+		}
+		.ok_or(Error::<T>::NumOverflow)?;
+		let collateral_to_winner = winner_share
+			.checked_mul_int(item.collateral)
+			.ok_or(Error::<T>::NumOverflow)?;
+		T::CollateralCurrency::transfer(&module_account, winner, collateral_to_winner)
 			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
 
-		// refund to pool
-		T::LiquidityPools::deposit_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, pool_refund_collateral)
-			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		for (collateral_currency_id, amount) in <SyntheticTokens<T>>::extra_collaterals(item.pool_id, item.currency_id)
+		{
+			let to_winner = winner_share.checked_mul_int(amount).ok_or(Error::<T>::NumOverflow)?;
+			T::MultiCurrency::transfer(collateral_currency_id, &module_account, winner, to_winner)
+				.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+			let mut removed = to_winner;
+			if !deficit.is_zero() {
+				let to_treasury = amount.saturating_sub(to_winner);
+				T::MultiCurrency::transfer(collateral_currency_id, &module_account, &treasury_account, to_treasury)
+					.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+				removed = amount;
+			}
+			<SyntheticTokens<T>>::remove_extra_collateral(
+				item.pool_id,
+				item.currency_id,
+				collateral_currency_id,
+				removed,
+			);
+		}
+
+		let rest = item.collateral.saturating_sub(collateral_to_winner);
+		if deficit.is_zero() {
+			// refund surplus to pool
+			T::LiquidityPools::deposit_liquidity(&module_account, item.pool_id, rest)
+				.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+		} else {
+			// record deficit as system debit, backed by the rest collateral
+			T::CollateralCurrency::transfer(&module_account, &treasury_account, rest)
+				.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
+			SystemDebits::mutate(item.currency_id, |d| *d = d.saturating_add(deficit));
+			Self::deposit_event(RawEvent::SystemDebitIncreased(item.currency_id, deficit));
+		}
 
-		<SyntheticTokens<T>>::remove_position(pool_id, currency_id, collateral_position_delta, synthetic);
+		Self::remove_position(item.pool_id, item.currency_id, item.collateral, item.target);
 
-		Ok(collateral)
+		Ok((collateral_to_winner, burned_synthetic))
 	}
+}
 
-	fn do_add_collateral(
-		who: &T::AccountId,
-		pool_id: LiquidityPoolId,
-		currency_id: CurrencyId,
-		collateral: Balance,
-	) -> DispatchResult {
+// Private methods
+impl<T: Config> Module<T> {
+	/// Ensure `currency_id` could be minted in `pool_id`.
+	fn ensure_can_mint(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> DispatchResult {
 		ensure!(
 			T::SyntheticCurrencyIds::get().contains(&currency_id),
 			Error::<T>::NotValidSyntheticCurrencyId
 		);
 
-		T::LiquidityPools::deposit_liquidity(who, pool_id, collateral)?;
-		T::LiquidityPools::withdraw_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, collateral)?;
-
-		<SyntheticTokens<T>>::add_position(pool_id, currency_id, collateral, Zero::zero());
-
+		ensure!(
+			T::SyntheticProtocolLiquidityPools::can_mint(pool_id, currency_id),
+			Error::<T>::CannotMintInPool
+		);
+		ensure!(
+			!Self::is_under_liquidation(pool_id, currency_id),
+			Error::<T>::UnderLiquidation
+		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
 		Ok(())
 	}
 
-	fn do_withdraw_collateral(who: &T::AccountId, pool_id: LiquidityPoolId, currency_id: CurrencyId) -> BalanceResult {
+	/// Ensure `currency_id` could be redeemed in `pool_id`.
+	fn ensure_can_redeem(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> DispatchResult {
 		ensure!(
 			T::SyntheticCurrencyIds::get().contains(&currency_id),
 			Error::<T>::NotValidSyntheticCurrencyId
 		);
+		ensure!(!Self::is_in_auction(pool_id, currency_id), Error::<T>::InAuction);
+		Ok(())
+	}
 
-		ensure!(T::LiquidityPools::is_owner(pool_id, who), Error::<T>::NoPermission);
+	/// Amounts of minting with `collateral` paid, fee included. If `Ok`, return with `(synthetic,
+	/// fee, ask_price, additional_collateral)`.
+	fn mint_amounts(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+		max_price: Price,
+	) -> result::Result<(Balance, Balance, Price, Balance), DispatchError> {
+		let price = Self::price(currency_id)?;
+		let ask_price = Self::ask_price(pool_id, currency_id, price, max_price)?;
 
-		let price =
-			T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get()).ok_or(Error::<T>::NoPrice)?;
-		let (collateral_position_delta, pool_refund_collateral) =
-			Self::collateral_change_on_remove_position(pool_id, currency_id, price, Zero::zero(), Zero::zero())?;
+		// fee = collateral * mint_fee_rate
+		let fee = Self::mint_fee_rates(currency_id).mul_floor(collateral);
+		let collateral = collateral.checked_sub(fee).ok_or(Error::<T>::NumOverflow)?;
 
-		T::LiquidityPools::deposit_liquidity(&<SyntheticTokens<T>>::account_id(), pool_id, pool_refund_collateral)
-			.map_err(|_| Error::<T>::InsufficientLockedCollateral)?;
-		T::LiquidityPools::withdraw_liquidity(who, pool_id, pool_refund_collateral)?;
+		// synthetic = collateral / ask_price
+		let synthetic = Price::from_inner(collateral)
+			.checked_div(&ask_price)
+			.map(|x| x.into_inner())
+			.ok_or(Error::<T>::NumOverflow)?;
 
-		<SyntheticTokens<T>>::remove_position(pool_id, currency_id, collateral_position_delta, Zero::zero());
+		// synthetic_value = synthetic * price
+		// `synthetic_value` is how much `synthetic` values in collateral unit.
+		let synthetic_value = price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
+		// additional_collateral = synthetic_value * (1 + ratio) - collateral
+		let additional_collateral =
+			Self::additional_collateral_amount(pool_id, currency_id, collateral, synthetic_value)?;
 
-		Ok(pool_refund_collateral)
+		Ok((synthetic, fee, ask_price, additional_collateral))
+	}
+
+	/// Amounts of redeeming `synthetic`. If `Ok`, return with `(price, bid_price, collateral,
+	/// fee)`, and the fee is not deducted from `collateral` yet.
+	fn redeem_amounts(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic: Balance,
+		min_price: Option<Price>,
+	) -> result::Result<(Price, Price, Balance, Balance), DispatchError> {
+		let price = Self::price(currency_id)?;
+		// bid_price = price - bid_spread
+		let bid_price = Self::bid_price(pool_id, currency_id, price, min_price)?;
+
+		// collateral = synthetic * bid_price
+		let collateral = bid_price.checked_mul_int(synthetic).ok_or(Error::<T>::NumOverflow)?;
+
+		// fee = collateral * redeem_fee_rate
+		let fee = Self::redeem_fee_rates(currency_id).mul_floor(collateral);
+
+		Ok((price, bid_price, collateral, fee))
+	}
+
+	/// The price of `currency_id` in collateral currency from oracle, or from risk price provider
+	/// in risk checks. Would fail if any of the two prices is stale or unreliable for current price
+	/// usage.
+	fn price(currency_id: CurrencyId) -> result::Result<Price, DispatchError> {
+		Self::ensure_price_usable(currency_id)?;
+		let collateral_currency_id = T::GetCollateralCurrencyId::get();
+		let price = if is_risk_pricing() {
+			T::RiskPriceProvider::get_price(currency_id, collateral_currency_id)
+		} else {
+			T::PriceProvider::get_price(currency_id, collateral_currency_id)
+		};
+		price.ok_or_else(|| Error::<T>::NoPrice.into())
+	}
+
+	/// Ensure neither `currency_id` nor collateral currency price is stale or unreliable for
+	/// current price usage.
+	fn ensure_price_usable(currency_id: CurrencyId) -> DispatchResult {
+		let usage = price_usage();
+		let collateral_currency_id = T::GetCollateralCurrencyId::get();
+		ensure!(
+			!T::PriceGuard::is_stale(currency_id, usage) && !T::PriceGuard::is_stale(collateral_currency_id, usage),
+			Error::<T>::StalePrice
+		);
+		ensure!(
+			!T::PriceGuard::is_unreliable(currency_id, usage)
+				&& !T::PriceGuard::is_unreliable(collateral_currency_id, usage),
+			Error::<T>::UnreliablePrice
+		);
+		Ok(())
 	}
-}
 
-// Private methods
-impl<T: Config> Module<T> {
 	/// Get ask price from liquidity pool for a given currency. Would fail if price could not meet
 	/// max slippage.
 	///
@@ -433,6 +1828,248 @@ impl<T: Config> Module<T> {
 		Ok(bid_price)
 	}
 
+	/// Returns collateral required by collateral ratio of `currency_id`.
+	///
+	/// required_collateral = synthetic * price * (1 + collateral_ratio)
+	fn required_collateral(currency_id: CurrencyId, price: Price, synthetic: Balance) -> BalanceResult {
+		let collateral_ratio = <SyntheticTokens<T>>::collateral_ratio_or_default(currency_id);
+		price
+			.checked_mul_int(synthetic)
+			.and_then(|v| v.checked_add(collateral_ratio * v))
+			.ok_or_else(|| Error::<T>::NumOverflow.into())
+	}
+
+	/// Returns collateral needed to restore `currency_id` position of `pool_id` to collateral
+	/// ratio. The collateral position must cover synthetic value by itself, before extra
+	/// collaterals are counted.
+	///
+	/// collateral_to_be_safe = max(required_collateral - collateral - extra_collateral_value,
+	/// synthetic_value - collateral)
+	fn collateral_to_be_safe(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> BalanceResult {
+		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		let price = Self::price(currency_id)?;
+		let required_collateral = Self::required_collateral(currency_id, price, synthetic_position)?;
+		let synthetic_value = price
+			.checked_mul_int(synthetic_position)
+			.ok_or(Error::<T>::NumOverflow)?;
+		let collateral = collateral_position.saturating_sub(Self::accrued_stability_fee(pool_id, currency_id));
+		let with_extra_collateral = collateral.saturating_add(Self::extra_collateral_value(pool_id, currency_id));
+		Ok(required_collateral
+			.saturating_sub(with_extra_collateral)
+			.max(synthetic_value.saturating_sub(collateral)))
+	}
+
+	/// Collateral of `currency_id` position in `pool_id` counted in collateral ratio, accrued
+	/// stability fee deducted.
+	///
+	/// Redeeming is only paid from the collateral position, so extra collaterals are counted only
+	/// if the collateral position alone covers `synthetic_value`.
+	fn counted_collateral(pool_id: LiquidityPoolId, currency_id: CurrencyId, synthetic_value: Balance) -> Balance {
+		let (collateral_position, _) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		let collateral = collateral_position.saturating_sub(Self::accrued_stability_fee(pool_id, currency_id));
+		if collateral < synthetic_value {
+			collateral
+		} else {
+			collateral.saturating_add(Self::extra_collateral_value(pool_id, currency_id))
+		}
+	}
+
+	/// Value of extra collaterals of `currency_id` in `pool_id`, after haircut. Collaterals not
+	/// whitelisted or without usable price are not counted.
+	///
+	/// extra_collateral_value = sum(amount * price * (1 - haircut))
+	fn extra_collateral_value(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Balance {
+		<SyntheticTokens<T>>::extra_collaterals(pool_id, currency_id)
+			.into_iter()
+			.filter_map(|(collateral_currency_id, amount)| {
+				let haircut = Self::collateral_haircuts(collateral_currency_id)?;
+				let price = Self::price(collateral_currency_id).ok()?;
+				let value = price.checked_mul_int(amount)?;
+				Some(value.saturating_sub(haircut * value))
+			})
+			.fold(Zero::zero(), |total: Balance, value| total.saturating_add(value))
+	}
+
+	/// Stability fee of `currency_id` position in `pool_id` accrued since last accrual, not recorded
+	/// in `UnpaidStabilityFees` yet. Positions never accrued before start accruing from now on.
+	///
+	/// stability_fee = synthetic * price * rate * elapsed / ONE_YEAR
+	fn stability_fee(pool_id: LiquidityPoolId, currency_id: CurrencyId, now: u64) -> Balance {
+		let rate = Self::stability_fee_rates(currency_id);
+		let last = Self::last_stability_fee_accrual_time(pool_id, currency_id);
+		let elapsed = now.saturating_sub(last);
+		if rate.is_zero() || last.is_zero() || elapsed.is_zero() {
+			return Zero::zero();
+		}
+
+		let (_, synthetic) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get())
+			.and_then(|price| price.checked_mul_int(synthetic))
+			.and_then(|value| {
+				FixedU128::checked_from_rational(elapsed, ONE_YEAR).and_then(|t| t.checked_mul_int(rate * value))
+			})
+			.unwrap_or_default()
+	}
+
+	/// Accrue stability fee of `currency_id` position in `pool_id` up to now into
+	/// `UnpaidStabilityFees`. Must be called before the synthetic position changes, so that fees are
+	/// accrued on the position size actually held.
+	fn accrue_stability_fee(pool_id: LiquidityPoolId, currency_id: CurrencyId) {
+		let now = T::UnixTime::now().as_secs();
+		let fee = Self::stability_fee(pool_id, currency_id, now);
+		if !fee.is_zero() {
+			UnpaidStabilityFees::mutate(pool_id, currency_id, |unpaid| *unpaid = unpaid.saturating_add(fee));
+		}
+		LastStabilityFeeAccrualTimes::insert(pool_id, currency_id, now);
+	}
+
+	/// Add collateral and synthetic to `currency_id` position of `pool_id`, stability fee accrued
+	/// before the change.
+	fn add_position(pool_id: LiquidityPoolId, currency_id: CurrencyId, collateral: Balance, synthetic: Balance) {
+		Self::accrue_stability_fee(pool_id, currency_id);
+		<SyntheticTokens<T>>::add_position(pool_id, currency_id, collateral, synthetic);
+	}
+
+	/// Remove collateral and synthetic from `currency_id` position of `pool_id`, stability fee
+	/// accrued before the change.
+	fn remove_position(pool_id: LiquidityPoolId, currency_id: CurrencyId, collateral: Balance, synthetic: Balance) {
+		Self::accrue_stability_fee(pool_id, currency_id);
+		<SyntheticTokens<T>>::remove_position(pool_id, currency_id, collateral, synthetic);
+	}
+
+	/// Charge stability fees of pool positions from pool liquidity to treasury, starting from
+	/// `cursor`. Fees that can't be paid are left unpaid. At most `MaxStabilityFeeChecksPerBlock`
+	/// positions are checked, and the cursor is kept for later blocks if not finished.
+	///
+	/// Returns `(pools_count, checked_count)`.
+	fn charge_stability_fees(cursor: StabilityFeeCursor) -> (u32, u32) {
+		let mut pools = T::LiquidityPools::all();
+		pools.sort();
+		let pools_count = pools.len() as u32;
+		let currency_ids = T::SyntheticCurrencyIds::get();
+		let max_checks = T::MaxStabilityFeeChecksPerBlock::get();
+
+		let mut checked_count = 0;
+		for pool_id in pools.into_iter().filter(|&pool_id| pool_id >= cursor.pool_id) {
+			let start = if pool_id == cursor.pool_id {
+				cursor.currency_index as usize
+			} else {
+				0
+			};
+			for (index, &currency_id) in currency_ids.iter().enumerate().skip(start) {
+				if checked_count >= max_checks {
+					StabilityFeeCharging::put(StabilityFeeCursor {
+						pool_id,
+						currency_index: index as u32,
+						..cursor
+					});
+					return (pools_count, checked_count);
+				}
+				checked_count += 1;
+				Self::charge_stability_fee(pool_id, currency_id, cursor.time);
+			}
+		}
+
+		StabilityFeeCharging::kill();
+		(pools_count, checked_count)
+	}
+
+	/// Charge stability fee of `currency_id` position in `pool_id`, accrued up to now, unpaid fees
+	/// included.
+	fn charge_stability_fee(pool_id: LiquidityPoolId, currency_id: CurrencyId, now: u64) {
+		Self::accrue_stability_fee(pool_id, currency_id);
+		let fee = Self::unpaid_stability_fees(pool_id, currency_id);
+		if fee.is_zero() {
+			return;
+		}
+
+		let mut charged = fee.min(T::LiquidityPools::liquidity(pool_id));
+		if !charged.is_zero()
+			&& T::LiquidityPools::withdraw_liquidity(&T::GetTreasuryAccountId::get(), pool_id, charged).is_err()
+		{
+			charged = Zero::zero();
+		}
+		let unpaid = fee.saturating_sub(charged);
+		if unpaid.is_zero() {
+			UnpaidStabilityFees::remove(pool_id, currency_id);
+		} else {
+			UnpaidStabilityFees::insert(pool_id, currency_id, unpaid);
+		}
+
+		StabilityFeeHistory::mutate(pool_id, currency_id, |history| {
+			history.push((now, charged));
+			let len = T::StabilityFeeHistoryLength::get() as usize;
+			if history.len() > len {
+				history.drain(..history.len() - len);
+			}
+		});
+
+		Self::deposit_event(RawEvent::StabilityFeeCharged(currency_id, pool_id, charged, unpaid));
+	}
+
+	/// All pools to route minting or redeeming through. Would fail if more than `max_pools`.
+	fn pools_to_route(max_pools: u32) -> result::Result<Vec<LiquidityPoolId>, DispatchError> {
+		let pools = T::LiquidityPools::all();
+		ensure!(pools.len() <= max_pools as usize, Error::<T>::TooManyPools);
+		Ok(pools)
+	}
+
+	/// Max collateral could be minted in `pool_id`, fee included, limited by its liquidity. `None`
+	/// if the pool doesn't need additional collateral.
+	///
+	/// capacity = liquidity / (price / ask_price * (1 + ratio) - 1) / (1 - mint_fee_rate)
+	fn mint_capacity(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		price: Price,
+		ask_price: Price,
+	) -> result::Result<Option<Balance>, DispatchError> {
+		let ratio = T::SyntheticProtocolLiquidityPools::additional_collateral_ratio(pool_id, currency_id);
+		let with_ratio = price
+			.checked_div(&ask_price)
+			.and_then(|p| p.checked_mul(&Into::<FixedU128>::into(ratio).saturating_add(FixedU128::one())))
+			.ok_or(Error::<T>::NumOverflow)?;
+		if with_ratio <= FixedU128::one() {
+			return Ok(None);
+		}
+
+		let additional_rate = with_ratio.saturating_sub(FixedU128::one());
+		let without_fee_rate = Permill::one().saturating_sub(Self::mint_fee_rates(currency_id));
+		if without_fee_rate.is_zero() {
+			return Ok(Some(Zero::zero()));
+		}
+
+		// the fee is deducted before collateralising, as in `mint_amounts`
+		Price::from_inner(T::LiquidityPools::liquidity(pool_id))
+			.checked_div(&additional_rate)
+			.and_then(|x| x.checked_div(&without_fee_rate.into()))
+			.map(|x| Some(x.into_inner()))
+			.ok_or_else(|| Error::<T>::NumOverflow.into())
+	}
+
+	/// Max synthetic could be redeemed in `pool_id`, limited by its position.
+	///
+	/// capacity = min(synthetic_position, collateral_position / bid_price)
+	fn redeem_capacity(pool_id: LiquidityPoolId, currency_id: CurrencyId, bid_price: Price) -> BalanceResult {
+		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		if bid_price.is_zero() {
+			return Ok(synthetic_position);
+		}
+		let by_collateral = Price::from_inner(collateral_position)
+			.checked_div(&bid_price)
+			.map(|x| x.into_inner())
+			.ok_or(Error::<T>::NumOverflow)?;
+		Ok(synthetic_position.min(by_collateral))
+	}
+
+	fn is_under_liquidation(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> bool {
+		PositionsUnderLiquidation::contains_key(pool_id, currency_id)
+	}
+
+	fn is_in_auction(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> bool {
+		<PositionsInAuction<T>>::contains_key(pool_id, currency_id)
+	}
+
 	/// Calculate liquidity provider's collateral parts:
 	///
 	/// synthetic_value * (1 + ratio) - collateral
@@ -514,7 +2151,35 @@ impl<T: Config> Module<T> {
 		liquidized_collateral: Balance,
 	) -> result::Result<(Balance, Balance, Balance), DispatchError> {
 		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		let (pool_refund_collateral, incentive) = Self::refund_and_incentive_on_liquidation(
+			currency_id,
+			price,
+			collateral_position,
+			synthetic_position,
+			burned_synthetic,
+			liquidized_collateral,
+		)?;
+
+		// collateral_with_incentive_and_refund = liquidized_collateral + incentive + pool_refund_collateral
+		let collateral_with_incentive_and_refund = liquidized_collateral
+			.checked_add(incentive)
+			.and_then(|v| v.checked_add(pool_refund_collateral))
+			.ok_or(Error::<T>::NumOverflow)?;
+		Ok((collateral_with_incentive_and_refund, pool_refund_collateral, incentive))
+	}
 
+	/// Calculate refund and incentive for liquidating a position of `(collateral_position,
+	/// synthetic_position)`.
+	///
+	/// If `Ok`, return with `(refund_collateral, incentive)`
+	fn refund_and_incentive_on_liquidation(
+		currency_id: CurrencyId,
+		price: Price,
+		collateral_position: Balance,
+		synthetic_position: Balance,
+		burned_synthetic: Balance,
+		liquidized_collateral: Balance,
+	) -> result::Result<(Balance, Balance), DispatchError> {
 		let new_synthetic_position = synthetic_position
 			.checked_sub(burned_synthetic)
 			.ok_or(Error::<T>::InsufficientSyntheticInPosition)?;
@@ -528,7 +2193,7 @@ impl<T: Config> Module<T> {
 			.ok_or(Error::<T>::NumOverflow)?;
 		// if synthetic position not backed by enough collateral, no incentive
 		if collateral_position <= synthetic_position_value {
-			return Ok((liquidized_collateral, Zero::zero(), Zero::zero()));
+			return Ok((Zero::zero(), Zero::zero()));
 		}
 
 		// current_ratio = collateral_position / synthetic_position_value
@@ -558,35 +2223,146 @@ impl<T: Config> Module<T> {
 				.checked_mul_int(available_for_incentive)
 				.ok_or(Error::<T>::NumOverflow)?;
 
-			// pool_refund_collateral = available_for_incentive - incentive
-			let pool_refund_collateral = available_for_incentive
+			// refund_collateral = available_for_incentive - incentive
+			let refund_collateral = available_for_incentive
 				.checked_sub(incentive)
 				.expect("available_for_incentive > incentive; qed");
 
-			// collateral_with_incentive_and_refund = liquidized_collateral + incentive + pool_refund_collateral
-			let collateral_with_incentive_and_refund = liquidized_collateral
-				.checked_add(incentive)
-				.and_then(|v| v.checked_add(pool_refund_collateral))
-				.ok_or(Error::<T>::NumOverflow)?;
-			Ok((collateral_with_incentive_and_refund, pool_refund_collateral, incentive))
+			Ok((refund_collateral, incentive))
 		} else {
 			// no more incentive could be given
-			Ok((liquidized_collateral, Zero::zero(), Zero::zero()))
+			Ok((Zero::zero(), Zero::zero()))
 		}
 	}
+
+	/// Collateral ratio of the `currency_id` in `pool_id` with `price`.
+	fn collateral_ratio_with_price(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		price: Price,
+	) -> Option<FixedU128> {
+		let (_, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		let synthetic_position_value = price.checked_mul_int(synthetic_position)?;
+		let total_collateral = Self::counted_collateral(pool_id, currency_id, synthetic_position_value);
+
+		Some(FixedU128::checked_from_rational(total_collateral, synthetic_position_value).unwrap_or_default())
+	}
+
+	/// Collateral ratio of the `currency_id` in `pool_id` for risk checks, extra collaterals priced
+	/// for risk checks too.
+	fn risk_collateral_ratio(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+	) -> result::Result<FixedU128, DispatchError> {
+		with_risk_prices(|| {
+			let price = Self::price(currency_id)?;
+			Self::collateral_ratio_with_price(pool_id, currency_id, price).ok_or_else(|| Error::<T>::NumOverflow.into())
+		})
+	}
 }
 
 // RPC methods.
 impl<T: Config> Module<T> {
+	/// Quote of minting `currency_id` in `pool_id` with `collateral` paid, fee included.
+	pub fn mint_quote(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+	) -> result::Result<Quote, DispatchError> {
+		Self::ensure_can_mint(pool_id, currency_id)?;
+
+		let (synthetic, fee, ask_price, additional_collateral) =
+			Self::mint_amounts(pool_id, currency_id, collateral, Price::max_value())?;
+		Ok(Quote {
+			price: ask_price,
+			amount: synthetic,
+			fee,
+			pool_collateral: additional_collateral,
+			is_sufficient: T::LiquidityPools::liquidity(pool_id) >= additional_collateral,
+		})
+	}
+
+	/// Quote of redeeming `synthetic` of `currency_id` in `pool_id`.
+	pub fn redeem_quote(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		synthetic: Balance,
+	) -> result::Result<Quote, DispatchError> {
+		Self::ensure_can_redeem(pool_id, currency_id)?;
+
+		let (price, bid_price, collateral, fee) = Self::redeem_amounts(pool_id, currency_id, synthetic, None)?;
+		let pool_refund_collateral =
+			Self::collateral_change_on_remove_position(pool_id, currency_id, price, synthetic, collateral)
+				.ok()
+				.map(|(_, refund)| refund);
+		Ok(Quote {
+			price: bid_price,
+			amount: collateral.checked_sub(fee).ok_or(Error::<T>::NumOverflow)?,
+			fee,
+			pool_collateral: pool_refund_collateral.unwrap_or_default(),
+			is_sufficient: pool_refund_collateral.is_some(),
+		})
+	}
+
 	/// Collateral ratio of the `currency_id` in `pool_id`.
 	///
-	/// collateral_ratio = collateral_position / (synthetic_position * price)
+	/// collateral_ratio = (collateral_position - accrued_stability_fee + extra_collateral_value) /
+	/// (synthetic_position * price)
+	///
+	/// `extra_collateral_value` is not counted if the collateral position, accrued stability fee
+	/// deducted, is lower than synthetic value.
 	pub fn collateral_ratio(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<FixedU128> {
-		let (collateral_position, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
 		let price = T::PriceProvider::get_price(currency_id, T::GetCollateralCurrencyId::get())?;
-		let synthetic_position_value = price.checked_mul_int(synthetic_position)?;
+		Self::collateral_ratio_with_price(pool_id, currency_id, price)
+	}
+
+	/// Stability fee of `currency_id` in `pool_id` not paid yet, including unpaid fees and fees
+	/// accrued since last accrual.
+	pub fn accrued_stability_fee(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Balance {
+		Self::stability_fee(pool_id, currency_id, T::UnixTime::now().as_secs())
+			.saturating_add(Self::unpaid_stability_fees(pool_id, currency_id))
+	}
+
+	/// Collateral ratio of `who`'s vault of `currency_id` in `pool_id`, with the price for risk
+	/// checks.
+	///
+	/// collateral_ratio = collateral / (synthetic * price)
+	pub fn vault_collateral_ratio(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+	) -> Option<FixedU128> {
+		let (collateral, synthetic) = <SyntheticTokens<T>>::get_vault(who, pool_id, currency_id);
+		let price = with_risk_prices(|| Self::price(currency_id)).ok()?;
+		let synthetic_value = price.checked_mul_int(synthetic)?;
 
-		Some(FixedU128::checked_from_rational(collateral_position, synthetic_position_value).unwrap_or_default())
+		Some(FixedU128::checked_from_rational(collateral, synthetic_value).unwrap_or_default())
+	}
+
+	/// Check if `currency_id` position of `pool_id` is safe or not. Positions without synthetic
+	/// are always safe.
+	pub fn is_safe_position(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> result::Result<bool, DispatchError> {
+		let (_, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		if synthetic_position.is_zero() {
+			return Ok(true);
+		}
+		let ratio = Self::risk_collateral_ratio(pool_id, currency_id)?;
+		Ok(Self::is_safe_collateral_ratio(currency_id, ratio))
+	}
+
+	/// Check if `currency_id` position of `pool_id` is below extreme ratio. Positions without
+	/// synthetic are never below.
+	pub fn is_below_extreme_ratio(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+	) -> result::Result<bool, DispatchError> {
+		let (_, synthetic_position) = <SyntheticTokens<T>>::get_position(pool_id, currency_id);
+		if synthetic_position.is_zero() {
+			return Ok(false);
+		}
+		let ratio = Self::risk_collateral_ratio(pool_id, currency_id)?;
+		let extreme_ratio = <SyntheticTokens<T>>::extreme_ratio_or_default(currency_id);
+		Ok(ratio <= Into::<FixedU128>::into(extreme_ratio).saturating_add(FixedU128::one()))
 	}
 
 	/// Check if a given collateral `ratio` of `currency_id` is safe or not.
@@ -596,3 +2372,283 @@ impl<T: Config> Module<T> {
 		ratio > safe_ratio_threshold
 	}
 }
+
+/// Error which may occur while executing the off-chain code.
+#[cfg_attr(test, derive(PartialEq))]
+enum OffchainErr {
+	OffchainLock,
+	SubmitTransaction,
+	NotValidator,
+	CheckFail,
+}
+
+// constant for offchain worker
+const LOCK_DURATION: u64 = 40_000; // 40 sec
+const OFFCHAIN_WORKER_LOCK: &[u8] = b"laminar/synthetic-protocol/offchain-worker-lock";
+#[cfg(feature = "std")]
+const TAG: &str = "SYNTHETIC_PROTOCOL_OFFCHAIN_WORKER";
+
+impl sp_std::fmt::Debug for OffchainErr {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		match *self {
+			OffchainErr::OffchainLock => write!(fmt, "Failed to get or extend lock"),
+			OffchainErr::SubmitTransaction => write!(fmt, "Failed to submit transaction"),
+			OffchainErr::NotValidator => write!(fmt, "Not validator"),
+			OffchainErr::CheckFail => write!(fmt, "Check fail"),
+		}
+	}
+}
+
+impl<T: Config> Module<T> {
+	#[allow(unused_variables)] // `block_number` is used in macros
+	fn offchain_worker(block_number: T::BlockNumber) -> Result<(), OffchainErr> {
+		// check if we are a potential validator
+		if !sp_io::offchain::is_validator() {
+			return Err(OffchainErr::NotValidator);
+		}
+
+		// Acquire offchain worker lock.
+		let lock_expiration = Duration::from_millis(LOCK_DURATION);
+		let mut lock = StorageLock::<'_, Time>::with_deadline(&OFFCHAIN_WORKER_LOCK, lock_expiration);
+		let mut guard = lock.try_lock().map_err(|_| OffchainErr::OffchainLock)?;
+
+		debug::native::trace!(target: TAG, "Started [block_number = {:?}]", block_number);
+
+		for (pool_id, currency_id) in <SyntheticTokens<T>>::positions_with_synthetic() {
+			// one failing position, e.g. because of a missing price, shouldn't block the others
+			if let Err(error) = Self::check_position(pool_id, currency_id, block_number) {
+				debug::native::error!(
+					target: TAG,
+					"{:?} [pool_id = {:?}, currency_id = {:?}, block_number = {:?}]",
+					error,
+					pool_id,
+					currency_id,
+					block_number
+				);
+			}
+
+			guard.extend_lock().map_err(|_| OffchainErr::OffchainLock)?;
+		}
+
+		debug::native::trace!(target: TAG, "Finished [block_number = {:?}]", block_number);
+		Ok(())
+
+		// drop `guard` and unlock implicitly at end of scope.
+	}
+
+	/// Check a synthetic position and submit the liquidation call it needs, if any.
+	#[allow(unused_variables)] // `block_number` is used in macros
+	fn check_position(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		block_number: T::BlockNumber,
+	) -> Result<(), OffchainErr> {
+		if Self::should_start_collateral_auction(pool_id, currency_id)? {
+			let call = Call::<T>::start_collateral_auction(pool_id, currency_id);
+			SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+				.map_err(|_| OffchainErr::SubmitTransaction)?;
+			debug::native::trace!(
+				target: TAG,
+				"Start collateral auction [pool_id = {:?}, currency_id = {:?}, block_number = {:?}]",
+				pool_id,
+				currency_id,
+				block_number
+			);
+		} else if !Self::is_in_auction(pool_id, currency_id) {
+			let is_safe = Self::is_safe_position(pool_id, currency_id).map_err(|_| OffchainErr::CheckFail)?;
+			if !is_safe {
+				if Self::should_force_add_collateral(pool_id, currency_id)? {
+					let call = Call::<T>::force_add_collateral(pool_id, currency_id);
+					SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+						.map_err(|_| OffchainErr::SubmitTransaction)?;
+					debug::native::trace!(
+						target: TAG,
+						"Force add collateral [pool_id = {:?}, currency_id = {:?}, block_number = {:?}]",
+						pool_id,
+						currency_id,
+						block_number
+					);
+				} else if !Self::is_under_liquidation(pool_id, currency_id) {
+					let call = Call::<T>::mark_under_liquidation(pool_id, currency_id);
+					SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+						.map_err(|_| OffchainErr::SubmitTransaction)?;
+					debug::native::trace!(
+						target: TAG,
+						"Mark under liquidation [pool_id = {:?}, currency_id = {:?}, block_number = {:?}]",
+						pool_id,
+						currency_id,
+						block_number
+					);
+				}
+			} else if Self::is_under_liquidation(pool_id, currency_id) {
+				let call = Call::<T>::position_become_safe(pool_id, currency_id);
+				SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+					.map_err(|_| OffchainErr::SubmitTransaction)?;
+				debug::native::trace!(
+					target: TAG,
+					"Position become safe [pool_id = {:?}, currency_id = {:?}, block_number = {:?}]",
+					pool_id,
+					currency_id,
+					block_number
+				);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns `true` if the position is unsafe, and the pool has enough free liquidity to restore
+	/// it to collateral ratio.
+	fn should_force_add_collateral(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Result<bool, OffchainErr> {
+		if Self::is_in_auction(pool_id, currency_id)
+			|| Self::is_safe_position(pool_id, currency_id).map_err(|_| OffchainErr::CheckFail)?
+		{
+			return Ok(false);
+		}
+		let collateral = Self::collateral_to_be_safe(pool_id, currency_id).map_err(|_| OffchainErr::CheckFail)?;
+		Ok(!collateral.is_zero() && T::LiquidityPools::liquidity(pool_id) >= collateral)
+	}
+
+	fn should_mark_under_liquidation(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Result<bool, OffchainErr> {
+		if Self::is_under_liquidation(pool_id, currency_id) || Self::is_in_auction(pool_id, currency_id) {
+			return Ok(false);
+		}
+		Self::is_safe_position(pool_id, currency_id)
+			.map(|is_safe| !is_safe)
+			.map_err(|_| OffchainErr::CheckFail)
+	}
+
+	fn should_start_collateral_auction(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Result<bool, OffchainErr> {
+		if Self::is_in_auction(pool_id, currency_id) {
+			return Ok(false);
+		}
+		Self::is_below_extreme_ratio(pool_id, currency_id).map_err(|_| OffchainErr::CheckFail)
+	}
+
+	fn should_become_safe(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Result<bool, OffchainErr> {
+		if !Self::is_under_liquidation(pool_id, currency_id) {
+			return Ok(false);
+		}
+		Self::is_safe_position(pool_id, currency_id).map_err(|_| OffchainErr::CheckFail)
+	}
+}
+
+impl<T: Config> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		with_price_usage(PriceUsage::Liquidation, || match call {
+			Call::force_add_collateral(pool_id, currency_id) => {
+				if Self::should_force_add_collateral(*pool_id, *currency_id).ok() == Some(true) {
+					return ValidTransaction::with_tag_prefix("synthetic_protocol/force_add_collateral")
+						.priority(T::UnsignedPriority::get())
+						.and_provides((pool_id, currency_id))
+						.longevity(64_u64)
+						.propagate(true)
+						.build();
+				}
+				InvalidTransaction::Stale.into()
+			}
+			Call::mark_under_liquidation(pool_id, currency_id) => {
+				if Self::should_mark_under_liquidation(*pool_id, *currency_id).ok() == Some(true) {
+					return ValidTransaction::with_tag_prefix("synthetic_protocol/mark_under_liquidation")
+						.priority(T::UnsignedPriority::get())
+						.and_provides((pool_id, currency_id))
+						.longevity(64_u64)
+						.propagate(true)
+						.build();
+				}
+				InvalidTransaction::Stale.into()
+			}
+			Call::position_become_safe(pool_id, currency_id) => {
+				if Self::should_become_safe(*pool_id, *currency_id).ok() == Some(true) {
+					return ValidTransaction::with_tag_prefix("synthetic_protocol/position_become_safe")
+						.priority(T::UnsignedPriority::get())
+						.and_provides((pool_id, currency_id))
+						.longevity(64_u64)
+						.propagate(true)
+						.build();
+				}
+				InvalidTransaction::Stale.into()
+			}
+			Call::start_collateral_auction(pool_id, currency_id) => {
+				if Self::should_start_collateral_auction(*pool_id, *currency_id).ok() == Some(true) {
+					return ValidTransaction::with_tag_prefix("synthetic_protocol/start_collateral_auction")
+						.priority(T::UnsignedPriority::get())
+						.and_provides((pool_id, currency_id))
+						.longevity(64_u64)
+						.propagate(true)
+						.build();
+				}
+				InvalidTransaction::Stale.into()
+			}
+			_ => InvalidTransaction::Call.into(),
+		})
+	}
+}
+
+impl<T: Config> AuctionHandler<T::AccountId, Balance, T::BlockNumber, AuctionIdOf<T>> for Module<T> {
+	fn on_new_bid(
+		now: T::BlockNumber,
+		id: AuctionIdOf<T>,
+		new_bid: (T::AccountId, Balance),
+		last_bid: Option<(T::AccountId, Balance)>,
+	) -> OnNewBidResult<T::BlockNumber> {
+		if with_transaction_result(|| Self::do_lock_bid(id, &new_bid, last_bid)).is_err() {
+			return OnNewBidResult {
+				accept_bid: false,
+				auction_end_change: Change::NoChange,
+			};
+		}
+
+		// leave at least `AuctionTimeToClose` for other bidders
+		let min_end = now.saturating_add(T::AuctionTimeToClose::get());
+		let auction_end_change = match T::Auction::auction_info(id).and_then(|info| info.end) {
+			Some(end) if end >= min_end => Change::NoChange,
+			_ => Change::NewValue(Some(min_end)),
+		};
+		OnNewBidResult {
+			accept_bid: true,
+			auction_end_change,
+		}
+	}
+
+	fn on_auction_ended(id: AuctionIdOf<T>, winner: Option<(T::AccountId, Balance)>) {
+		let item = match <CollateralAuctions<T>>::take(id) {
+			Some(item) => item,
+			None => return,
+		};
+		<PositionsInAuction<T>>::remove(item.pool_id, item.currency_id);
+
+		if let Some((bidder, bid)) = winner {
+			match with_transaction_result(|| Self::do_deal_collateral_auction(&item, &bidder, bid)) {
+				Ok((collateral, synthetic)) => {
+					Self::deposit_event(RawEvent::CollateralAuctionDealt(id, bidder, collateral, synthetic));
+					return;
+				}
+				Err(_) => {
+					// return the locked synthetic to bidder
+					let locked = bid.min(item.target);
+					if T::MultiCurrency::transfer(
+						item.currency_id,
+						&<SyntheticTokens<T>>::account_id(),
+						&bidder,
+						locked,
+					)
+					.is_err()
+					{
+						Self::deposit_event(RawEvent::CollateralAuctionRefundFailed(
+							id,
+							bidder,
+							item.currency_id,
+							locked,
+						));
+					}
+				}
+			}
+		}
+
+		// no bid or failed to deal, the position is left unchanged
+		Self::deposit_event(RawEvent::CollateralAuctionAborted(id));
+	}
+}
@@ -2,10 +2,14 @@
 
 #![cfg(test)]
 
-use frame_support::{impl_outer_event, impl_outer_origin, ord_parameter_types, parameter_types};
+use frame_support::{impl_outer_dispatch, impl_outer_event, impl_outer_origin, ord_parameter_types, parameter_types};
 use frame_system::EnsureSignedBy;
 use sp_core::H256;
-use sp_runtime::{testing::Header, traits::IdentityLookup, DispatchResult, Perbill, Permill};
+use sp_runtime::{
+	testing::{Header, TestXt},
+	traits::IdentityLookup,
+	DispatchResult, Perbill, Permill,
+};
 use sp_std::{cell::RefCell, collections::btree_map::BTreeMap};
 
 use orml_currencies::Currency;
@@ -26,6 +30,12 @@ ord_parameter_types! {
 	pub const One: AccountId = 1;
 }
 
+impl_outer_dispatch! {
+	pub enum Call for Runtime where origin: Origin {
+		synthetic_protocol::SyntheticProtocol,
+	}
+}
+
 mod synthetic_protocol {
 	pub use crate::Event;
 }
@@ -33,7 +43,7 @@ mod synthetic_protocol {
 impl_outer_event! {
 	pub enum TestEvent for Runtime {
 		frame_system<T>,
-		orml_tokens<T>, orml_currencies<T>,
+		orml_tokens<T>, orml_currencies<T>, orml_auction<T>,
 		module_synthetic_tokens, synthetic_protocol<T>,
 	}
 }
@@ -51,7 +61,7 @@ parameter_types! {
 pub type AccountId = u32;
 impl frame_system::Config for Runtime {
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -78,6 +88,17 @@ impl frame_system::Config for Runtime {
 }
 pub type System = frame_system::Module<Runtime>;
 
+parameter_types! {
+	pub const MinimumPeriod: u64 = 5;
+}
+impl pallet_timestamp::Config for Runtime {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+pub type Timestamp = pallet_timestamp::Module<Runtime>;
+
 type Amount = i128;
 
 parameter_type_with_key! {
@@ -113,7 +134,7 @@ impl orml_currencies::Config for Runtime {
 parameter_types! {
 	pub const GetCollateralCurrencyId: CurrencyId = CurrencyId::AUSD;
 	pub const GetSyntheticCurrencyId: CurrencyId = CurrencyId::FEUR;
-	pub SyntheticCurrencyIds: Vec<CurrencyId> = vec![CurrencyId::FEUR];
+	pub SyntheticCurrencyIds: Vec<CurrencyId> = vec![CurrencyId::FEUR, CurrencyId::FJPY];
 	pub const DefaultExtremeRatio: Permill = Permill::from_percent(1);
 	pub const DefaultLiquidationRatio: Permill = Permill::from_percent(5);
 	pub const DefaultCollateralRatio: Permill = Permill::from_percent(10);
@@ -158,16 +179,70 @@ impl DataProvider<CurrencyId, Price> for MockPrices {
 	}
 }
 
+thread_local! {
+	static RISK_PRICES: RefCell<BTreeMap<CurrencyId, Price>> = RefCell::new(BTreeMap::new());
+}
+
+/// Prices for risk checks. Same as `MockPrices` if not set.
+pub struct MockRiskPrices;
+impl MockRiskPrices {
+	pub fn set_mock_price(currency_id: CurrencyId, price: Price) {
+		RISK_PRICES.with(|v| v.borrow_mut().insert(currency_id, price));
+	}
+}
+
+impl DataProvider<CurrencyId, Price> for MockRiskPrices {
+	fn get(key: &CurrencyId) -> Option<Price> {
+		RISK_PRICES
+			.with(|v| v.borrow().get(key).cloned())
+			.or_else(|| MockPrices::prices(*key))
+	}
+}
+
+thread_local! {
+	static STALE_PRICES: RefCell<Vec<(CurrencyId, PriceUsage)>> = RefCell::new(vec![]);
+	static UNRELIABLE_PRICES: RefCell<Vec<(CurrencyId, PriceUsage)>> = RefCell::new(vec![]);
+}
+
+pub struct MockPriceGuard;
+impl MockPriceGuard {
+	pub fn set_stale(currency_id: CurrencyId, usage: PriceUsage) {
+		STALE_PRICES.with(|v| v.borrow_mut().push((currency_id, usage)));
+	}
+
+	pub fn set_unreliable(currency_id: CurrencyId, usage: PriceUsage) {
+		UNRELIABLE_PRICES.with(|v| v.borrow_mut().push((currency_id, usage)));
+	}
+
+	pub fn reset() {
+		STALE_PRICES.with(|v| v.borrow_mut().clear());
+		UNRELIABLE_PRICES.with(|v| v.borrow_mut().clear());
+	}
+}
+
+impl PriceGuard for MockPriceGuard {
+	fn is_stale(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		STALE_PRICES.with(|v| v.borrow().contains(&(currency_id, usage)))
+	}
+
+	fn is_unreliable(currency_id: CurrencyId, usage: PriceUsage) -> bool {
+		UNRELIABLE_PRICES.with(|v| v.borrow().contains(&(currency_id, usage)))
+	}
+}
+
 thread_local! {
 	static SPREAD: RefCell<Price> = RefCell::new(Price::zero());
+	static POOL_SPREADS: RefCell<BTreeMap<LiquidityPoolId, Price>> = RefCell::new(BTreeMap::new());
 	static ADDITIONAL_COLLATERAL_RATIO: RefCell<Permill> = RefCell::new(Permill::zero());
 	static IS_ALLOWED: RefCell<bool> = RefCell::new(false);
 }
 
 pub struct MockLiquidityPools;
 impl MockLiquidityPools {
-	fn spread() -> Price {
-		SPREAD.with(|v| *v.borrow_mut())
+	fn spread(pool_id: LiquidityPoolId) -> Price {
+		POOL_SPREADS
+			.with(|v| v.borrow().get(&pool_id).copied())
+			.unwrap_or_else(|| SPREAD.with(|v| *v.borrow_mut()))
 	}
 
 	fn additional_collateral_ratio() -> Permill {
@@ -182,6 +257,10 @@ impl MockLiquidityPools {
 		SPREAD.with(|v| *v.borrow_mut() = spread);
 	}
 
+	pub fn set_mock_pool_spreads(spreads: BTreeMap<LiquidityPoolId, Price>) {
+		POOL_SPREADS.with(|v| *v.borrow_mut() = spreads);
+	}
+
 	pub fn set_mock_additional_collateral_ratio(ratio: Permill) {
 		ADDITIONAL_COLLATERAL_RATIO.with(|v| *v.borrow_mut() = ratio);
 	}
@@ -193,7 +272,7 @@ impl MockLiquidityPools {
 
 impl LiquidityPools<AccountId> for MockLiquidityPools {
 	fn all() -> Vec<LiquidityPoolId> {
-		unimplemented!()
+		vec![MOCK_POOL, ANOTHER_MOCK_POOL]
 	}
 
 	/// ALICE is the mock owner
@@ -219,14 +298,14 @@ impl LiquidityPools<AccountId> for MockLiquidityPools {
 }
 
 impl SyntheticProtocolLiquidityPools<AccountId> for MockLiquidityPools {
-	fn bid_spread(_pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<Price> {
+	fn bid_spread(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<Price> {
 		let price = MockPrices::prices(currency_id)?;
-		Some(Self::spread().saturating_mul(price))
+		Some(Self::spread(pool_id).saturating_mul(price))
 	}
 
-	fn ask_spread(_pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<Price> {
+	fn ask_spread(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Option<Price> {
 		let price = MockPrices::prices(currency_id)?;
-		Some(Self::spread().saturating_mul(price))
+		Some(Self::spread(pool_id).saturating_mul(price))
 	}
 
 	fn additional_collateral_ratio(_pool_id: LiquidityPoolId, _currency_id: CurrencyId) -> Permill {
@@ -238,20 +317,63 @@ impl SyntheticProtocolLiquidityPools<AccountId> for MockLiquidityPools {
 	}
 }
 
+parameter_types! {
+	pub const AuctionDuration: u64 = 10;
+	pub const AuctionTimeToClose: u64 = 5;
+}
+
+impl orml_auction::Config for Runtime {
+	type Event = TestEvent;
+	type Balance = Balance;
+	type AuctionId = u32;
+	type Handler = SyntheticProtocol;
+	type WeightInfo = ();
+}
+pub type AuctionModule = orml_auction::Module<Runtime>;
+
+pub type Extrinsic = TestXt<Call, ()>;
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Runtime
+where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = Extrinsic;
+}
+
+parameter_types! {
+	pub const UnsignedPriority: u64 = 1 << 20;
+	pub const GetTreasuryAccountId: AccountId = TREASURY_ACCOUNT;
+	pub const StabilityFeeInterval: u64 = 60 * 60;
+	pub const StabilityFeeHistoryLength: u32 = 3;
+	pub const MaxStabilityFeeChecksPerBlock: u32 = 3;
+}
+
 impl Config for Runtime {
 	type Event = TestEvent;
 	type MultiCurrency = orml_currencies::Module<Runtime>;
 	type CollateralCurrency = CollateralCurrency;
 	type GetCollateralCurrencyId = GetCollateralCurrencyId;
+	type GetTreasuryAccountId = GetTreasuryAccountId;
 	type PriceProvider = DefaultPriceProvider<CurrencyId, MockPrices>;
+	type RiskPriceProvider = DefaultPriceProvider<CurrencyId, MockRiskPrices>;
+	type PriceGuard = MockPriceGuard;
 	type LiquidityPools = MockLiquidityPools;
 	type SyntheticProtocolLiquidityPools = MockLiquidityPools;
+	type Auction = AuctionModule;
+	type AuctionDuration = AuctionDuration;
+	type AuctionTimeToClose = AuctionTimeToClose;
+	type UnixTime = Timestamp;
+	type StabilityFeeInterval = StabilityFeeInterval;
+	type StabilityFeeHistoryLength = StabilityFeeHistoryLength;
+	type MaxStabilityFeeChecksPerBlock = MaxStabilityFeeChecksPerBlock;
+	type UnsignedPriority = UnsignedPriority;
 	type WeightInfo = ();
 }
 pub type SyntheticProtocol = Module<Runtime>;
 
 pub const ALICE: AccountId = 0;
 pub const BOB: AccountId = 1;
+pub const TREASURY_ACCOUNT: AccountId = 3;
 pub fn origin_of(account_id: AccountId) -> Origin {
 	Origin::signed(account_id)
 }
@@ -263,6 +385,7 @@ pub struct ExtBuilder {
 	endowed_accounts: Vec<(AccountId, CurrencyId, Balance)>,
 	prices: Vec<(CurrencyId, Price)>,
 	spread: Price,
+	pool_spreads: BTreeMap<LiquidityPoolId, Price>,
 	additional_collateral_ratio: Permill,
 	is_allowed: bool,
 }
@@ -274,6 +397,7 @@ impl Default for ExtBuilder {
 			// collateral price set to `1` for calculation simplicity.
 			prices: vec![(CurrencyId::AUSD, Price::saturating_from_rational(1, 1))],
 			spread: Price::zero(),
+			pool_spreads: BTreeMap::new(),
 			additional_collateral_ratio: Permill::zero(),
 			is_allowed: true,
 		}
@@ -315,6 +439,12 @@ impl ExtBuilder {
 		self.spread(Price::from_fraction(0.01))
 	}
 
+	/// Spread of `pool_id`, overrides the common spread.
+	pub fn pool_spread(mut self, pool_id: LiquidityPoolId, spread: Price) -> Self {
+		self.pool_spreads.insert(pool_id, spread);
+		self
+	}
+
 	pub fn additional_collateral_ratio(mut self, ratio: Permill) -> Self {
 		self.additional_collateral_ratio = ratio;
 		self
@@ -335,6 +465,7 @@ impl ExtBuilder {
 			.for_each(|(c, p)| MockPrices::set_mock_price(*c, Some(*p)));
 
 		MockLiquidityPools::set_mock_spread(self.spread);
+		MockLiquidityPools::set_mock_pool_spreads(self.pool_spreads.clone());
 		MockLiquidityPools::set_mock_additional_collateral_ratio(self.additional_collateral_ratio);
 		MockLiquidityPools::set_is_allowed(self.is_allowed);
 	}
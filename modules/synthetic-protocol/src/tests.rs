@@ -3,9 +3,17 @@
 #![cfg(test)]
 
 use super::*;
-use frame_support::{assert_noop, assert_ok};
+use codec::Decode;
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::{OnFinalize, OnInitialize},
+};
 use mock::*;
-use sp_runtime::{DispatchResult, Permill};
+use sp_core::offchain::{
+	testing::{TestOffchainExt, TestTransactionPoolExt},
+	OffchainExt, TransactionPoolExt,
+};
+use sp_runtime::{traits::BadOrigin, DispatchResult, Permill};
 
 fn mint_feur(who: AccountId, amount: Balance) -> DispatchResult {
 	SyntheticProtocol::mint(
@@ -87,6 +95,37 @@ fn mint_fails_if_no_price() {
 		});
 }
 
+#[test]
+fn mint_fails_if_stale_price() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPriceGuard::set_stale(CurrencyId::FEUR, PriceUsage::Liquidation);
+			assert_ok!(mint_feur(ALICE, 100));
+
+			MockPriceGuard::set_stale(CurrencyId::AUSD, PriceUsage::Trading);
+			assert_noop!(mint_feur(ALICE, 100), Error::<Runtime>::StalePrice);
+		});
+}
+
+#[test]
+fn mint_fails_if_unreliable_price() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPriceGuard::set_unreliable(CurrencyId::FEUR, PriceUsage::Trading);
+			assert_noop!(mint_feur(ALICE, 100), Error::<Runtime>::UnreliablePrice);
+		});
+}
+
 #[test]
 fn mint_fails_if_slippage_too_greedy() {
 	ExtBuilder::default()
@@ -215,6 +254,7 @@ fn mint_does_correct_math() {
 				MOCK_POOL,
 				ONE_MILL,
 				synthetic,
+				0,
 			));
 			assert!(System::events().iter().any(|record| record.event == event));
 		});
@@ -450,6 +490,7 @@ fn redeem_does_correct_math() {
 				MOCK_POOL,
 				redeemed_collateral,
 				synthetic_to_redeem,
+				0,
 			));
 			assert!(System::events().iter().any(|record| record.event == event));
 		});
@@ -537,6 +578,337 @@ fn mint_and_redeem_by_multi_buyers() {
 		});
 }
 
+#[test]
+fn swap_fails_if_amount_too_low() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::FJPY, Some(Price::saturating_from_rational(1, 2)));
+			assert_ok!(mint_feur(ALICE, 990_000));
+
+			assert_noop!(
+				SyntheticProtocol::swap(
+					origin_of(ALICE),
+					MOCK_POOL,
+					CurrencyId::FEUR,
+					100_000,
+					MOCK_POOL,
+					CurrencyId::FJPY,
+					600_001,
+				),
+				Error::<Runtime>::SwapAmountTooLow
+			);
+		});
+}
+
+#[test]
+fn swap_does_correct_math() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::FJPY, Some(Price::saturating_from_rational(1, 2)));
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_eq!(position(), (1_089_000, 330_000));
+
+			assert_ok!(SyntheticProtocol::swap(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				100_000,
+				MOCK_POOL,
+				CurrencyId::FJPY,
+				600_000,
+			));
+
+			// released collateral = 100_000 * 3 = 300_000
+			// FEUR position: required = 230_000 * 3 * 1.1 = 759_000, pool refund = 30_000
+			assert_eq!(synthetic_balance(ALICE), 230_000);
+			assert_eq!(position(), (759_000, 230_000));
+
+			// FJPY minted = 300_000 / 0.5 = 600_000
+			// FJPY position: collateral = 600_000 * 0.5 * 1.1 = 330_000, from pool = 30_000
+			assert_eq!(
+				orml_currencies::Module::<Runtime>::free_balance(CurrencyId::FJPY, &ALICE),
+				600_000
+			);
+			assert_eq!(
+				TestSyntheticTokens::get_position(MOCK_POOL, CurrencyId::FJPY),
+				(330_000, 600_000)
+			);
+
+			// collateral moved between positions, balance of alice unchanged
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 990_000);
+			assert_eq!(mock_pool_liquidity(), ONE_MILL - 99_000);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::Swapped(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				100_000,
+				CurrencyId::FJPY,
+				MOCK_POOL,
+				600_000,
+				0,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn swap_charges_spreads_and_fees_of_both_legs() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::FJPY, Some(Price::saturating_from_rational(1, 2)));
+			// synthetic = 990_000 / 3.03 = 326_732
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_ok!(SyntheticProtocol::set_fee_rates(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(2),
+				Permill::from_percent(2)
+			));
+			assert_ok!(SyntheticProtocol::set_fee_rates(
+				origin_of(BOB),
+				CurrencyId::FJPY,
+				Permill::from_percent(1),
+				Permill::from_percent(2)
+			));
+
+			// redeemed = 100_000 * 2.97 = 297_000, FEUR redeem fee = 297_000 * 2% = 5_940
+			// FJPY mint fee = 291_060 * 1% = 2_910, FJPY = 288_150 / 0.505 = 570_594
+			assert_noop!(
+				SyntheticProtocol::swap(
+					origin_of(ALICE),
+					MOCK_POOL,
+					CurrencyId::FEUR,
+					100_000,
+					MOCK_POOL,
+					CurrencyId::FJPY,
+					570_595,
+				),
+				Error::<Runtime>::SwapAmountTooLow
+			);
+			assert_ok!(SyntheticProtocol::swap(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				100_000,
+				MOCK_POOL,
+				CurrencyId::FJPY,
+				570_594,
+			));
+
+			assert_eq!(synthetic_balance(ALICE), 226_732);
+			assert_eq!(
+				orml_currencies::Module::<Runtime>::free_balance(CurrencyId::FJPY, &ALICE),
+				570_594
+			);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 8_850);
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 990_000);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::Swapped(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				100_000,
+				CurrencyId::FJPY,
+				MOCK_POOL,
+				570_594,
+				8_850,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+fn routed_ext_builder() -> ExtBuilder {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(MOCK_POOL, CurrencyId::AUSD, 9_900),
+			(ANOTHER_MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.pool_spread(ANOTHER_MOCK_POOL, Price::saturating_from_rational(1, 10))
+}
+
+#[test]
+fn routed_mint_fails_if_not_enough_liquidity_in_pools() {
+	routed_ext_builder().build().execute_with(|| {
+		// only `MOCK_POOL` meets price, with capacity 99_000
+		assert_noop!(
+			SyntheticProtocol::routed_mint(
+				origin_of(ALICE),
+				CurrencyId::FEUR,
+				429_000,
+				Price::saturating_from_rational(31, 10),
+				2,
+			),
+			Error::<Runtime>::InsufficientLiquidityInPools
+		);
+	});
+}
+
+#[test]
+fn routed_mint_and_redeem_fail_if_too_many_pools() {
+	routed_ext_builder().build().execute_with(|| {
+		assert_noop!(
+			SyntheticProtocol::routed_mint(
+				origin_of(ALICE),
+				CurrencyId::FEUR,
+				1_000,
+				Price::saturating_from_rational(4, 1),
+				1,
+			),
+			Error::<Runtime>::TooManyPools
+		);
+		assert_noop!(
+			SyntheticProtocol::routed_redeem(
+				origin_of(ALICE),
+				CurrencyId::FEUR,
+				1_000,
+				Price::saturating_from_rational(2, 1),
+				1,
+			),
+			Error::<Runtime>::TooManyPools
+		);
+	});
+}
+
+#[test]
+fn routed_mint_and_redeem_work() {
+	routed_ext_builder().build().execute_with(|| {
+		assert_ok!(SyntheticProtocol::routed_mint(
+			origin_of(ALICE),
+			CurrencyId::FEUR,
+			429_000,
+			Price::saturating_from_rational(4, 1),
+			2,
+		));
+
+		// `MOCK_POOL` ask price 3, capacity = 9_900 / (3 / 3 * 1.1 - 1) = 99_000
+		// synthetic = 99_000 / 3 = 33_000
+		assert_eq!(position(), (108_900, 33_000));
+		assert_eq!(mock_pool_liquidity(), 0);
+
+		// `ANOTHER_MOCK_POOL` ask price 3.3, no additional collateral needed
+		// synthetic = 330_000 / 3.3 = 100_000
+		assert_eq!(
+			TestSyntheticTokens::get_position(ANOTHER_MOCK_POOL, CurrencyId::FEUR),
+			(330_000, 100_000)
+		);
+		assert_eq!(synthetic_balance(ALICE), 133_000);
+		assert_eq!(collateral_balance(ALICE), ONE_MILL - 429_000);
+
+		let event = TestEvent::synthetic_protocol(RawEvent::RoutedMinted(
+			ALICE,
+			CurrencyId::FEUR,
+			429_000,
+			133_000,
+			0,
+			vec![(MOCK_POOL, 99_000, 33_000), (ANOTHER_MOCK_POOL, 330_000, 100_000)],
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(SyntheticProtocol::routed_redeem(
+			origin_of(ALICE),
+			CurrencyId::FEUR,
+			50_000,
+			Price::saturating_from_rational(2, 1),
+			2,
+		));
+
+		// `MOCK_POOL` bid price 3, redeemed collateral = 33_000 * 3 = 99_000
+		// pool refund = 108_900 - 99_000 = 9_900
+		assert_eq!(position(), (0, 0));
+		assert_eq!(mock_pool_liquidity(), 9_900);
+
+		// `ANOTHER_MOCK_POOL` bid price 2.7, redeemed collateral = 17_000 * 2.7 = 45_900
+		// required = 83_000 * 3 * 1.1 = 273_900
+		assert_eq!(
+			TestSyntheticTokens::get_position(ANOTHER_MOCK_POOL, CurrencyId::FEUR),
+			(273_900, 83_000)
+		);
+		assert_eq!(synthetic_balance(ALICE), 83_000);
+		assert_eq!(collateral_balance(ALICE), ONE_MILL - 429_000 + 99_000 + 45_900);
+
+		let event = TestEvent::synthetic_protocol(RawEvent::RoutedRedeemed(
+			ALICE,
+			CurrencyId::FEUR,
+			144_900,
+			50_000,
+			0,
+			vec![(MOCK_POOL, 99_000, 33_000), (ANOTHER_MOCK_POOL, 45_900, 17_000)],
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		// not enough synthetic in pools with acceptable bid price
+		assert_noop!(
+			SyntheticProtocol::routed_redeem(
+				origin_of(ALICE),
+				CurrencyId::FEUR,
+				10_000,
+				Price::saturating_from_rational(28, 10),
+				2,
+			),
+			Error::<Runtime>::InsufficientSyntheticInPools
+		);
+	});
+}
+
+#[test]
+fn routed_mint_capacity_excludes_fee() {
+	routed_ext_builder().build().execute_with(|| {
+		assert_ok!(SyntheticProtocol::set_fee_rates(
+			origin_of(BOB),
+			CurrencyId::FEUR,
+			Permill::from_percent(1),
+			Permill::zero()
+		));
+		assert_ok!(SyntheticProtocol::routed_mint(
+			origin_of(ALICE),
+			CurrencyId::FEUR,
+			430_000,
+			Price::saturating_from_rational(4, 1),
+			2,
+		));
+
+		// `MOCK_POOL` capacity = 9_900 / (3 / 3 * 1.1 - 1) / (1 - 1%) = 100_000
+		// fee = 1_000, synthetic = 99_000 / 3 = 33_000
+		assert_eq!(position(), (108_900, 33_000));
+		assert_eq!(mock_pool_liquidity(), 0);
+
+		// `ANOTHER_MOCK_POOL` fee = 3_300, synthetic = 326_700 / 3.3 = 99_000
+		assert_eq!(
+			TestSyntheticTokens::get_position(ANOTHER_MOCK_POOL, CurrencyId::FEUR),
+			(326_700, 99_000)
+		);
+		assert_eq!(synthetic_balance(ALICE), 132_000);
+		assert_eq!(collateral_balance(TREASURY_ACCOUNT), 4_300);
+
+		let event = TestEvent::synthetic_protocol(RawEvent::RoutedMinted(
+			ALICE,
+			CurrencyId::FEUR,
+			430_000,
+			132_000,
+			4_300,
+			vec![(MOCK_POOL, 100_000, 33_000), (ANOTHER_MOCK_POOL, 330_000, 99_000)],
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
 #[test]
 fn liquidate_fails_if_liquidator_not_enough_synthetic() {
 	ExtBuilder::default()
@@ -569,6 +941,51 @@ fn liquidate_fails_if_no_price() {
 		});
 }
 
+#[test]
+fn liquidate_uses_liquidation_price_policy() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, ONE_MILL));
+			set_mock_feur_price(32, 10);
+			assert_ok!(SyntheticCurrency::deposit(&BOB, 100_000));
+
+			MockPriceGuard::set_stale(CurrencyId::FEUR, PriceUsage::Liquidation);
+			assert_noop!(liquidate(BOB, 100_000), Error::<Runtime>::StalePrice);
+
+			// stale for trading only, not blocking liquidation
+			MockPriceGuard::reset();
+			MockPriceGuard::set_stale(CurrencyId::FEUR, PriceUsage::Trading);
+			assert_ok!(liquidate(BOB, 100_000));
+		});
+}
+
+#[test]
+fn liquidate_uses_risk_prices() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, ONE_MILL));
+			assert_ok!(SyntheticCurrency::deposit(&BOB, 100_000));
+
+			// spot price spike doesn't make position unsafe
+			MockRiskPrices::set_mock_price(CurrencyId::FEUR, Price::saturating_from_integer(3));
+			set_mock_feur_price(32, 10);
+			assert_noop!(liquidate(BOB, 100_000), Error::<Runtime>::StillInSafePosition);
+
+			MockRiskPrices::set_mock_price(CurrencyId::FEUR, Price::saturating_from_rational(32, 10));
+			assert_ok!(liquidate(BOB, 100_000));
+		});
+}
+
 #[test]
 fn liquidate_fails_if_synthetic_position_too_low() {
 	ExtBuilder::default()
@@ -1048,3 +1465,1311 @@ fn mint_all_of_collateral() {
 			assert_eq!(mock_pool_liquidity(), 20);
 		});
 }
+
+fn vault_mint_feur(who: AccountId, amount: Balance, ratio: Permill) -> DispatchResult {
+	SyntheticProtocol::vault_mint(
+		origin_of(who),
+		MOCK_POOL,
+		CurrencyId::FEUR,
+		amount,
+		ratio,
+		Price::saturating_from_rational(4, 1),
+	)
+}
+
+fn vault(who: AccountId) -> (Balance, Balance) {
+	TestSyntheticTokens::get_vault(&who, MOCK_POOL, CurrencyId::FEUR)
+}
+
+#[test]
+fn vault_mint_fails_if_ratio_too_low() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.build()
+		.execute_with(|| {
+			assert_noop!(
+				vault_mint_feur(ALICE, 660_000, Permill::from_percent(5)),
+				Error::<Runtime>::CollateralRatioTooLow
+			);
+		});
+}
+
+#[test]
+fn vault_mint_and_burn_work() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.build()
+		.execute_with(|| {
+			// synthetic = 660_000 / (3 * (1 + 0.1)) = 200_000
+			assert_ok!(vault_mint_feur(ALICE, 660_000, Permill::from_percent(10)));
+			assert_eq!(synthetic_balance(ALICE), 200_000);
+			assert_eq!(collateral_balance(ALICE), 340_000);
+			assert_eq!(vault(ALICE), (660_000, 200_000));
+			assert_eq!(collateral_balance(TestSyntheticTokens::account_id()), 660_000);
+
+			// pool is not involved
+			assert_eq!(mock_pool_liquidity(), ONE_MILL);
+			assert_eq!(position(), (0, 0));
+			assert_eq!(
+				SyntheticProtocol::vault_collateral_ratio(&ALICE, MOCK_POOL, CurrencyId::FEUR),
+				Some(FixedU128::saturating_from_rational(11, 10))
+			);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::VaultMinted(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				660_000,
+				200_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			assert_noop!(
+				SyntheticProtocol::vault_burn(origin_of(ALICE), MOCK_POOL, CurrencyId::FEUR, 200_001),
+				Error::<Runtime>::InsufficientSyntheticInPosition
+			);
+
+			assert_ok!(SyntheticProtocol::vault_burn(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				100_000
+			));
+			assert_eq!(synthetic_balance(ALICE), 100_000);
+			assert_eq!(collateral_balance(ALICE), 670_000);
+			assert_eq!(vault(ALICE), (330_000, 100_000));
+
+			let event = TestEvent::synthetic_protocol(RawEvent::VaultBurned(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				330_000,
+				100_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			assert_ok!(SyntheticProtocol::vault_burn(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				100_000
+			));
+			assert_eq!(collateral_balance(ALICE), ONE_MILL);
+			assert_eq!(vault(ALICE), (0, 0));
+		});
+}
+
+#[test]
+fn vault_mint_pays_ask_spread_to_pool() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.build()
+		.execute_with(|| {
+			// synthetic = 666_600 / (3.03 * (1 + 0.1)) = 200_000
+			// spread = 200_000 * (3.03 - 3) = 6_000
+			assert_ok!(vault_mint_feur(ALICE, 666_600, Permill::from_percent(10)));
+			assert_eq!(synthetic_balance(ALICE), 200_000);
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 666_600);
+			assert_eq!(vault(ALICE), (660_600, 200_000));
+			assert_eq!(collateral_balance(TestSyntheticTokens::account_id()), 660_600);
+			assert_eq!(mock_pool_liquidity(), ONE_MILL + 6_000);
+
+			// ratio = 660_600 / (200_000 * 3) = 1.101
+			assert_eq!(
+				SyntheticProtocol::vault_collateral_ratio(&ALICE, MOCK_POOL, CurrencyId::FEUR),
+				Some(FixedU128::saturating_from_rational(1_101, 1_000))
+			);
+
+			// risk price used for the vault collateral ratio
+			MockRiskPrices::set_mock_price(CurrencyId::FEUR, Price::saturating_from_rational(33, 10));
+			assert_eq!(
+				SyntheticProtocol::vault_collateral_ratio(&ALICE, MOCK_POOL, CurrencyId::FEUR),
+				Some(FixedU128::saturating_from_rational(660_600, 660_000))
+			);
+		});
+}
+
+#[test]
+fn vault_add_and_withdraw_collateral_work() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.build()
+		.execute_with(|| {
+			assert_ok!(vault_mint_feur(ALICE, 660_000, Permill::from_percent(10)));
+
+			// required = 200_000 * 3 * (1 + 0.1) = 660_000
+			assert_noop!(
+				SyntheticProtocol::vault_withdraw_collateral(origin_of(ALICE), MOCK_POOL, CurrencyId::FEUR, 1),
+				Error::<Runtime>::CollateralRatioTooLow
+			);
+
+			assert_ok!(SyntheticProtocol::vault_add_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				30_000
+			));
+			assert_eq!(vault(ALICE), (690_000, 200_000));
+			assert_eq!(collateral_balance(ALICE), 310_000);
+
+			assert_noop!(
+				SyntheticProtocol::vault_withdraw_collateral(origin_of(ALICE), MOCK_POOL, CurrencyId::FEUR, 30_001),
+				Error::<Runtime>::CollateralRatioTooLow
+			);
+			assert_ok!(SyntheticProtocol::vault_withdraw_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				30_000
+			));
+			assert_eq!(vault(ALICE), (660_000, 200_000));
+			assert_eq!(collateral_balance(ALICE), 340_000);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::VaultCollateralWithdrew(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				30_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn vault_liquidate_works() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.build()
+		.execute_with(|| {
+			assert_ok!(vault_mint_feur(ALICE, 660_000, Permill::from_percent(10)));
+			assert_ok!(SyntheticCurrency::transfer(&ALICE, &BOB, 100_000));
+
+			assert_noop!(
+				SyntheticProtocol::vault_liquidate(origin_of(BOB), ALICE, MOCK_POOL, CurrencyId::FEUR, 100_000),
+				Error::<Runtime>::StillInSafePosition
+			);
+
+			// ratio = 660_000 / (200_000 * 3.15) ~= 1.0476, below liquidation ratio
+			set_mock_feur_price(315, 100);
+
+			assert_ok!(SyntheticProtocol::vault_liquidate(
+				origin_of(BOB),
+				ALICE,
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				100_000
+			));
+
+			// collateral = 100_000 * 3.15 = 315_000
+			// incentive
+			// = (660_000 - 315_000 - 100_000 * 3.15 * 1.0476) * (0.05 - 0.0476) / (0.05 - 0.01)
+			// = 15_001 * 0.0595 ~= 892
+			assert_eq!(synthetic_balance(BOB), 0);
+			assert_eq!(collateral_balance(BOB), 315_892);
+			assert_eq!(vault(ALICE), (344_108, 100_000));
+			assert_eq!(position(), (0, 0));
+
+			let event = TestEvent::synthetic_protocol(RawEvent::VaultLiquidated(
+				BOB,
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				315_000,
+				100_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn force_add_collateral_and_liquidation_status_work() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			// synthetic = 990_000 / 3 = 330_000
+			// collateral = 330_000 * 3 * (1 + 0.1) = 1_089_000
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_eq!(position(), (1_089_000, 330_000));
+
+			assert_noop!(
+				SyntheticProtocol::force_add_collateral(Origin::none(), MOCK_POOL, CurrencyId::FEUR),
+				Error::<Runtime>::StillInSafePosition
+			);
+			assert_noop!(
+				SyntheticProtocol::mark_under_liquidation(origin_of(ALICE), MOCK_POOL, CurrencyId::FEUR),
+				BadOrigin
+			);
+
+			// ratio = 1_089_000 / (330_000 * 3.2) = 1.03125
+			set_mock_feur_price(32, 10);
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(false)
+			);
+
+			assert_ok!(SyntheticProtocol::mark_under_liquidation(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+			assert_eq!(
+				SyntheticProtocol::positions_under_liquidation(MOCK_POOL, CurrencyId::FEUR),
+				Some(())
+			);
+			assert_noop!(mint_feur(ALICE, 10_000), Error::<Runtime>::UnderLiquidation);
+			assert_noop!(withdraw_collateral(ALICE), Error::<Runtime>::UnderLiquidation);
+			assert_noop!(
+				SyntheticProtocol::position_become_safe(Origin::none(), MOCK_POOL, CurrencyId::FEUR),
+				Error::<Runtime>::UnsafePosition
+			);
+
+			// required = 330_000 * 3.2 * (1 + 0.1) = 1_161_600
+			assert_ok!(SyntheticProtocol::force_add_collateral(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+			assert_eq!(position(), (1_161_600, 330_000));
+			assert_eq!(mock_pool_liquidity(), ONE_MILL - 99_000 - 72_600);
+			assert_eq!(
+				SyntheticProtocol::positions_under_liquidation(MOCK_POOL, CurrencyId::FEUR),
+				None
+			);
+
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::CollateralForceAdded(CurrencyId::FEUR, MOCK_POOL, 72_600));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			// ratio = 1_161_600 / (330_000 * 3.5) ~= 1.0057
+			set_mock_feur_price(35, 10);
+			assert_ok!(SyntheticProtocol::mark_under_liquidation(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+
+			set_mock_feur_price(3, 1);
+			assert_ok!(SyntheticProtocol::position_become_safe(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+			assert_eq!(
+				SyntheticProtocol::positions_under_liquidation(MOCK_POOL, CurrencyId::FEUR),
+				None
+			);
+			assert_noop!(
+				SyntheticProtocol::position_become_safe(Origin::none(), MOCK_POOL, CurrencyId::FEUR),
+				Error::<Runtime>::NotUnderLiquidation
+			);
+		});
+}
+
+#[test]
+fn offchain_worker_should_work() {
+	let mut ext = ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build();
+
+	let (offchain, _state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		assert_ok!(mint_feur(ALICE, 990_000));
+
+		// safe, nothing to do
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+		assert!(pool_state.read().transactions.is_empty());
+
+		// unsafe, pool has enough liquidity
+		set_mock_feur_price(32, 10);
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		let force_add_collateral = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*force_add_collateral).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			mock::Call::SyntheticProtocol(super::Call::force_add_collateral(MOCK_POOL, CurrencyId::FEUR))
+		);
+
+		// unsafe, pool has not enough liquidity
+		assert_ok!(MockLiquidityPools::withdraw_liquidity(&BOB, MOCK_POOL, 900_000));
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		let mark_under_liquidation = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*mark_under_liquidation).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			mock::Call::SyntheticProtocol(super::Call::mark_under_liquidation(MOCK_POOL, CurrencyId::FEUR))
+		);
+
+		// already under liquidation
+		PositionsUnderLiquidation::insert(MOCK_POOL, CurrencyId::FEUR, ());
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+		assert!(pool_state.read().transactions.is_empty());
+
+		// safe again
+		set_mock_feur_price(3, 1);
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		let position_become_safe = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*position_become_safe).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			mock::Call::SyntheticProtocol(super::Call::position_become_safe(MOCK_POOL, CurrencyId::FEUR))
+		);
+
+		// below extreme ratio
+		PositionsUnderLiquidation::remove(MOCK_POOL, CurrencyId::FEUR);
+		set_mock_feur_price(33, 10);
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		let start_collateral_auction = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*start_collateral_auction).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			mock::Call::SyntheticProtocol(super::Call::start_collateral_auction(MOCK_POOL, CurrencyId::FEUR))
+		);
+
+		// already in auction
+		assert_ok!(SyntheticProtocol::start_collateral_auction(
+			Origin::none(),
+			MOCK_POOL,
+			CurrencyId::FEUR
+		));
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+		assert!(pool_state.read().transactions.is_empty());
+	});
+}
+
+#[test]
+fn offchain_worker_continues_if_a_position_check_fails() {
+	let mut ext = ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build();
+
+	let (offchain, _state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		MockPrices::set_mock_price(CurrencyId::FJPY, Some(Price::saturating_from_rational(1, 2)));
+		assert_ok!(SyntheticProtocol::mint(
+			origin_of(ALICE),
+			MOCK_POOL,
+			CurrencyId::FJPY,
+			100_000,
+			Price::saturating_from_rational(1, 1),
+		));
+		assert_ok!(mint_feur(ALICE, 300_000));
+
+		// FJPY position can't be checked without a price, FEUR position is unsafe
+		MockPrices::set_mock_price(CurrencyId::FJPY, None);
+		set_mock_feur_price(32, 10);
+		assert_ok!(SyntheticProtocol::offchain_worker(1));
+
+		assert_eq!(pool_state.read().transactions.len(), 1);
+		let force_add_collateral = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &*force_add_collateral).unwrap();
+		assert_eq!(
+			tx.call,
+			mock::Call::SyntheticProtocol(super::Call::force_add_collateral(MOCK_POOL, CurrencyId::FEUR))
+		);
+	});
+}
+
+fn start_collateral_auction() -> DispatchResult {
+	SyntheticProtocol::start_collateral_auction(Origin::none(), MOCK_POOL, CurrencyId::FEUR)
+}
+
+#[test]
+fn start_collateral_auction_works() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_eq!(position(), (1_089_000, 330_000));
+
+			// ratio = 1_089_000 / (330_000 * 3.2) = 1.03125
+			set_mock_feur_price(32, 10);
+			assert_noop!(start_collateral_auction(), Error::<Runtime>::AboveExtremeRatio);
+			assert_ok!(SyntheticProtocol::mark_under_liquidation(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+
+			// ratio = 1_089_000 / (330_000 * 3.3) = 1
+			set_mock_feur_price(33, 10);
+			assert_eq!(
+				SyntheticProtocol::is_below_extreme_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Ok(true)
+			);
+			assert_ok!(start_collateral_auction());
+			assert_eq!(
+				SyntheticProtocol::collateral_auctions(0),
+				Some(CollateralAuctionItem {
+					pool_id: MOCK_POOL,
+					currency_id: CurrencyId::FEUR,
+					collateral: 1_089_000,
+					target: 330_000,
+				})
+			);
+			assert_eq!(
+				SyntheticProtocol::positions_in_auction(MOCK_POOL, CurrencyId::FEUR),
+				Some(0)
+			);
+			assert_eq!(
+				SyntheticProtocol::positions_under_liquidation(MOCK_POOL, CurrencyId::FEUR),
+				None
+			);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::CollateralAuctionStarted(
+				0,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				1_089_000,
+				330_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			// position is frozen
+			assert_noop!(start_collateral_auction(), Error::<Runtime>::InAuction);
+			assert_noop!(mint_feur(ALICE, 1_000), Error::<Runtime>::InAuction);
+			assert_noop!(redeem_ausd(ALICE, 1_000), Error::<Runtime>::InAuction);
+			assert_noop!(liquidate(ALICE, 1_000), Error::<Runtime>::InAuction);
+			assert_noop!(add_collateral(ALICE, 1_000), Error::<Runtime>::InAuction);
+			assert_noop!(withdraw_collateral(ALICE), Error::<Runtime>::InAuction);
+
+			// no bid, position unchanged
+			AuctionModule::on_finalize(11);
+			assert_eq!(SyntheticProtocol::collateral_auctions(0), None);
+			assert_eq!(
+				SyntheticProtocol::positions_in_auction(MOCK_POOL, CurrencyId::FEUR),
+				None
+			);
+			assert_eq!(position(), (1_089_000, 330_000));
+
+			let event = TestEvent::synthetic_protocol(RawEvent::CollateralAuctionAborted(0));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn collateral_auction_failed_refund_emits_event() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, 990_000));
+			set_mock_feur_price(33, 10);
+			assert_ok!(start_collateral_auction());
+
+			// no synthetic locked for the bid, both the deal and the refund fail
+			<SyntheticProtocol as AuctionHandler<AccountId, Balance, u64, AuctionIdOf<Runtime>>>::on_auction_ended(
+				0,
+				Some((BOB, 1_000)),
+			);
+
+			assert_eq!(position(), (1_089_000, 330_000));
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::CollateralAuctionRefundFailed(0, BOB, CurrencyId::FEUR, 1_000));
+			assert!(System::events().iter().any(|record| record.event == event));
+			let event = TestEvent::synthetic_protocol(RawEvent::CollateralAuctionAborted(0));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+#[test]
+fn collateral_auction_refunds_surplus_to_pool() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_ok!(SyntheticCurrency::transfer(&ALICE, &BOB, 30_000));
+			set_mock_feur_price(33, 10);
+			assert_ok!(start_collateral_auction());
+
+			assert_ok!(AuctionModule::bid(origin_of(BOB), 0, 30_000));
+			assert_eq!(synthetic_balance(BOB), 0);
+
+			// BOB refunded
+			assert_ok!(AuctionModule::bid(origin_of(ALICE), 0, 300_000));
+			assert_eq!(synthetic_balance(ALICE), 0);
+			assert_eq!(synthetic_balance(BOB), 30_000);
+
+			// only target amount is locked for higher bids
+			assert_ok!(SyntheticCurrency::transfer(&BOB, &ALICE, 30_000));
+			assert_ok!(AuctionModule::bid(origin_of(ALICE), 0, 440_000));
+			assert_eq!(synthetic_balance(ALICE), 0);
+			assert_eq!(synthetic_balance(TestSyntheticTokens::account_id()), 330_000);
+
+			AuctionModule::on_finalize(11);
+
+			// collateral to winner = 1_089_000 * 330_000 / 440_000 = 816_750
+			// surplus = 1_089_000 - 816_750 = 272_250
+			assert_eq!(synthetic_balance(TestSyntheticTokens::account_id()), 0);
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 990_000 + 816_750);
+			assert_eq!(mock_pool_liquidity(), ONE_MILL - 99_000 + 272_250);
+			assert_eq!(collateral_balance(TestSyntheticTokens::account_id()), 0);
+			assert_eq!(position(), (0, 0));
+			assert_eq!(SyntheticProtocol::system_debits(CurrencyId::FEUR), 0);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::CollateralAuctionDealt(0, ALICE, 816_750, 330_000));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn collateral_auction_records_deficit_as_system_debit() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(mint_feur(ALICE, 990_000));
+			set_mock_feur_price(33, 10);
+			assert_ok!(start_collateral_auction());
+
+			// auction end postponed to 9 + 5
+			System::set_block_number(9);
+			assert_ok!(AuctionModule::bid(origin_of(ALICE), 0, 297_000));
+			AuctionModule::on_finalize(11);
+			assert!(SyntheticProtocol::collateral_auctions(0).is_some());
+
+			AuctionModule::on_finalize(14);
+			assert_eq!(SyntheticProtocol::collateral_auctions(0), None);
+
+			// collateral to winner = 1_089_000 * 297_000 / 330_000 = 980_100
+			// collateral backing system debit = 1_089_000 - 980_100 = 108_900
+			assert_eq!(synthetic_balance(ALICE), 33_000);
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 990_000 + 980_100);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 108_900);
+			assert_eq!(collateral_balance(TestSyntheticTokens::account_id()), 0);
+			assert_eq!(mock_pool_liquidity(), ONE_MILL - 99_000);
+			assert_eq!(position(), (0, 0));
+			assert_eq!(SyntheticProtocol::system_debits(CurrencyId::FEUR), 33_000);
+
+			let event = TestEvent::synthetic_protocol(RawEvent::SystemDebitIncreased(CurrencyId::FEUR, 33_000));
+			assert!(System::events().iter().any(|record| record.event == event));
+			let event = TestEvent::synthetic_protocol(RawEvent::CollateralAuctionDealt(0, ALICE, 980_100, 297_000));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn set_fee_rates_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			SyntheticProtocol::set_fee_rates(
+				origin_of(ALICE),
+				CurrencyId::FEUR,
+				Permill::from_percent(1),
+				Permill::from_percent(2)
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			SyntheticProtocol::set_fee_rates(
+				origin_of(BOB),
+				CurrencyId::AUSD,
+				Permill::from_percent(1),
+				Permill::from_percent(2)
+			),
+			Error::<Runtime>::NotValidSyntheticCurrencyId
+		);
+
+		assert_ok!(SyntheticProtocol::set_fee_rates(
+			origin_of(BOB),
+			CurrencyId::FEUR,
+			Permill::from_percent(1),
+			Permill::from_percent(2)
+		));
+		assert_eq!(
+			SyntheticProtocol::mint_fee_rates(CurrencyId::FEUR),
+			Permill::from_percent(1)
+		);
+		assert_eq!(
+			SyntheticProtocol::redeem_fee_rates(CurrencyId::FEUR),
+			Permill::from_percent(2)
+		);
+		let event = TestEvent::synthetic_protocol(RawEvent::FeeRatesUpdated(
+			CurrencyId::FEUR,
+			Permill::from_percent(1),
+			Permill::from_percent(2),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn mint_and_redeem_charge_fees() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyntheticProtocol::set_fee_rates(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(1),
+				Permill::from_percent(2)
+			));
+
+			// fee = 1000 * 1% = 10, synthetic = (1000 - 10) / 3 = 330
+			assert_ok!(mint_feur(ALICE, 1000));
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 10);
+			assert_eq!(synthetic_balance(ALICE), 330);
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::Minted(ALICE, CurrencyId::FEUR, MOCK_POOL, 1000, 330, 10));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			// fee = 330 * 3 * 2% = 19, redeemed = 990 - 19 = 971
+			assert_ok!(redeem_ausd(ALICE, 330));
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 29);
+			assert_eq!(collateral_balance(ALICE), ONE_MILL - 1000 + 971);
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::Redeemed(ALICE, CurrencyId::FEUR, MOCK_POOL, 971, 330, 19));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn mint_and_redeem_quotes_work() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.one_percent_spread()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			// same math as `mint_does_correct_math`
+			let quote = SyntheticProtocol::mint_quote(MOCK_POOL, CurrencyId::FEUR, ONE_MILL).unwrap();
+			assert_eq!(quote.amount, 330_033);
+			assert_eq!(quote.fee, 0);
+			assert_eq!(quote.pool_collateral, 89_109);
+			assert!(quote.is_sufficient);
+
+			// quoting changes nothing
+			assert_eq!(collateral_balance(ALICE), ONE_MILL);
+			assert_eq!(mock_pool_liquidity(), ONE_MILL);
+			assert_eq!(position(), (0, 0));
+
+			assert_ok!(mint_feur(ALICE, ONE_MILL));
+			assert_eq!(synthetic_balance(ALICE), quote.amount);
+
+			// same math as `redeem_does_correct_math`
+			let quote = SyntheticProtocol::redeem_quote(MOCK_POOL, CurrencyId::FEUR, 100_000).unwrap();
+			assert_eq!(quote.amount, 297_000);
+			assert_eq!(quote.fee, 0);
+			assert_eq!(quote.pool_collateral, 33_000);
+			assert!(quote.is_sufficient);
+
+			// more than the position
+			let quote = SyntheticProtocol::redeem_quote(MOCK_POOL, CurrencyId::FEUR, 400_000).unwrap();
+			assert_eq!(quote.pool_collateral, 0);
+			assert!(!quote.is_sufficient);
+
+			assert_noop!(
+				SyntheticProtocol::mint_quote(MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+				Error::<Runtime>::NotValidSyntheticCurrencyId
+			);
+		});
+}
+
+#[test]
+fn mint_and_redeem_quotes_include_fees() {
+	ExtBuilder::default()
+		.balances(vec![(ALICE, CurrencyId::AUSD, ONE_MILL)])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyntheticProtocol::set_fee_rates(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(1),
+				Permill::from_percent(2)
+			));
+
+			// same math as `mint_and_redeem_charge_fees`
+			let quote = SyntheticProtocol::mint_quote(MOCK_POOL, CurrencyId::FEUR, 1000).unwrap();
+			assert_eq!(quote.amount, 330);
+			assert_eq!(quote.fee, 10);
+			// pool has no liquidity
+			assert!(!quote.is_sufficient);
+
+			let quote = SyntheticProtocol::redeem_quote(MOCK_POOL, CurrencyId::FEUR, 330).unwrap();
+			assert_eq!(quote.amount, 971);
+			assert_eq!(quote.fee, 19);
+		});
+}
+
+fn set_time_and_initialize(secs: u64) {
+	Timestamp::set_timestamp(secs * 1000);
+	SyntheticProtocol::on_initialize(System::block_number());
+}
+
+#[test]
+fn set_stability_fee_rate_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			SyntheticProtocol::set_stability_fee_rate(origin_of(ALICE), CurrencyId::FEUR, Permill::from_percent(10)),
+			BadOrigin
+		);
+		assert_noop!(
+			SyntheticProtocol::set_stability_fee_rate(origin_of(BOB), CurrencyId::AUSD, Permill::from_percent(10)),
+			Error::<Runtime>::NotValidSyntheticCurrencyId
+		);
+
+		assert_ok!(SyntheticProtocol::set_stability_fee_rate(
+			origin_of(BOB),
+			CurrencyId::FEUR,
+			Permill::from_percent(10)
+		));
+		assert_eq!(
+			SyntheticProtocol::stability_fee_rates(CurrencyId::FEUR),
+			Permill::from_percent(10)
+		);
+		let event = TestEvent::synthetic_protocol(RawEvent::StabilityFeeRateUpdated(
+			CurrencyId::FEUR,
+			Permill::from_percent(10),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn stability_fee_charged_from_pool_liquidity() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyntheticProtocol::set_stability_fee_rate(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(10)
+			));
+			set_time_and_initialize(1);
+			assert_eq!(SyntheticProtocol::last_stability_fee_time(), 1);
+
+			assert_ok!(mint_feur(ALICE, 3000));
+			assert_eq!(position(), (3300, 1000));
+			let liquidity = mock_pool_liquidity();
+
+			// not charged before interval
+			set_time_and_initialize(1 + 60);
+			assert_eq!(SyntheticProtocol::last_stability_fee_time(), 1);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 0);
+
+			// half a year: 3000 * 10% / 2 = 150
+			Timestamp::set_timestamp((1 + ONE_YEAR / 2) * 1000);
+			assert_eq!(
+				SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR),
+				150
+			);
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(105, 100))
+			);
+
+			set_time_and_initialize(1 + ONE_YEAR / 2);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 150);
+			assert_eq!(mock_pool_liquidity(), liquidity - 150);
+			assert_eq!(SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR), 0);
+			assert_eq!(
+				SyntheticProtocol::stability_fee_history(MOCK_POOL, CurrencyId::FEUR),
+				vec![(1 + ONE_YEAR / 2, 150)]
+			);
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::StabilityFeeCharged(CurrencyId::FEUR, MOCK_POOL, 150, 0));
+			assert!(System::events().iter().any(|record| record.event == event));
+		});
+}
+
+#[test]
+fn stability_fee_left_unpaid_if_not_enough_liquidity() {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(MOCK_POOL, CurrencyId::AUSD, 400),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyntheticProtocol::set_stability_fee_rate(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(10)
+			));
+			set_time_and_initialize(1);
+			assert_ok!(mint_feur(ALICE, 3000));
+			assert_eq!(mock_pool_liquidity(), 100);
+
+			set_time_and_initialize(1 + ONE_YEAR / 2);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 100);
+			assert_eq!(mock_pool_liquidity(), 0);
+			assert_eq!(
+				SyntheticProtocol::unpaid_stability_fees(MOCK_POOL, CurrencyId::FEUR),
+				50
+			);
+			assert_eq!(
+				SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR),
+				50
+			);
+			let event =
+				TestEvent::synthetic_protocol(RawEvent::StabilityFeeCharged(CurrencyId::FEUR, MOCK_POOL, 100, 50));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			// unpaid fee is charged with new fee once liquidity is available
+			assert_ok!(CollateralCurrency::transfer(&ALICE, &MOCK_POOL, 1000));
+			set_time_and_initialize(1 + ONE_YEAR);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 300);
+			assert_eq!(SyntheticProtocol::unpaid_stability_fees(MOCK_POOL, CurrencyId::FEUR), 0);
+			assert_eq!(
+				SyntheticProtocol::stability_fee_history(MOCK_POOL, CurrencyId::FEUR),
+				vec![(1 + ONE_YEAR / 2, 100), (1 + ONE_YEAR, 200)]
+			);
+		});
+}
+
+#[test]
+fn stability_fee_charging_resumes_on_later_blocks() {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+			(ANOTHER_MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::FJPY, Some(Price::saturating_from_rational(1, 2)));
+			for currency_id in vec![CurrencyId::FEUR, CurrencyId::FJPY] {
+				assert_ok!(SyntheticProtocol::set_stability_fee_rate(
+					origin_of(BOB),
+					currency_id,
+					Permill::from_percent(10)
+				));
+			}
+			set_time_and_initialize(1);
+			assert_ok!(mint_feur(ALICE, 3000));
+			assert_ok!(SyntheticProtocol::mint(
+				origin_of(ALICE),
+				ANOTHER_MOCK_POOL,
+				CurrencyId::FJPY,
+				3000,
+				Price::saturating_from_integer(1),
+			));
+
+			// 4 positions to check, 3 checked in this block, FJPY of `ANOTHER_MOCK_POOL` is the last
+			set_time_and_initialize(1 + ONE_YEAR / 2);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 150);
+			assert_eq!(
+				SyntheticProtocol::stability_fee_charging(),
+				Some(StabilityFeeCursor {
+					time: 1 + ONE_YEAR / 2,
+					pool_id: ANOTHER_MOCK_POOL,
+					currency_index: 1,
+				})
+			);
+			assert_eq!(SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR), 0);
+			assert_eq!(
+				SyntheticProtocol::accrued_stability_fee(ANOTHER_MOCK_POOL, CurrencyId::FJPY),
+				150
+			);
+
+			System::set_block_number(2);
+			SyntheticProtocol::on_initialize(System::block_number());
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 300);
+			assert_eq!(SyntheticProtocol::stability_fee_charging(), None);
+			assert_eq!(
+				SyntheticProtocol::accrued_stability_fee(ANOTHER_MOCK_POOL, CurrencyId::FJPY),
+				0
+			);
+			assert_eq!(
+				SyntheticProtocol::stability_fee_history(ANOTHER_MOCK_POOL, CurrencyId::FJPY),
+				vec![(1 + ONE_YEAR / 2, 150)]
+			);
+			assert_eq!(SyntheticProtocol::last_stability_fee_time(), 1 + ONE_YEAR / 2);
+		});
+}
+
+#[test]
+fn stability_fee_accrued_before_position_changes() {
+	ExtBuilder::default()
+		.one_million_for_alice_n_mock_pool()
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			assert_ok!(SyntheticProtocol::set_stability_fee_rate(
+				origin_of(BOB),
+				CurrencyId::FEUR,
+				Permill::from_percent(10)
+			));
+			set_time_and_initialize(1);
+			assert_ok!(mint_feur(ALICE, 3000));
+			assert_eq!(
+				SyntheticProtocol::last_stability_fee_accrual_time(MOCK_POOL, CurrencyId::FEUR),
+				1
+			);
+
+			// half a year with 1000 synthetic: 3000 * 10% / 2 = 150
+			Timestamp::set_timestamp((1 + ONE_YEAR / 2) * 1000);
+			assert_ok!(redeem_ausd(ALICE, 500));
+			assert_eq!(
+				SyntheticProtocol::unpaid_stability_fees(MOCK_POOL, CurrencyId::FEUR),
+				150
+			);
+			assert_eq!(
+				SyntheticProtocol::last_stability_fee_accrual_time(MOCK_POOL, CurrencyId::FEUR),
+				1 + ONE_YEAR / 2
+			);
+
+			// another half a year with 500 synthetic: 1500 * 10% / 2 = 75
+			Timestamp::set_timestamp((1 + ONE_YEAR) * 1000);
+			assert_eq!(
+				SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR),
+				225
+			);
+
+			set_time_and_initialize(1 + ONE_YEAR);
+			assert_eq!(collateral_balance(TREASURY_ACCOUNT), 225);
+			assert_eq!(SyntheticProtocol::accrued_stability_fee(MOCK_POOL, CurrencyId::FEUR), 0);
+			assert_eq!(
+				SyntheticProtocol::stability_fee_history(MOCK_POOL, CurrencyId::FEUR),
+				vec![(1 + ONE_YEAR, 225)]
+			);
+		});
+}
+
+#[test]
+fn set_collateral_currency_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			SyntheticProtocol::set_collateral_currency(
+				origin_of(ALICE),
+				CurrencyId::LAMI,
+				Some(Permill::from_percent(20))
+			),
+			BadOrigin
+		);
+		assert_noop!(
+			SyntheticProtocol::set_collateral_currency(
+				origin_of(BOB),
+				CurrencyId::AUSD,
+				Some(Permill::from_percent(20))
+			),
+			Error::<Runtime>::InvalidCollateralCurrency
+		);
+
+		assert_ok!(SyntheticProtocol::set_collateral_currency(
+			origin_of(BOB),
+			CurrencyId::LAMI,
+			Some(Permill::from_percent(20))
+		));
+		assert_eq!(
+			SyntheticProtocol::collateral_haircuts(CurrencyId::LAMI),
+			Some(Permill::from_percent(20))
+		);
+		let event = TestEvent::synthetic_protocol(RawEvent::CollateralCurrencyUpdated(
+			CurrencyId::LAMI,
+			Some(Permill::from_percent(20)),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+
+		assert_ok!(SyntheticProtocol::set_collateral_currency(
+			origin_of(BOB),
+			CurrencyId::LAMI,
+			None
+		));
+		assert_eq!(SyntheticProtocol::collateral_haircuts(CurrencyId::LAMI), None);
+	});
+}
+
+#[test]
+fn extra_collateral_works() {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(ALICE, CurrencyId::LAMI, 100_000),
+			(MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::LAMI, Some(Price::saturating_from_rational(1, 2)));
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_eq!(position(), (1_089_000, 330_000));
+
+			assert_noop!(
+				SyntheticProtocol::add_extra_collateral(
+					origin_of(ALICE),
+					MOCK_POOL,
+					CurrencyId::FEUR,
+					CurrencyId::LAMI,
+					100_000
+				),
+				Error::<Runtime>::CollateralCurrencyNotWhitelisted
+			);
+
+			assert_ok!(SyntheticProtocol::set_collateral_currency(
+				origin_of(BOB),
+				CurrencyId::LAMI,
+				Some(Permill::from_percent(20))
+			));
+			assert_ok!(SyntheticProtocol::add_extra_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				CurrencyId::LAMI,
+				100_000
+			));
+			assert_eq!(
+				TestSyntheticTokens::get_extra_collateral(MOCK_POOL, CurrencyId::FEUR, CurrencyId::LAMI),
+				100_000
+			);
+			assert_eq!(
+				orml_currencies::Module::<Runtime>::free_balance(CurrencyId::LAMI, &ALICE),
+				0
+			);
+			let event = TestEvent::synthetic_protocol(RawEvent::ExtraCollateralAdded(
+				ALICE,
+				CurrencyId::FEUR,
+				MOCK_POOL,
+				CurrencyId::LAMI,
+				100_000,
+			));
+			assert!(System::events().iter().any(|record| record.event == event));
+
+			// extra collateral value = 100_000 * 0.5 * (1 - 0.2) = 40_000
+			// ratio = (1_089_000 + 40_000) / (330_000 * 3.2) ~= 1.0691
+			set_mock_feur_price(32, 10);
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(1_129_000, 1_056_000))
+			);
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(true)
+			);
+			assert_noop!(
+				SyntheticProtocol::force_add_collateral(Origin::none(), MOCK_POOL, CurrencyId::FEUR),
+				Error::<Runtime>::StillInSafePosition
+			);
+
+			// required = 330_000 * 3.2 * 1.1 = 1_161_600
+			assert_noop!(
+				SyntheticProtocol::withdraw_extra_collateral(
+					origin_of(ALICE),
+					MOCK_POOL,
+					CurrencyId::FEUR,
+					CurrencyId::LAMI,
+					10_000
+				),
+				Error::<Runtime>::CollateralRatioTooLow
+			);
+			assert_noop!(
+				SyntheticProtocol::withdraw_extra_collateral(
+					origin_of(BOB),
+					MOCK_POOL,
+					CurrencyId::FEUR,
+					CurrencyId::LAMI,
+					10_000
+				),
+				Error::<Runtime>::NoPermission
+			);
+			assert_noop!(
+				SyntheticProtocol::withdraw_extra_collateral(
+					origin_of(ALICE),
+					MOCK_POOL,
+					CurrencyId::AUSD,
+					CurrencyId::LAMI,
+					10_000
+				),
+				Error::<Runtime>::NotValidSyntheticCurrencyId
+			);
+
+			// not counted if removed from whitelist
+			assert_ok!(SyntheticProtocol::set_collateral_currency(
+				origin_of(BOB),
+				CurrencyId::LAMI,
+				None
+			));
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(false)
+			);
+
+			// required = 330_000 * 3 * 1.1 = 1_089_000
+			set_mock_feur_price(3, 1);
+			assert_ok!(SyntheticProtocol::withdraw_extra_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				CurrencyId::LAMI,
+				100_000
+			));
+			assert_eq!(
+				TestSyntheticTokens::get_extra_collateral(MOCK_POOL, CurrencyId::FEUR, CurrencyId::LAMI),
+				0
+			);
+			assert_eq!(
+				orml_currencies::Module::<Runtime>::free_balance(CurrencyId::LAMI, &ALICE),
+				100_000
+			);
+		});
+}
+
+#[test]
+fn extra_collateral_priced_with_usable_risk_prices() {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(ALICE, CurrencyId::LAMI, 100_000),
+			(MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::LAMI, Some(Price::saturating_from_rational(1, 2)));
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_ok!(SyntheticProtocol::set_collateral_currency(
+				origin_of(BOB),
+				CurrencyId::LAMI,
+				Some(Permill::from_percent(20))
+			));
+			assert_ok!(SyntheticProtocol::add_extra_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				CurrencyId::LAMI,
+				100_000
+			));
+
+			// extra collateral value = 100_000 * 0.5 * (1 - 0.2) = 40_000
+			set_mock_feur_price(32, 10);
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(true)
+			);
+
+			// extra collateral value with risk price = 100_000 * 0.1 * (1 - 0.2) = 8_000
+			// ratio = (1_089_000 + 8_000) / (330_000 * 3.2) ~= 1.0388
+			MockRiskPrices::set_mock_price(CurrencyId::LAMI, Price::saturating_from_rational(1, 10));
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(false)
+			);
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(1_129_000, 1_056_000))
+			);
+
+			// not counted if the price is stale
+			MockPriceGuard::set_stale(CurrencyId::LAMI, PriceUsage::Trading);
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(1_089_000, 1_056_000))
+			);
+			MockPriceGuard::reset();
+		});
+}
+
+#[test]
+fn extra_collateral_not_counted_if_collateral_position_below_synthetic_value() {
+	ExtBuilder::default()
+		.balances(vec![
+			(ALICE, CurrencyId::AUSD, ONE_MILL),
+			(ALICE, CurrencyId::LAMI, ONE_MILL),
+			(MOCK_POOL, CurrencyId::AUSD, ONE_MILL),
+		])
+		.synthetic_price_three()
+		.ten_percent_additional_collateral_ratio()
+		.build()
+		.execute_with(|| {
+			MockPrices::set_mock_price(CurrencyId::LAMI, Some(Price::saturating_from_rational(1, 2)));
+			assert_ok!(mint_feur(ALICE, 990_000));
+			assert_ok!(SyntheticProtocol::set_collateral_currency(
+				origin_of(BOB),
+				CurrencyId::LAMI,
+				Some(Permill::from_percent(20))
+			));
+			assert_ok!(SyntheticProtocol::add_extra_collateral(
+				origin_of(ALICE),
+				MOCK_POOL,
+				CurrencyId::FEUR,
+				CurrencyId::LAMI,
+				ONE_MILL
+			));
+
+			// extra collateral value = 1_000_000 * 0.5 * (1 - 0.2) = 400_000
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(1_489_000, 990_000))
+			);
+
+			// synthetic value = 330_000 * 3.4 = 1_122_000, higher than collateral position
+			set_mock_feur_price(34, 10);
+			assert_eq!(
+				SyntheticProtocol::collateral_ratio(MOCK_POOL, CurrencyId::FEUR),
+				Some(Price::saturating_from_rational(1_089_000, 1_122_000))
+			);
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(false)
+			);
+
+			// the collateral position is restored to synthetic value, extra collateral counted after that
+			assert_ok!(SyntheticProtocol::force_add_collateral(
+				Origin::none(),
+				MOCK_POOL,
+				CurrencyId::FEUR
+			));
+			assert_eq!(position(), (1_122_000, 330_000));
+			assert_eq!(
+				SyntheticProtocol::is_safe_position(MOCK_POOL, CurrencyId::FEUR),
+				Ok(true)
+			);
+		});
+}
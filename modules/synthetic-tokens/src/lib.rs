@@ -2,7 +2,8 @@
 
 use codec::{Decode, Encode};
 use frame_support::{
-	decl_error, decl_event, decl_module, decl_storage,
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	storage::{migration::StorageIterator, IterableStorageDoubleMap},
 	traits::{EnsureOrigin, Get},
 	weights::Weight,
 };
@@ -10,7 +11,7 @@ use laminar_primitives::{Balance, CurrencyId, LiquidityPoolId};
 use module_traits::BaseLiquidityPoolManager;
 use orml_utilities::with_transaction_result;
 use sp_runtime::{
-	traits::{AccountIdConversion, CheckedDiv, CheckedSub, Zero},
+	traits::{AccountIdConversion, CheckedDiv, CheckedSub, Saturating, Zero},
 	DispatchResult, FixedPointNumber, FixedU128, ModuleId, Permill, RuntimeDebug,
 };
 use sp_std::prelude::Vec;
@@ -26,6 +27,7 @@ pub trait WeightInfo {
 	fn set_extreme_ratio() -> Weight;
 	fn set_liquidation_ratio() -> Weight;
 	fn set_collateral_ratio() -> Weight;
+	fn set_ratios() -> Weight;
 }
 
 pub trait Config: frame_system::Config {
@@ -75,18 +77,68 @@ pub struct SyntheticTokensRatio {
 	pub collateral: Option<Permill>,
 }
 
+/// Storage version of the module, for storage migrations.
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Releases {
+	/// Ratios of each currency stored together in `Ratios`.
+	V1,
+	/// Ratios of each currency stored separately.
+	V2,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1
+	}
+}
+
 decl_storage! {
 	trait Store for Module<T: Config> as SyntheticTokens {
-		/// Ratios for each currency.
-		Ratios get(fn ratios) config(): map hasher(twox_64_concat) CurrencyId => SyntheticTokensRatio;
+		/// Extreme liquidation ratio for each currency.
+		///
+		/// Ratios are stored as plain values, so that they could be gradually updated.
+		pub ExtremeRatios: map hasher(twox_64_concat) CurrencyId => Option<Permill>;
+
+		/// Liquidation ratio for each currency.
+		pub LiquidationRatios: map hasher(twox_64_concat) CurrencyId => Option<Permill>;
+
+		/// Collateral ratio for each currency.
+		pub CollateralRatios: map hasher(twox_64_concat) CurrencyId => Option<Permill>;
 
 		/// Positions of a currency in a pool
 		Positions get(fn positions): double_map hasher(twox_64_concat) LiquidityPoolId, hasher(twox_64_concat) CurrencyId => Position;
+
+		/// User-owned vaults of a currency in a pool, collateralized by the owner only.
+		Vaults get(fn vaults): double_map hasher(twox_64_concat) (LiquidityPoolId, CurrencyId), hasher(twox_64_concat) T::AccountId => Position;
+
+		/// Extra collaterals of a currency in a pool, by collateral currency.
+		ExtraCollaterals: double_map hasher(twox_64_concat) (LiquidityPoolId, CurrencyId), hasher(twox_64_concat) CurrencyId => Balance;
+
+		/// Storage version of the module. New chains start with the latest version.
+		StorageVersion get(fn storage_version) build(|_: &GenesisConfig| Releases::V2): Releases;
+	}
+
+	add_extra_genesis {
+		config(ratios): Vec<(CurrencyId, SyntheticTokensRatio)>;
+
+		build(|config: &GenesisConfig| {
+			config.ratios.iter().for_each(|(currency_id, ratios)| {
+				assert!(
+					Module::<T>::checked_ratios(ratios).is_some(),
+					"ratios of {:?} must be `extreme < liquidation < collateral`",
+					currency_id,
+				);
+				Module::<T>::set_ratios_unchecked(*currency_id, ratios.clone());
+			})
+		})
 	}
 }
 
 decl_error! {
-	pub enum Error for Module<T: Config> {}
+	pub enum Error for Module<T: Config> {
+		/// Ratios must satisfy `extreme < liquidation < collateral`.
+		InvalidRatios,
+	}
 }
 
 decl_event! {
@@ -99,6 +151,13 @@ decl_event! {
 
 		/// Collateral ratio updated: \[currency_id, ratio\]
 		CollateralRatioUpdated(CurrencyId, Permill),
+
+		/// Ratios updated: \[currency_id, extreme_ratio, liquidation_ratio, collateral_ratio\]
+		RatiosUpdated(CurrencyId, Permill, Permill, Permill),
+
+		/// Ratios written without validation raised to be ordered: \[currency_id, extreme_ratio,
+		/// liquidation_ratio, collateral_ratio\]
+		RatiosClamped(CurrencyId, Permill, Permill, Permill),
 	}
 }
 
@@ -113,6 +172,14 @@ decl_module! {
 		const DefaultCollateralRatio: Permill = T::DefaultCollateralRatio::get();
 		const SyntheticCurrencyIds: Vec<CurrencyId> = T::SyntheticCurrencyIds::get();
 
+		fn on_initialize() -> Weight {
+			Self::clamp_ratios()
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_ratios()
+		}
+
 		/// Set extreme liquidation ratio.
 		///
 		/// May only be called from `UpdateOrigin`.
@@ -120,7 +187,7 @@ decl_module! {
 		pub fn set_extreme_ratio(origin, currency_id: CurrencyId, #[compact] ratio: Permill) {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
-				Ratios::mutate(currency_id, |r| r.extreme = Some(ratio));
+				Self::do_update_ratios(currency_id, |r| r.extreme = Some(ratio))?;
 				Self::deposit_event(Event::ExtremeRatioUpdated(currency_id, ratio));
 				Ok(())
 			})?;
@@ -133,7 +200,7 @@ decl_module! {
 		pub fn set_liquidation_ratio(origin, currency_id: CurrencyId, #[compact] ratio: Permill) {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
-				Ratios::mutate(currency_id, |r| r.liquidation = Some(ratio));
+				Self::do_update_ratios(currency_id, |r| r.liquidation = Some(ratio))?;
 				Self::deposit_event(Event::LiquidationRatioUpdated(currency_id, ratio));
 				Ok(())
 			})?;
@@ -146,11 +213,36 @@ decl_module! {
 		pub fn set_collateral_ratio(origin, currency_id: CurrencyId, #[compact] ratio: Permill) {
 			with_transaction_result(|| {
 				T::UpdateOrigin::ensure_origin(origin)?;
-				Ratios::mutate(currency_id, |r| r.collateral = Some(ratio));
+				Self::do_update_ratios(currency_id, |r| r.collateral = Some(ratio))?;
 				Self::deposit_event(Event::CollateralRatioUpdated(currency_id, ratio));
 				Ok(())
 			})?;
 		}
+
+		/// Set extreme liquidation, liquidation and collateral ratios together.
+		///
+		/// May only be called from `UpdateOrigin`.
+		#[weight = T::WeightInfo::set_ratios()]
+		pub fn set_ratios(
+			origin,
+			currency_id: CurrencyId,
+			#[compact] extreme: Permill,
+			#[compact] liquidation: Permill,
+			#[compact] collateral: Permill,
+		) {
+			with_transaction_result(|| {
+				T::UpdateOrigin::ensure_origin(origin)?;
+				Self::do_update_ratios(currency_id, |r| {
+					*r = SyntheticTokensRatio {
+						extreme: Some(extreme),
+						liquidation: Some(liquidation),
+						collateral: Some(collateral),
+					}
+				})?;
+				Self::deposit_event(Event::RatiosUpdated(currency_id, extreme, liquidation, collateral));
+				Ok(())
+			})?;
+		}
 	}
 }
 
@@ -160,6 +252,94 @@ decl_module! {
 /// DO NOT change this in runtime upgrade without migration.
 const MODULE_ID: ModuleId = ModuleId(*b"lami/stk");
 
+impl<T: Config> Module<T> {
+	/// Update ratios of `currency_id` with `f`. Would fail if updated ratios, with default ratios
+	/// for ones not set, are not `extreme < liquidation < collateral`.
+	fn do_update_ratios(currency_id: CurrencyId, f: impl FnOnce(&mut SyntheticTokensRatio)) -> DispatchResult {
+		let mut ratios = Self::ratios(currency_id);
+		f(&mut ratios);
+		ensure!(Self::checked_ratios(&ratios).is_some(), Error::<T>::InvalidRatios);
+
+		Self::set_ratios_unchecked(currency_id, ratios);
+		Ok(())
+	}
+
+	/// Returns `(extreme, liquidation, collateral)` of `ratios`, with default ratios for ones not
+	/// set. `None` if not `extreme < liquidation < collateral`.
+	fn checked_ratios(ratios: &SyntheticTokensRatio) -> Option<(Permill, Permill, Permill)> {
+		let extreme = ratios.extreme.unwrap_or_else(T::DefaultExtremeRatio::get);
+		let liquidation = ratios.liquidation.unwrap_or_else(T::DefaultLiquidationRatio::get);
+		let collateral = ratios.collateral.unwrap_or_else(T::DefaultCollateralRatio::get);
+		if extreme < liquidation && liquidation < collateral {
+			Some((extreme, liquidation, collateral))
+		} else {
+			None
+		}
+	}
+
+	/// Returns `(extreme, liquidation, collateral)` of `ratios`, with default ratios for ones not
+	/// set. Liquidation and collateral ratios are raised, if needed, to be `extreme < liquidation
+	/// < collateral`.
+	fn clamped_ratios(ratios: &SyntheticTokensRatio) -> (Permill, Permill, Permill) {
+		let step = Permill::from_parts(1);
+		let extreme = ratios
+			.extreme
+			.unwrap_or_else(T::DefaultExtremeRatio::get)
+			// leave room for the other two
+			.min(Permill::from_parts(999_998));
+		let liquidation = ratios
+			.liquidation
+			.unwrap_or_else(T::DefaultLiquidationRatio::get)
+			.max(extreme.saturating_add(step));
+		let collateral = ratios
+			.collateral
+			.unwrap_or_else(T::DefaultCollateralRatio::get)
+			.max(liquidation.saturating_add(step));
+		(extreme, liquidation, collateral)
+	}
+
+	/// Clamp ratios of synthetic currencies written without validation, for instance by gradual
+	/// updates, and store the clamped ones.
+	fn clamp_ratios() -> Weight {
+		let currency_ids = T::SyntheticCurrencyIds::get();
+		let mut clamped_count = 0;
+		for currency_id in currency_ids.iter() {
+			let ratios = Self::ratios(*currency_id);
+			if Self::checked_ratios(&ratios).is_some() {
+				continue;
+			}
+
+			let (extreme, liquidation, collateral) = Self::clamped_ratios(&ratios);
+			// only store changed ones, so that ones not set still follow default ratios
+			let changed = |ratio: Option<Permill>, default: Permill, clamped: Permill| {
+				if ratio.unwrap_or(default) == clamped {
+					ratio
+				} else {
+					Some(clamped)
+				}
+			};
+			Self::set_ratios_unchecked(
+				*currency_id,
+				SyntheticTokensRatio {
+					extreme: changed(ratios.extreme, T::DefaultExtremeRatio::get(), extreme),
+					liquidation: changed(ratios.liquidation, T::DefaultLiquidationRatio::get(), liquidation),
+					collateral: changed(ratios.collateral, T::DefaultCollateralRatio::get(), collateral),
+				},
+			);
+			clamped_count += 1;
+			Self::deposit_event(Event::RatiosClamped(*currency_id, extreme, liquidation, collateral));
+		}
+
+		T::DbWeight::get().reads_writes(currency_ids.len() as Weight * 3, clamped_count * 3)
+	}
+
+	fn set_ratios_unchecked(currency_id: CurrencyId, ratios: SyntheticTokensRatio) {
+		ExtremeRatios::mutate(currency_id, |r| *r = ratios.extreme);
+		LiquidationRatios::mutate(currency_id, |r| *r = ratios.liquidation);
+		CollateralRatios::mutate(currency_id, |r| *r = ratios.collateral);
+	}
+}
+
 impl<T: Config> Module<T> {
 	pub fn account_id() -> T::AccountId {
 		MODULE_ID.into_account()
@@ -186,6 +366,89 @@ impl<T: Config> Module<T> {
 		(collateral, synthetic)
 	}
 
+	/// Get all positions with synthetic issued. Returns a list of `(pool_id, currency_id)`.
+	pub fn positions_with_synthetic() -> Vec<(LiquidityPoolId, CurrencyId)> {
+		Positions::iter()
+			.filter(|(_, _, p)| !p.synthetic.is_zero())
+			.map(|(pool_id, currency_id, _)| (pool_id, currency_id))
+			.collect()
+	}
+
+	pub fn add_vault(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+		synthetic: Balance,
+	) {
+		<Vaults<T>>::mutate((pool_id, currency_id), who, |v| {
+			v.collateral = v.collateral.saturating_add(collateral);
+			v.synthetic = v.synthetic.saturating_add(synthetic)
+		});
+	}
+
+	/// Remove `collateral` and `synthetic` from a vault. The vault will be removed if empty.
+	pub fn remove_vault(
+		who: &T::AccountId,
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral: Balance,
+		synthetic: Balance,
+	) {
+		let mut v = <Vaults<T>>::take((pool_id, currency_id), who);
+		v.collateral = v.collateral.saturating_sub(collateral);
+		v.synthetic = v.synthetic.saturating_sub(synthetic);
+		if !v.collateral.is_zero() || !v.synthetic.is_zero() {
+			<Vaults<T>>::insert((pool_id, currency_id), who, v);
+		}
+	}
+
+	/// Get vault of `who` under `pool_id` and `currency_id`. Returns `(collateral_amount,
+	/// synthetic_amount)`.
+	pub fn get_vault(who: &T::AccountId, pool_id: LiquidityPoolId, currency_id: CurrencyId) -> (Balance, Balance) {
+		let Position { collateral, synthetic } = <Vaults<T>>::get((pool_id, currency_id), who);
+		(collateral, synthetic)
+	}
+
+	pub fn add_extra_collateral(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_currency_id: CurrencyId,
+		amount: Balance,
+	) {
+		ExtraCollaterals::mutate((pool_id, currency_id), collateral_currency_id, |c| {
+			*c = c.saturating_add(amount)
+		});
+	}
+
+	/// Remove `amount` from extra collateral. The record will be removed if empty.
+	pub fn remove_extra_collateral(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_currency_id: CurrencyId,
+		amount: Balance,
+	) {
+		let remaining = ExtraCollaterals::take((pool_id, currency_id), collateral_currency_id).saturating_sub(amount);
+		if !remaining.is_zero() {
+			ExtraCollaterals::insert((pool_id, currency_id), collateral_currency_id, remaining);
+		}
+	}
+
+	/// Get extra collateral of `collateral_currency_id` under `pool_id` and `currency_id`.
+	pub fn get_extra_collateral(
+		pool_id: LiquidityPoolId,
+		currency_id: CurrencyId,
+		collateral_currency_id: CurrencyId,
+	) -> Balance {
+		ExtraCollaterals::get((pool_id, currency_id), collateral_currency_id)
+	}
+
+	/// All extra collaterals under `pool_id` and `currency_id`. Returns `(collateral_currency_id,
+	/// amount)` pairs.
+	pub fn extra_collaterals(pool_id: LiquidityPoolId, currency_id: CurrencyId) -> Vec<(CurrencyId, Balance)> {
+		ExtraCollaterals::iter_prefix((pool_id, currency_id)).collect()
+	}
+
 	/// Calculate incentive ratio.
 	///
 	/// If `ratio < extreme_ratio`, return `1`; if `ratio >= liquidation_ratio`, return `0`;
@@ -223,31 +486,68 @@ impl<T: Config> Module<T> {
 }
 
 impl<T: Config> Module<T> {
+	/// Ratios of `currency_id`, `None` if not set.
+	pub fn ratios(currency_id: CurrencyId) -> SyntheticTokensRatio {
+		SyntheticTokensRatio {
+			extreme: ExtremeRatios::get(currency_id),
+			liquidation: LiquidationRatios::get(currency_id),
+			collateral: CollateralRatios::get(currency_id),
+		}
+	}
+
+	/// Ratios of `currency_id` as `(extreme, liquidation, collateral)`, with default ratios for
+	/// ones not set.
+	///
+	/// Ratios could be written without validation, for instance by gradual updates. They are
+	/// clamped until stored by `on_initialize`.
+	fn ratios_or_default(currency_id: CurrencyId) -> (Permill, Permill, Permill) {
+		Self::clamped_ratios(&Self::ratios(currency_id))
+	}
+
 	pub fn liquidation_ratio_or_default(currency_id: CurrencyId) -> Permill {
-		Self::ratios(currency_id)
-			.liquidation
-			.unwrap_or_else(T::DefaultLiquidationRatio::get)
+		Self::ratios_or_default(currency_id).1
 	}
 
 	pub fn extreme_ratio_or_default(currency_id: CurrencyId) -> Permill {
-		Self::ratios(currency_id)
-			.extreme
-			.unwrap_or_else(T::DefaultExtremeRatio::get)
+		Self::ratios_or_default(currency_id).0
 	}
 
 	pub fn collateral_ratio_or_default(currency_id: CurrencyId) -> Permill {
-		Self::ratios(currency_id)
-			.collateral
-			.unwrap_or_else(T::DefaultCollateralRatio::get)
+		Self::ratios_or_default(currency_id).2
+	}
+}
+
+// Storage migrations
+impl<T: Config> Module<T> {
+	/// Split legacy `Ratios` of each currency into `ExtremeRatios`, `LiquidationRatios` and
+	/// `CollateralRatios`.
+	fn migrate_ratios() -> Weight {
+		if Self::storage_version() >= Releases::V2 {
+			return T::DbWeight::get().reads(1);
+		}
+
+		let mut count = 0;
+		for (key, ratios) in StorageIterator::<SyntheticTokensRatio>::new(b"SyntheticTokens", b"Ratios").drain() {
+			// `twox_64_concat` hashed key: 8 bytes hash followed by the encoded currency ID
+			if let Some(currency_id) = key.get(8..).and_then(|mut k| CurrencyId::decode(&mut k).ok()) {
+				Self::set_ratios_unchecked(currency_id, ratios);
+				count += 1;
+			}
+		}
+
+		StorageVersion::put(Releases::V2);
+
+		T::DbWeight::get().reads_writes(count + 2, count * 4 + 1)
 	}
 }
 
 impl<T: Config> BaseLiquidityPoolManager<LiquidityPoolId, Balance> for Module<T> {
 	fn can_remove(pool_id: LiquidityPoolId) -> bool {
-		T::SyntheticCurrencyIds::get()
-			.iter()
-			.map(|currency_id| -> (Balance, Balance) { Self::get_position(pool_id, *currency_id) })
-			.all(|x| x.1.is_zero())
+		T::SyntheticCurrencyIds::get().iter().all(|currency_id| {
+			Self::get_position(pool_id, *currency_id).1.is_zero()
+				&& <Vaults<T>>::iter_prefix((pool_id, *currency_id)).next().is_none()
+				&& ExtraCollaterals::iter_prefix((pool_id, *currency_id)).next().is_none()
+		})
 	}
 
 	fn ensure_can_withdraw(_pool: LiquidityPoolId, _amount: Balance) -> DispatchResult {
@@ -5,7 +5,12 @@
 use super::*;
 use mock::*;
 
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::{get_storage_value, put_storage_value},
+	traits::{OnInitialize, OnRuntimeUpgrade},
+	StorageHasher, Twox64Concat,
+};
 use sp_runtime::{
 	traits::{BadOrigin, Saturating},
 	Permill,
@@ -23,7 +28,7 @@ fn root_set_extreme_ratio() {
 	ExtBuilder::default().build().execute_with(|| {
 		assert_eq!(SyntheticTokens::ratios(CurrencyId::FEUR).extreme, None);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(2);
 		assert_ok!(SyntheticTokens::set_extreme_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -39,7 +44,7 @@ fn root_set_extreme_ratio() {
 #[test]
 fn non_root_set_extreme_ratio_fails() {
 	ExtBuilder::default().build().execute_with(|| {
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(2);
 
 		assert_noop!(
 			SyntheticTokens::set_extreme_ratio(bob(), CurrencyId::FEUR, ratio),
@@ -55,7 +60,7 @@ fn root_set_liquidation_ratio() {
 	ExtBuilder::default().build().execute_with(|| {
 		assert_eq!(SyntheticTokens::ratios(CurrencyId::FEUR).liquidation, None);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(6);
 		assert_ok!(SyntheticTokens::set_liquidation_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -71,7 +76,7 @@ fn root_set_liquidation_ratio() {
 #[test]
 fn non_root_set_liquidation_ratio_fails() {
 	ExtBuilder::default().build().execute_with(|| {
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(6);
 		assert_noop!(
 			SyntheticTokens::set_liquidation_ratio(bob(), CurrencyId::FEUR, ratio),
 			BadOrigin
@@ -86,7 +91,7 @@ fn root_set_collateral_ratio() {
 	ExtBuilder::default().build().execute_with(|| {
 		assert_eq!(SyntheticTokens::ratios(CurrencyId::FEUR).collateral, None);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(20);
 		assert_ok!(SyntheticTokens::set_collateral_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -102,7 +107,7 @@ fn root_set_collateral_ratio() {
 #[test]
 fn non_root_set_collateral_ratio_fails() {
 	ExtBuilder::default().build().execute_with(|| {
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(20);
 		assert_noop!(
 			SyntheticTokens::set_collateral_ratio(bob(), CurrencyId::FEUR, ratio),
 			BadOrigin
@@ -112,6 +117,111 @@ fn non_root_set_collateral_ratio_fails() {
 	});
 }
 
+#[test]
+fn set_ratios_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			SyntheticTokens::set_ratios(
+				bob(),
+				CurrencyId::FEUR,
+				Permill::from_percent(2),
+				Permill::from_percent(6),
+				Permill::from_percent(20)
+			),
+			BadOrigin
+		);
+
+		assert_ok!(SyntheticTokens::set_ratios(
+			alice(),
+			CurrencyId::FEUR,
+			Permill::from_percent(2),
+			Permill::from_percent(6),
+			Permill::from_percent(20)
+		));
+		assert_eq!(
+			SyntheticTokens::ratios(CurrencyId::FEUR),
+			SyntheticTokensRatio {
+				extreme: Some(Permill::from_percent(2)),
+				liquidation: Some(Permill::from_percent(6)),
+				collateral: Some(Permill::from_percent(20)),
+			}
+		);
+
+		let event = TestEvent::synthetic_tokens(Event::RatiosUpdated(
+			CurrencyId::FEUR,
+			Permill::from_percent(2),
+			Permill::from_percent(6),
+			Permill::from_percent(20),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+fn set_ratios_fails_if_not_ordered() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			SyntheticTokens::set_ratios(
+				alice(),
+				CurrencyId::FEUR,
+				Permill::from_percent(6),
+				Permill::from_percent(6),
+				Permill::from_percent(20)
+			),
+			Error::<Runtime>::InvalidRatios
+		);
+		assert_noop!(
+			SyntheticTokens::set_ratios(
+				alice(),
+				CurrencyId::FEUR,
+				Permill::from_percent(2),
+				Permill::from_percent(20),
+				Permill::from_percent(6)
+			),
+			Error::<Runtime>::InvalidRatios
+		);
+	});
+}
+
+#[test]
+fn set_single_ratio_fails_if_not_ordered_with_defaults() {
+	ExtBuilder::default().build().execute_with(|| {
+		// defaults: extreme 1%, liquidation 5%, collateral 10%
+		assert_noop!(
+			SyntheticTokens::set_extreme_ratio(alice(), CurrencyId::FEUR, Permill::from_percent(5)),
+			Error::<Runtime>::InvalidRatios
+		);
+		assert_noop!(
+			SyntheticTokens::set_liquidation_ratio(alice(), CurrencyId::FEUR, Permill::from_percent(1)),
+			Error::<Runtime>::InvalidRatios
+		);
+		assert_noop!(
+			SyntheticTokens::set_liquidation_ratio(alice(), CurrencyId::FEUR, Permill::from_percent(10)),
+			Error::<Runtime>::InvalidRatios
+		);
+		assert_noop!(
+			SyntheticTokens::set_collateral_ratio(alice(), CurrencyId::FEUR, Permill::from_percent(5)),
+			Error::<Runtime>::InvalidRatios
+		);
+
+		// validated against stored ratios
+		assert_ok!(SyntheticTokens::set_collateral_ratio(
+			alice(),
+			CurrencyId::FEUR,
+			Permill::from_percent(50)
+		));
+		assert_ok!(SyntheticTokens::set_liquidation_ratio(
+			alice(),
+			CurrencyId::FEUR,
+			Permill::from_percent(20)
+		));
+		assert_noop!(
+			SyntheticTokens::set_collateral_ratio(alice(), CurrencyId::FEUR, Permill::from_percent(15)),
+			Error::<Runtime>::InvalidRatios
+		);
+	});
+}
+
 #[test]
 fn liquidation_ratio_or_default() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -120,7 +230,7 @@ fn liquidation_ratio_or_default() {
 			<Runtime as Config>::DefaultLiquidationRatio::get()
 		);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(6);
 		assert_ok!(SyntheticTokens::set_liquidation_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -138,7 +248,7 @@ fn extreme_ratio_or_default() {
 			<Runtime as Config>::DefaultExtremeRatio::get()
 		);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(2);
 		assert_ok!(SyntheticTokens::set_extreme_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -156,7 +266,7 @@ fn collateral_ratio_or_default() {
 			<Runtime as Config>::DefaultCollateralRatio::get()
 		);
 
-		let ratio = Permill::from_percent(1);
+		let ratio = Permill::from_percent(20);
 		assert_ok!(SyntheticTokens::set_collateral_ratio(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
@@ -225,26 +335,23 @@ fn full_incentive_if_equal_or_below_extreme_ratio() {
 	});
 }
 
-// Given: ratio 10%, extreme 0%, liquidation 100%.
-// Incentive should be 90%.
+// Given: ratio 10%, extreme 0%, liquidation 50%.
+// Incentive should be 80%.
 #[test]
 fn proportional_incentive_between_extreme_and_liquidation() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_ok!(SyntheticTokens::set_extreme_ratio(
+		assert_ok!(SyntheticTokens::set_ratios(
 			Origin::signed(UpdateOrigin::get()),
 			CurrencyId::FEUR,
-			Permill::zero()
-		));
-		assert_ok!(SyntheticTokens::set_liquidation_ratio(
-			Origin::signed(UpdateOrigin::get()),
-			CurrencyId::FEUR,
-			Permill::one()
+			Permill::zero(),
+			Permill::from_percent(50),
+			Permill::from_percent(100)
 		));
 
 		let ten_percent = FixedU128::saturating_from_rational(1, 10);
 		assert_eq!(
 			SyntheticTokens::incentive_ratio(CurrencyId::FEUR, plus_one(ten_percent)),
-			FixedU128::saturating_from_rational(9, 10)
+			FixedU128::saturating_from_rational(8, 10)
 		);
 	});
 }
@@ -283,3 +390,189 @@ fn should_add_remove_get_position() {
 		assert_eq!(SyntheticTokens::get_position(0, CurrencyId::FEUR), (0, 0));
 	});
 }
+
+#[test]
+fn should_add_remove_get_vault() {
+	ExtBuilder::default().build().execute_with(|| {
+		let who = 1;
+		assert_eq!(SyntheticTokens::get_vault(&who, 0, CurrencyId::FEUR), (0, 0));
+
+		SyntheticTokens::add_vault(&who, 0, CurrencyId::FEUR, 3, 2);
+		assert_eq!(
+			SyntheticTokens::vaults((0, CurrencyId::FEUR), who),
+			Position {
+				collateral: 3,
+				synthetic: 2
+			}
+		);
+		assert_eq!(SyntheticTokens::get_vault(&who, 0, CurrencyId::FEUR), (3, 2));
+		assert!(!SyntheticTokens::can_remove(0));
+
+		SyntheticTokens::remove_vault(&who, 0, CurrencyId::FEUR, 2, 2);
+		assert_eq!(SyntheticTokens::get_vault(&who, 0, CurrencyId::FEUR), (1, 0));
+
+		SyntheticTokens::remove_vault(&who, 0, CurrencyId::FEUR, 1, 0);
+		assert!(!<Vaults<Runtime>>::contains_key((0, CurrencyId::FEUR), who));
+		assert!(SyntheticTokens::can_remove(0));
+	});
+}
+
+#[test]
+fn should_add_remove_get_extra_collateral() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			SyntheticTokens::get_extra_collateral(0, CurrencyId::FEUR, CurrencyId::LAMI),
+			0
+		);
+
+		SyntheticTokens::add_extra_collateral(0, CurrencyId::FEUR, CurrencyId::LAMI, 3);
+		SyntheticTokens::add_extra_collateral(0, CurrencyId::FEUR, CurrencyId::FBTC, 1);
+		assert_eq!(
+			SyntheticTokens::get_extra_collateral(0, CurrencyId::FEUR, CurrencyId::LAMI),
+			3
+		);
+		let mut extra = SyntheticTokens::extra_collaterals(0, CurrencyId::FEUR);
+		extra.sort();
+		assert_eq!(extra, vec![(CurrencyId::LAMI, 3), (CurrencyId::FBTC, 1)]);
+		assert!(!SyntheticTokens::can_remove(0));
+
+		SyntheticTokens::remove_extra_collateral(0, CurrencyId::FEUR, CurrencyId::LAMI, 3);
+		SyntheticTokens::remove_extra_collateral(0, CurrencyId::FEUR, CurrencyId::FBTC, 2);
+		assert!(SyntheticTokens::extra_collaterals(0, CurrencyId::FEUR).is_empty());
+		assert!(SyntheticTokens::can_remove(0));
+	});
+}
+
+#[test]
+fn ratios_clamped_if_not_ordered() {
+	ExtBuilder::default().build().execute_with(|| {
+		// written without validation, e.g. by gradual updates
+		LiquidationRatios::insert(CurrencyId::FEUR, Permill::from_percent(20));
+		assert_eq!(
+			SyntheticTokens::extreme_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_percent(1)
+		);
+		assert_eq!(
+			SyntheticTokens::liquidation_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_percent(20)
+		);
+		assert_eq!(
+			SyntheticTokens::collateral_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_parts(200_001)
+		);
+
+		ExtremeRatios::insert(CurrencyId::FEUR, Permill::from_percent(100));
+		assert_eq!(
+			SyntheticTokens::extreme_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_parts(999_998)
+		);
+		assert_eq!(
+			SyntheticTokens::liquidation_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_parts(999_999)
+		);
+		assert_eq!(
+			SyntheticTokens::collateral_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_percent(100)
+		);
+		ExtremeRatios::remove(CurrencyId::FEUR);
+
+		CollateralRatios::insert(CurrencyId::FEUR, Permill::from_percent(30));
+		assert_eq!(
+			SyntheticTokens::liquidation_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_percent(20)
+		);
+		assert_eq!(
+			SyntheticTokens::collateral_ratio_or_default(CurrencyId::FEUR),
+			Permill::from_percent(30)
+		);
+	});
+}
+
+#[test]
+fn on_initialize_stores_clamped_ratios() {
+	ExtBuilder::default().build().execute_with(|| {
+		SyntheticTokens::on_initialize(1);
+		assert_eq!(
+			SyntheticTokens::ratios(CurrencyId::FEUR),
+			SyntheticTokensRatio::default()
+		);
+
+		LiquidationRatios::insert(CurrencyId::FEUR, Permill::from_percent(20));
+		SyntheticTokens::on_initialize(2);
+		assert_eq!(
+			SyntheticTokens::ratios(CurrencyId::FEUR),
+			SyntheticTokensRatio {
+				extreme: None,
+				liquidation: Some(Permill::from_percent(20)),
+				collateral: Some(Permill::from_parts(200_001)),
+			}
+		);
+
+		let event = TestEvent::synthetic_tokens(Event::RatiosClamped(
+			CurrencyId::FEUR,
+			Permill::from_percent(1),
+			Permill::from_percent(20),
+			Permill::from_parts(200_001),
+		));
+		assert!(System::events().iter().any(|record| record.event == event));
+	});
+}
+
+#[test]
+#[should_panic(expected = "must be `extreme < liquidation < collateral`")]
+fn genesis_rejects_invalid_ratios() {
+	let _ = GenesisConfig {
+		ratios: vec![(
+			CurrencyId::FEUR,
+			SyntheticTokensRatio {
+				extreme: Some(Permill::from_percent(5)),
+				liquidation: Some(Permill::from_percent(1)),
+				collateral: None,
+			},
+		)],
+	}
+	.build_storage::<Runtime>();
+}
+
+#[test]
+fn migrate_ratios_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		let ratios = SyntheticTokensRatio {
+			extreme: Some(Permill::from_percent(2)),
+			liquidation: Some(Permill::from_percent(6)),
+			collateral: None,
+		};
+		let key = Twox64Concat::hash(&CurrencyId::FEUR.encode());
+		put_storage_value(b"SyntheticTokens", b"Ratios", &key, ratios.clone());
+
+		SyntheticTokens::on_runtime_upgrade();
+
+		assert_eq!(SyntheticTokens::ratios(CurrencyId::FEUR), ratios);
+		assert_eq!(
+			get_storage_value::<SyntheticTokensRatio>(b"SyntheticTokens", b"Ratios", &key),
+			None
+		);
+		assert_eq!(SyntheticTokens::storage_version(), Releases::V2);
+	});
+}
+
+#[test]
+fn migrate_ratios_skipped_if_migrated() {
+	ExtBuilder::default().build().execute_with(|| {
+		StorageVersion::put(Releases::V2);
+		let ratios = SyntheticTokensRatio {
+			extreme: Some(Permill::from_percent(2)),
+			liquidation: None,
+			collateral: None,
+		};
+		let key = Twox64Concat::hash(&CurrencyId::FEUR.encode());
+		put_storage_value(b"SyntheticTokens", b"Ratios", &key, ratios.clone());
+
+		SyntheticTokens::on_runtime_upgrade();
+
+		assert_eq!(
+			SyntheticTokens::ratios(CurrencyId::FEUR),
+			SyntheticTokensRatio::default()
+		);
+	});
+}
@@ -86,6 +86,11 @@ pub trait MarginProtocolLiquidityPools<AccountId>: LiquidityPools<AccountId> {
 pub trait MarginProtocolLiquidityPoolsManager {
 	/// Return `Ok` iff the trading pair could be enabled in `pool_id`.
 	fn ensure_can_enable_trading_pair(pool_id: LiquidityPoolId, pair: TradingPair) -> DispatchResult;
+
+	/// Return net leveraged held amount of `pair` positions in `pool_id`, from traders' view.
+	///
+	/// Positive if traders are net long, i.e. the pool is net short; negative if traders are net short.
+	fn net_exposure(pool_id: LiquidityPoolId, pair: TradingPair) -> FixedI128;
 }
 
 /// The liquidity pool was disabled by owner.
@@ -99,3 +104,71 @@ pub trait OnRemoveLiquidityPool {
 	/// Invoked when the liquiditiy pool has been removed.
 	fn on_remove(pool_id: LiquidityPoolId);
 }
+
+/// Tradability of assets.
+pub trait TradableAssets {
+	/// Return `true` if `currency_id` could be traded, in margin trading pairs or as synthetic.
+	fn is_tradable(currency_id: CurrencyId) -> bool;
+}
+
+impl TradableAssets for () {
+	fn is_tradable(_currency_id: CurrencyId) -> bool {
+		true
+	}
+}
+
+/// Usage of prices. Different policies may apply to stale prices per usage.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum PriceUsage {
+	/// Opening and closing positions, minting and redeeming, etc.
+	Trading,
+
+	/// Margin calls, stop outs and liquidations.
+	Liquidation,
+}
+
+environmental::environmental!(current_price_usage: PriceUsage);
+
+/// Execute `f` with prices used for `usage`.
+pub fn with_price_usage<R>(usage: PriceUsage, f: impl FnOnce() -> R) -> R {
+	let mut usage = usage;
+	current_price_usage::using(&mut usage, f)
+}
+
+/// Current price usage, `Trading` if not executed in `with_price_usage`.
+pub fn price_usage() -> PriceUsage {
+	current_price_usage::with(|usage| *usage).unwrap_or(PriceUsage::Trading)
+}
+
+environmental::environmental!(risk_pricing: bool);
+
+/// Execute `f` with prices for risk checks, i.e. margin levels and collateral ratios, which may
+/// differ from the ones for execution.
+pub fn with_risk_prices<R>(f: impl FnOnce() -> R) -> R {
+	let mut is_risk_pricing = true;
+	risk_pricing::using(&mut is_risk_pricing, f)
+}
+
+/// Returns `true` if executed in `with_risk_prices`.
+pub fn is_risk_pricing() -> bool {
+	risk_pricing::with(|is_risk_pricing| *is_risk_pricing).unwrap_or(false)
+}
+
+/// Guard against prices not to be used.
+pub trait PriceGuard {
+	/// Return `true` if the price of `currency_id` is too old for `usage`.
+	fn is_stale(currency_id: CurrencyId, usage: PriceUsage) -> bool;
+
+	/// Return `true` if the price of `currency_id` is not reliable enough for `usage`.
+	fn is_unreliable(currency_id: CurrencyId, usage: PriceUsage) -> bool;
+}
+
+impl PriceGuard for () {
+	fn is_stale(_currency_id: CurrencyId, _usage: PriceUsage) -> bool {
+		false
+	}
+
+	fn is_unreliable(_currency_id: CurrencyId, _usage: PriceUsage) -> bool {
+		false
+	}
+}
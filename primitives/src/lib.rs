@@ -1,6 +1,4 @@
 #![cfg_attr(not(feature = "std"), no_std)]
-// Suppress warning generated from bitmask! macro.
-#![allow(clippy::transmute_ptr_to_ptr)]
 
 use codec::{Decode, Encode, Error, Input};
 use sp_runtime::{
@@ -15,9 +13,6 @@ use sp_std::{prelude::*, vec};
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
-#[macro_use]
-extern crate bitmask;
-
 pub mod arithmetic;
 
 /// An index to a block.
@@ -76,10 +71,16 @@ pub use sp_runtime::OpaqueExtrinsic as UncheckedExtrinsic;
 
 pub type LiquidityPoolId = u32;
 
+/// Index of an asset registered in the asset registry.
+pub type AssetId = u32;
+
+/// Currency identifier.
+///
+/// The legacy variants keep their encoding, assets added on chain later on are `Asset(AssetId)`.
 #[derive(Encode, Decode, Eq, PartialEq, Copy, Clone, RuntimeDebug, PartialOrd, Ord)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum CurrencyId {
-	LAMI = 0,
+	LAMI,
 	AUSD,
 	FEUR,
 	FJPY,
@@ -90,104 +91,262 @@ pub enum CurrencyId {
 	FCHF,
 	FXAU,
 	FOIL,
+	Asset(AssetId),
 }
 
 pub type Price = FixedU128;
 
-bitmask! {
-	#[derive(Encode, Decode, Default)]
-	pub mask Leverages: u16 where flags Leverage {
-		LongTwo 		= 0b0000000000000001,
-		LongThree 		= 0b0000000000000010,
-		LongFive 		= 0b0000000000000100,
-		LongTen 		= 0b0000000000001000,
-		LongTwenty 		= 0b0000000000010000,
-		LongThirty		= 0b0000000000100000,
-		LongFifty		= 0b0000000001000000,
-		LongReserved	= 0b0000000010000000,
-		ShortTwo 		= 0b0000000100000000,
-		ShortThree 		= 0b0000001000000000,
-		ShortFive 		= 0b0000010000000000,
-		ShortTen 		= 0b0000100000000000,
-		ShortTwenty		= 0b0001000000000000,
-		ShortThirty		= 0b0010000000000000,
-		ShortFifty		= 0b0100000000000000,
-		ShortReserved	= 0b1000000000000000,
+/// Max leverage value.
+pub const MAX_LEVERAGE: u8 = 100;
+
+/// Version tag of `Leverage` SCALE encoding.
+///
+/// Legacy encodings, of the former bitmask flags, are single bytes below this tag.
+const LEVERAGE_ENCODING_V1: u8 = 16;
+
+/// Leverage values of the legacy encoding, indexed by flag position.
+const LEGACY_LEVERAGE_VALUES: [u8; 8] = [2, 3, 5, 10, 20, 30, 50, 100];
+
+/// Position leverage: the direction, and a value in `1..=MAX_LEVERAGE`.
+#[derive(Eq, PartialEq, Copy, Clone, RuntimeDebug, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Leverage {
+	is_long: bool,
+	value: u8,
+}
+
+impl Leverage {
+	/// Long leverage of `value`, `None` if out of `1..=MAX_LEVERAGE`.
+	pub fn long(value: u8) -> Option<Self> {
+		Self::new(true, value)
 	}
+
+	/// Short leverage of `value`, `None` if out of `1..=MAX_LEVERAGE`.
+	pub fn short(value: u8) -> Option<Self> {
+		Self::new(false, value)
+	}
+
+	fn new(is_long: bool, value: u8) -> Option<Self> {
+		if value >= 1 && value <= MAX_LEVERAGE {
+			Some(Leverage { is_long, value })
+		} else {
+			None
+		}
+	}
+
+	pub fn is_long(&self) -> bool {
+		self.is_long
+	}
+
+	pub fn is_short(&self) -> bool {
+		!self.is_long
+	}
+
+	pub fn value(&self) -> u8 {
+		self.value
+	}
+}
+
+/// Leverages of the legacy encoding.
+#[allow(non_upper_case_globals)]
+impl Leverage {
+	pub const LongTwo: Leverage = Leverage {
+		is_long: true,
+		value: 2,
+	};
+	pub const LongThree: Leverage = Leverage {
+		is_long: true,
+		value: 3,
+	};
+	pub const LongFive: Leverage = Leverage {
+		is_long: true,
+		value: 5,
+	};
+	pub const LongTen: Leverage = Leverage {
+		is_long: true,
+		value: 10,
+	};
+	pub const LongTwenty: Leverage = Leverage {
+		is_long: true,
+		value: 20,
+	};
+	pub const LongThirty: Leverage = Leverage {
+		is_long: true,
+		value: 30,
+	};
+	pub const LongFifty: Leverage = Leverage {
+		is_long: true,
+		value: 50,
+	};
+	pub const ShortTwo: Leverage = Leverage {
+		is_long: false,
+		value: 2,
+	};
+	pub const ShortThree: Leverage = Leverage {
+		is_long: false,
+		value: 3,
+	};
+	pub const ShortFive: Leverage = Leverage {
+		is_long: false,
+		value: 5,
+	};
+	pub const ShortTen: Leverage = Leverage {
+		is_long: false,
+		value: 10,
+	};
+	pub const ShortTwenty: Leverage = Leverage {
+		is_long: false,
+		value: 20,
+	};
+	pub const ShortThirty: Leverage = Leverage {
+		is_long: false,
+		value: 30,
+	};
+	pub const ShortFifty: Leverage = Leverage {
+		is_long: false,
+		value: 50,
+	};
 }
 
 impl Encode for Leverage {
 	fn size_hint(&self) -> usize {
-		1
+		3
 	}
 
 	fn encode(&self) -> Vec<u8> {
-		vec![u16::trailing_zeros(**self) as u8]
+		vec![LEVERAGE_ENCODING_V1, self.is_long as u8, self.value]
 	}
 }
 
 impl Decode for Leverage {
 	fn decode<I: Input>(value: &mut I) -> Result<Self, Error> {
-		let trailing_zeros = value.read_byte()?;
-		if trailing_zeros >= 16 {
-			return Err(Error::from("overflow"));
+		let tag = value.read_byte()?;
+		if tag < LEVERAGE_ENCODING_V1 {
+			// legacy encoding: trailing zeros of the bitmask flag, longs followed by shorts
+			let is_long = tag < 8;
+			return Ok(Leverage {
+				is_long,
+				value: LEGACY_LEVERAGE_VALUES[(tag % 8) as usize],
+			});
 		}
-		match trailing_zeros {
-			0 => Ok(Leverage::LongTwo),
-			1 => Ok(Leverage::LongThree),
-			2 => Ok(Leverage::LongFive),
-			3 => Ok(Leverage::LongTen),
-			4 => Ok(Leverage::LongTwenty),
-			5 => Ok(Leverage::LongThirty),
-			6 => Ok(Leverage::LongFifty),
-			7 => Ok(Leverage::LongReserved),
-			8 => Ok(Leverage::ShortTwo),
-			9 => Ok(Leverage::ShortThree),
-			10 => Ok(Leverage::ShortFive),
-			11 => Ok(Leverage::ShortTen),
-			12 => Ok(Leverage::ShortTwenty),
-			13 => Ok(Leverage::ShortThirty),
-			14 => Ok(Leverage::ShortFifty),
-			15 => Ok(Leverage::ShortReserved),
-			_ => Err(Error::from("unknown value")),
+		if tag != LEVERAGE_ENCODING_V1 {
+			return Err(Error::from("unknown version"));
 		}
+		let is_long = bool::decode(value)?;
+		let leverage = value.read_byte()?;
+		Self::new(is_long, leverage).ok_or_else(|| Error::from("overflow"))
 	}
 }
 
-impl Leverage {
-	pub fn is_long(&self) -> bool {
-		!self.is_short()
+/// Inclusive range of leverage values.
+#[derive(Encode, Decode, Eq, PartialEq, Copy, Clone, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct LeverageRange {
+	/// Min leverage value.
+	pub min: u8,
+
+	/// Max leverage value.
+	pub max: u8,
+}
+
+impl LeverageRange {
+	pub fn contains(&self, value: u8) -> bool {
+		self.min <= value && value <= self.max
 	}
 
-	pub fn is_short(&self) -> bool {
-		*self >= Leverage::ShortTwo
+	/// Valid if `1 <= min <= max <= MAX_LEVERAGE`.
+	pub fn is_valid(&self) -> bool {
+		self.min >= 1 && self.min <= self.max && self.max <= MAX_LEVERAGE
 	}
+}
 
-	pub fn value(&self) -> u8 {
-		match *self {
-			Leverage::LongTwo | Leverage::ShortTwo => 2,
-			Leverage::LongThree | Leverage::ShortThree => 3,
-			Leverage::LongFive | Leverage::ShortFive => 5,
-			Leverage::LongTen | Leverage::ShortTen => 10,
-			Leverage::LongTwenty | Leverage::ShortTwenty => 20,
-			Leverage::LongThirty | Leverage::ShortThirty => 30,
-			Leverage::LongFifty | Leverage::ShortFifty => 50,
-			Leverage::LongReserved | Leverage::ShortReserved => 100,
+/// Enabled leverages, for long and short positions separately.
+#[derive(Encode, Decode, Eq, PartialEq, Copy, Clone, RuntimeDebug, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Leverages {
+	/// Enabled long leverages, `None` if long positions not enabled.
+	pub long: Option<LeverageRange>,
+
+	/// Enabled short leverages, `None` if short positions not enabled.
+	pub short: Option<LeverageRange>,
+}
+
+impl Leverages {
+	/// No leverage enabled.
+	pub fn none() -> Self {
+		Default::default()
+	}
+
+	/// All leverages, up to `MAX_LEVERAGE`, enabled.
+	pub fn all() -> Self {
+		let all = LeverageRange {
+			min: 1,
+			max: MAX_LEVERAGE,
+		};
+		Leverages {
+			long: Some(all),
+			short: Some(all),
 		}
 	}
-}
 
-#[cfg(not(feature = "std"))]
-impl core::fmt::Debug for Leverages {
-	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-		write!(f, "Leverages {:?}", self)
+	pub fn contains(&self, leverage: Leverage) -> bool {
+		let range = if leverage.is_long() { self.long } else { self.short };
+		range.map_or(false, |r| r.contains(leverage.value()))
+	}
+
+	/// Valid if all ranges are valid.
+	pub fn is_valid(&self) -> bool {
+		self.long.map_or(true, |r| r.is_valid()) && self.short.map_or(true, |r| r.is_valid())
+	}
+
+	/// Convert from the legacy bitmask of enabled leverages, with long flags in the low byte and
+	/// short flags in the high byte.
+	///
+	/// A range can't represent a sparse set of leverages, so the range of each direction starts
+	/// at its min enabled legacy leverage and only extends over the following legacy leverages
+	/// that are enabled and consecutive, i.e. `2x | 3x | 50x` becomes `2..=3`. This never enables
+	/// a leverage that was disabled, at the cost of disabling the non-consecutive ones.
+	pub fn from_legacy_bits(bits: u16) -> Self {
+		let range = |flags: u8| {
+			if flags == 0 {
+				return None;
+			}
+			let first = flags.trailing_zeros() as usize;
+			let min = LEGACY_LEVERAGE_VALUES[first];
+			let mut max = min;
+			for (index, &value) in LEGACY_LEVERAGE_VALUES.iter().enumerate().skip(first + 1) {
+				if flags & (1 << index) == 0 || value != max + 1 {
+					break;
+				}
+				max = value;
+			}
+			Some(LeverageRange { min, max })
+		};
+		Leverages {
+			long: range(bits as u8),
+			short: range((bits >> 8) as u8),
+		}
 	}
 }
 
-#[cfg(not(feature = "std"))]
-impl core::fmt::Debug for Leverage {
-	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-		write!(f, "Leverage {:?}", self)
+impl From<Leverage> for Leverages {
+	/// Only `leverage` enabled.
+	fn from(leverage: Leverage) -> Self {
+		let range = Some(LeverageRange {
+			min: leverage.value(),
+			max: leverage.value(),
+		});
+		if leverage.is_long() {
+			Leverages {
+				long: range,
+				short: None,
+			}
+		} else {
+			Leverages {
+				long: None,
+				short: range,
+			}
+		}
 	}
 }
 
@@ -215,6 +374,18 @@ pub struct TradingPair {
 	pub quote: CurrencyId,
 }
 
+/// The route a trading pair is priced through.
+#[derive(Encode, Decode, Clone, RuntimeDebug, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum PriceRoute {
+	/// Quoted directly by the oracle, as the price of the oracle key in USD.
+	Direct(CurrencyId),
+
+	/// Derived by multiplying prices of a chain of legs, from the pair's base to its quote
+	/// currency, i.e. `FEUR/AUSD`, `AUSD/FJPY` for `FEUR/FJPY`.
+	Legs(Vec<TradingPair>),
+}
+
 /// Liquidity pool identity info.
 #[derive(Encode, Decode, RuntimeDebug, Eq, PartialEq, Default, Clone)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -256,7 +427,7 @@ pub enum DataProviderId {
 mod tests {
 	use super::*;
 
-	const SHORTS: [Leverage; 8] = [
+	const SHORTS: [Leverage; 7] = [
 		Leverage::ShortTwo,
 		Leverage::ShortThree,
 		Leverage::ShortFive,
@@ -264,10 +435,9 @@ mod tests {
 		Leverage::ShortTwenty,
 		Leverage::ShortThirty,
 		Leverage::ShortFifty,
-		Leverage::ShortReserved,
 	];
 
-	const LONGS: [Leverage; 8] = [
+	const LONGS: [Leverage; 7] = [
 		Leverage::LongTwo,
 		Leverage::LongThree,
 		Leverage::LongFive,
@@ -275,22 +445,83 @@ mod tests {
 		Leverage::LongTwenty,
 		Leverage::LongThirty,
 		Leverage::LongFifty,
-		Leverage::LongReserved,
 	];
 
+	fn range(min: u8, max: u8) -> Option<LeverageRange> {
+		Some(LeverageRange { min, max })
+	}
+
 	#[test]
-	fn check_leverages_all_value() {
-		assert_eq!(*Leverages::all(), 0xffff);
-		assert_eq!(*LONGS.iter().fold(Leverages::none(), |acc, i| (acc | *i)), 0x00ff);
-		assert_eq!(*SHORTS.iter().fold(Leverages::none(), |acc, i| (acc | *i)), 0xff00);
+	fn leverages_contains_should_work() {
+		for leverage in LONGS.iter().chain(SHORTS.iter()) {
+			assert!(Leverages::all().contains(*leverage));
+			assert!(!Leverages::none().contains(*leverage));
+		}
 
-		let mut all = LONGS.clone().to_vec();
-		all.extend_from_slice(&SHORTS);
+		let leverages = Leverages {
+			long: range(4, 15),
+			short: None,
+		};
+		assert!(leverages.contains(Leverage::long(4).unwrap()));
+		assert!(leverages.contains(Leverage::long(15).unwrap()));
+		assert!(!leverages.contains(Leverage::long(3).unwrap()));
+		assert!(!leverages.contains(Leverage::long(16).unwrap()));
+		assert!(!leverages.contains(Leverage::short(5).unwrap()));
+	}
 
+	#[test]
+	fn leverages_from_legacy_bits_should_work() {
+		assert_eq!(Leverages::from_legacy_bits(0), Leverages::none());
 		assert_eq!(
-			all.iter().fold(Leverages::none(), |acc, i| (acc | *i)),
-			Leverages::all()
+			Leverages::from_legacy_bits(0xffff),
+			Leverages {
+				long: range(2, 3),
+				short: range(2, 3),
+			}
 		);
+		// LongTwo | LongFive | ShortThree
+		assert_eq!(
+			Leverages::from_legacy_bits(0b0000_0010_0000_0101),
+			Leverages {
+				long: range(2, 2),
+				short: range(3, 3),
+			}
+		);
+		// LongTwo | LongThree | LongFifty | ShortFive | ShortTen | ShortHundred
+		assert_eq!(
+			Leverages::from_legacy_bits(0b1000_1100_0100_0011),
+			Leverages {
+				long: range(2, 3),
+				short: range(5, 5),
+			}
+		);
+	}
+
+	#[test]
+	fn leverages_is_valid_should_work() {
+		assert!(Leverages::all().is_valid());
+		assert!(Leverages::none().is_valid());
+		assert!(Leverages {
+			long: range(25, 25),
+			short: range(1, MAX_LEVERAGE),
+		}
+		.is_valid());
+
+		assert!(!Leverages {
+			long: range(0, 2),
+			short: None,
+		}
+		.is_valid());
+		assert!(!Leverages {
+			long: None,
+			short: range(10, 5),
+		}
+		.is_valid());
+		assert!(!Leverages {
+			long: range(1, MAX_LEVERAGE + 1),
+			short: None,
+		}
+		.is_valid());
 	}
 
 	#[test]
@@ -324,25 +555,45 @@ mod tests {
 		assert_eq!(Leverage::ShortFifty.value(), 50);
 	}
 
+	#[test]
+	fn new_leverage_should_be_bounded() {
+		assert_eq!(Leverage::long(0), None);
+		assert_eq!(Leverage::short(MAX_LEVERAGE + 1), None);
+		assert_eq!(Leverage::long(15).map(|l| l.value()), Some(15));
+		assert_eq!(Leverage::short(MAX_LEVERAGE).map(|l| l.value()), Some(MAX_LEVERAGE));
+	}
+
 	#[test]
 	fn encode_decode_should_work() {
-		let mut all = LONGS.clone().to_vec();
+		let mut all = LONGS.to_vec();
 		all.extend_from_slice(&SHORTS);
+		all.push(Leverage::long(25).unwrap());
+		all.push(Leverage::short(MAX_LEVERAGE).unwrap());
 		for leverage in all {
 			let encoded = leverage.encode();
 			let decoded = Leverage::decode(&mut &encoded[..]).unwrap();
 			assert_eq!(leverage, decoded);
 		}
 
-		assert_eq!(Leverage::LongFifty, Leverage::decode(&mut &[6][..]).unwrap());
+		assert_eq!(Leverage::long(4).unwrap().encode(), vec![16, 1, 4]);
+		assert!(Leverage::decode(&mut &[16, 0, 0][..]).is_err());
+		assert!(Leverage::decode(&mut &[16, 1, MAX_LEVERAGE + 1][..]).is_err());
+		assert!(Leverage::decode(&mut &[17, 1, 4][..]).is_err());
 
-		let fifty = Leverages::from(Leverage::LongFifty | Leverage::ShortFifty);
-		assert_eq!(fifty, Leverages::decode(&mut &fifty.encode()[..]).unwrap());
-
-		let none_encoded = Leverages::none().encode();
-		assert_eq!(Leverages::decode(&mut &none_encoded[..]).unwrap(), Leverages::none());
+		let leverages = Leverages {
+			long: range(4, 15),
+			short: range(25, 25),
+		};
+		assert_eq!(leverages, Leverages::decode(&mut &leverages.encode()[..]).unwrap());
+	}
 
-		let all_encoded = Leverages::all().encode();
-		assert_eq!(Leverages::decode(&mut &all_encoded[..]).unwrap(), Leverages::all());
+	#[test]
+	fn decode_legacy_encoding_should_work() {
+		for (i, leverage) in LONGS.iter().chain([Leverage::long(100).unwrap()].iter()).enumerate() {
+			assert_eq!(*leverage, Leverage::decode(&mut &[i as u8][..]).unwrap());
+		}
+		for (i, leverage) in SHORTS.iter().chain([Leverage::short(100).unwrap()].iter()).enumerate() {
+			assert_eq!(*leverage, Leverage::decode(&mut &[i as u8 + 8][..]).unwrap());
+		}
 	}
 }
@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use primitives::{AccountId, Balance, Block, BlockNumber, CurrencyId, DataProviderId, Hash, Nonce};
-use sc_client_api::light::{Fetcher, RemoteBlockchain};
+use sc_client_api::{
+	light::{Fetcher, RemoteBlockchain},
+	BlockchainEvents,
+};
 use sc_consensus_babe::Epoch;
 use sc_finality_grandpa::{FinalityProofProvider, GrandpaJustificationStream, SharedAuthoritySet, SharedVoterState};
 pub use sc_rpc::DenyUnsafe;
@@ -74,10 +77,12 @@ pub fn create_full<C, P, SC, B>(deps: FullDeps<C, P, SC, B>) -> RpcExtension
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError>,
+	C: BlockchainEvents<Block>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: orml_oracle_rpc::OracleRuntimeApi<Block, DataProviderId, CurrencyId, dev_runtime::TimeStampedPrice>,
+	C::Api: liquidity_pools_rpc::LiquidityPoolsRuntimeApi<Block, AccountId>,
 	C::Api: margin_protocol_rpc::MarginProtocolRuntimeApi<Block, AccountId>,
 	C::Api: synthetic_protocol_rpc::SyntheticProtocolRuntimeApi<Block, AccountId>,
 	C::Api: BabeApi<Block>,
@@ -87,6 +92,7 @@ where
 	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 	B::State: sc_client_api::StateBackend<sp_runtime::traits::HashFor<Block>>,
 {
+	use liquidity_pools_rpc::{LiquidityPools, LiquidityPoolsApi};
 	use margin_protocol_rpc::{MarginProtocol, MarginProtocolApi};
 	use orml_oracle_rpc::{Oracle, OracleApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
@@ -137,12 +143,18 @@ where
 		shared_authority_set,
 		shared_voter_state,
 		justification_stream,
-		subscription_executor,
+		subscription_executor.clone(),
 		finality_provider,
 	)));
 	io.extend_with(OracleApi::to_delegate(Oracle::new(client.clone())));
-	io.extend_with(MarginProtocolApi::to_delegate(MarginProtocol::new(client.clone())));
-	io.extend_with(SyntheticProtocolApi::to_delegate(SyntheticProtocol::new(client)));
+	io.extend_with(MarginProtocolApi::to_delegate(MarginProtocol::new(
+		client.clone(),
+		subscription_executor,
+	)));
+	io.extend_with(SyntheticProtocolApi::to_delegate(SyntheticProtocol::new(
+		client.clone(),
+	)));
+	io.extend_with(LiquidityPoolsApi::to_delegate(LiquidityPools::new(client)));
 
 	io
 }
@@ -0,0 +1,56 @@
+use crate::{AssetRegistry, CurrencyId, Runtime};
+
+use asset_registry::AssetMetadata;
+use frame_system::RawOrigin;
+use orml_benchmarking::runtime_benchmarks;
+use sp_std::prelude::*;
+
+fn metadata(symbol: &[u8], oracle_key: CurrencyId) -> AssetMetadata {
+	AssetMetadata {
+		symbol: symbol.to_vec(),
+		decimals: 18,
+		oracle_key,
+		is_synthetic: true,
+		is_tradable: true,
+	}
+}
+
+runtime_benchmarks! {
+	{ Runtime, asset_registry }
+
+	_ {}
+
+	register_asset {
+	}: _(RawOrigin::Root, metadata(b"FGBP", CurrencyId::Asset(0)))
+
+	update_asset {
+		AssetRegistry::register_asset(RawOrigin::Root.into(), metadata(b"FGBP", CurrencyId::FEUR))?;
+	}: _(RawOrigin::Root, CurrencyId::Asset(0), metadata(b"FGBP", CurrencyId::Asset(0)))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::assert_ok;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into()
+	}
+
+	#[test]
+	fn test_register_asset() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_register_asset());
+		});
+	}
+
+	#[test]
+	fn test_update_asset() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_update_asset());
+		});
+	}
+}
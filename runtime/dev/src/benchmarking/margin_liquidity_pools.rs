@@ -4,7 +4,7 @@ use crate::{
 	SyntheticCurrencyIds, System,
 };
 
-use frame_support::traits::OnInitialize;
+use frame_support::traits::{Get, OnInitialize};
 use frame_system::RawOrigin;
 use margin_liquidity_pools::ONE_MINUTE;
 use sp_runtime::{DispatchError, FixedI128, Permill};
@@ -39,6 +39,18 @@ runtime_benchmarks! {
 		let caller = create_pool()?;
 	}: _(RawOrigin::Signed(caller), 0, EUR_USD, Price::from_inner(1u128), Price::from_inner(1u128))
 
+	set_spread_formula {
+		let caller = create_pool()?;
+		let formula = margin_liquidity_pools::SpreadFormula {
+			base: Price::from_inner(1u128),
+			multiplier: Price::from_inner(1u128),
+		};
+	}: _(RawOrigin::Signed(caller), 0, EUR_USD, Some(formula))
+
+	set_spread_skew {
+		let caller = create_pool()?;
+	}: _(RawOrigin::Signed(caller), 0, EUR_USD, Some(Price::from_inner(1u128)))
+
 	set_enabled_leverages {
 		let caller = create_pool()?;
 	}: _(RawOrigin::Signed(caller), 0, EUR_USD, Leverages::all())
@@ -163,6 +175,20 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn set_spread_formula() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_spread_formula());
+		});
+	}
+
+	#[test]
+	fn set_spread_skew() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_spread_skew());
+		});
+	}
+
 	#[test]
 	fn set_enabled_leverages() {
 		new_test_ext().execute_with(|| {
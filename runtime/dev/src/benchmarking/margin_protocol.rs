@@ -385,6 +385,16 @@ runtime_benchmarks! {
 		assert_eq!(MarginProtocol::liquidity_pool_enp_threshold(EUR_USD), Some(threshold.clone()));
 		assert_eq!(MarginProtocol::liquidity_pool_ell_threshold(EUR_USD), Some(threshold));
 	}
+
+	set_price_route {
+		let route = PriceRoute::Legs(vec![
+			TradingPair { base: CurrencyId::FEUR, quote: CurrencyId::FJPY },
+			TradingPair { base: CurrencyId::FJPY, quote: CurrencyId::AUSD },
+		]);
+	}: _(RawOrigin::Root, EUR_USD, Some(route.clone()))
+	verify {
+		assert_eq!(MarginProtocol::price_route(EUR_USD), route);
+	}
 }
 
 #[cfg(test)]
@@ -497,4 +507,11 @@ mod tests {
 			assert_ok!(test_benchmark_set_trading_pair_risk_threshold());
 		});
 	}
+
+	#[test]
+	fn set_price_route() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_price_route());
+		});
+	}
 }
@@ -1,8 +1,10 @@
 #![cfg(feature = "runtime-benchmarks")]
 
+pub mod asset_registry;
 pub mod base_liquidity_pools;
 pub mod margin_liquidity_pools;
 pub mod margin_protocol;
+pub mod prices;
 pub mod synthetic_liquidity_pools;
 pub mod synthetic_protocol;
 pub mod synthetic_tokens;
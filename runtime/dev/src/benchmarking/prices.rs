@@ -0,0 +1,91 @@
+use crate::{CurrencyId, Prices, Runtime};
+
+use frame_system::RawOrigin;
+use orml_benchmarking::runtime_benchmarks;
+use prices::{CircuitBreakerParams, LiquidationFallback};
+use sp_runtime::Permill;
+use sp_std::prelude::*;
+
+runtime_benchmarks! {
+	{ Runtime, prices }
+
+	_ {}
+
+	set_max_price_age {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Some(60_000))
+
+	set_liquidation_max_price_age {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Some(600_000))
+
+	set_max_divergence {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Some(Permill::from_percent(5)))
+
+	set_liquidation_fallback {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, LiquidationFallback::Continue)
+
+	set_circuit_breaker {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Some(CircuitBreakerParams { band: Permill::from_percent(10), window: 60_000 }))
+
+	reset_circuit_breaker {
+		Prices::set_circuit_breaker(
+			RawOrigin::Root.into(),
+			CurrencyId::FEUR,
+			Some(CircuitBreakerParams { band: Permill::from_percent(10), window: 60_000 }),
+		)?;
+	}: _(RawOrigin::Root, CurrencyId::FEUR)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::assert_ok;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default()
+			.build_storage::<Runtime>()
+			.unwrap()
+			.into()
+	}
+
+	#[test]
+	fn test_set_max_price_age() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_max_price_age());
+		});
+	}
+
+	#[test]
+	fn test_set_liquidation_max_price_age() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_liquidation_max_price_age());
+		});
+	}
+
+	#[test]
+	fn test_set_max_divergence() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_max_divergence());
+		});
+	}
+
+	#[test]
+	fn test_set_liquidation_fallback() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_liquidation_fallback());
+		});
+	}
+
+	#[test]
+	fn test_set_circuit_breaker() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_circuit_breaker());
+		});
+	}
+
+	#[test]
+	fn test_reset_circuit_breaker() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_reset_circuit_breaker());
+		});
+	}
+}
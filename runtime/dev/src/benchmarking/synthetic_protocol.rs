@@ -1,12 +1,12 @@
-use super::utils::{dollars, set_ausd_balance, set_price};
+use super::utils::{dollars, set_ausd_balance, set_balance, set_price};
 use crate::{
-	AccountId, BaseLiquidityPoolsForSynthetic, LaminarOracle as Oracle, Price, Runtime, SyntheticLiquidityPools,
-	SyntheticProtocol,
+	AccountId, BaseLiquidityPoolsForSynthetic, LaminarOracle as Oracle, Price, Runtime, StorageValue,
+	SyntheticLiquidityPools, SyntheticProtocol, System,
 };
 
-use frame_support::traits::ChangeMembers;
+use frame_support::traits::{ChangeMembers, OnInitialize};
 use frame_system::RawOrigin;
-use sp_runtime::{DispatchError, DispatchResult, FixedPointNumber};
+use sp_runtime::{DispatchError, DispatchResult, FixedPointNumber, Permill};
 use sp_std::prelude::*;
 
 use frame_benchmarking::account;
@@ -33,6 +33,22 @@ fn create_pool() -> Result<AccountId, DispatchError> {
 	Ok(owner)
 }
 
+/// Create more pools with FEUR enabled, `count` pools in total.
+fn create_more_pools(owner: &AccountId, count: u32) -> DispatchResult {
+	for pool_id in 1..count {
+		BaseLiquidityPoolsForSynthetic::create_pool(RawOrigin::Signed(owner.clone()).into())?;
+		SyntheticLiquidityPools::set_spread(
+			RawOrigin::Signed(owner.clone()).into(),
+			pool_id,
+			FEUR,
+			Price::zero(),
+			Price::zero(),
+		)?;
+		SyntheticLiquidityPools::set_synthetic_enabled(RawOrigin::Signed(owner.clone()).into(), pool_id, FEUR, true)?;
+	}
+	Ok(())
+}
+
 fn add_liquidity(owner: &AccountId, liquidity: Balance) -> DispatchResult {
 	set_ausd_balance(owner, liquidity + dollars(1u128))?;
 	BaseLiquidityPoolsForSynthetic::deposit_liquidity(RawOrigin::Signed(owner.clone()).into(), 0, liquidity)
@@ -78,6 +94,64 @@ runtime_benchmarks! {
 		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
 	}: _(RawOrigin::Signed(trader), 0, FEUR, balance / 2, Price::zero())
 
+	routed_mint {
+		let p in 1 .. 10;
+
+		let owner = create_pool()?;
+		create_more_pools(&owner, p)?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+	}: _(RawOrigin::Signed(trader), FEUR, balance, Price::saturating_from_integer(2), p)
+
+	routed_redeem {
+		let p in 1 .. 10;
+
+		let owner = create_pool()?;
+		create_more_pools(&owner, p)?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+	}: _(RawOrigin::Signed(trader), FEUR, balance / 2, Price::zero(), p)
+
+	swap {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+		SyntheticLiquidityPools::set_spread(
+			RawOrigin::Signed(owner.clone()).into(),
+			0,
+			CurrencyId::FJPY,
+			Price::zero(),
+			Price::zero(),
+		)?;
+		SyntheticLiquidityPools::set_synthetic_enabled(RawOrigin::Signed(owner.clone()).into(), 0, CurrencyId::FJPY, true)?;
+
+		set_up_oracle();
+		set_price(vec![
+			(CurrencyId::FEUR, Price::saturating_from_integer(1)),
+			(CurrencyId::FJPY, Price::saturating_from_integer(1)),
+		])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+	}: _(RawOrigin::Signed(trader), 0, FEUR, balance / 2, 0, CurrencyId::FJPY, 0)
+
 	liquidate {
 		let owner = create_pool()?;
 		let trader: AccountId = account("trader", 0, SEED);
@@ -117,6 +191,196 @@ runtime_benchmarks! {
 
 		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(1, 2))])?;
 	}: _(RawOrigin::Signed(owner), 0, FEUR)
+
+	vault_mint {
+		let _ = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+	}: _(RawOrigin::Signed(trader), 0, FEUR, balance, Permill::from_percent(50), Price::saturating_from_integer(2))
+
+	vault_burn {
+		let _ = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::vault_mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Permill::from_percent(50), Price::saturating_from_integer(2))?;
+	}: _(RawOrigin::Signed(trader), 0, FEUR, dollars(10u128))
+
+	vault_add_collateral {
+		let _ = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+	}: _(RawOrigin::Signed(trader), 0, FEUR, balance)
+
+	vault_withdraw_collateral {
+		let _ = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::vault_mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Permill::from_percent(50), Price::saturating_from_integer(2))?;
+	}: _(RawOrigin::Signed(trader), 0, FEUR, dollars(10u128))
+
+	vault_liquidate {
+		let _ = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::vault_mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Permill::from_percent(50), Price::saturating_from_integer(2))?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(145, 100))])?;
+	}: _(RawOrigin::Signed(trader.clone()), trader.clone(), 0, FEUR, dollars(10u128))
+
+	force_add_collateral {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(12, 10))])?;
+	}: _(RawOrigin::None, 0, FEUR)
+
+	mark_under_liquidation {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(12, 10))])?;
+	}: _(RawOrigin::None, 0, FEUR)
+
+	position_become_safe {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(12, 10))])?;
+		SyntheticProtocol::mark_under_liquidation(RawOrigin::None.into(), 0, FEUR)?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+	}: _(RawOrigin::None, 0, FEUR)
+
+	set_collateral_currency {
+	}: _(RawOrigin::Root, CurrencyId::FBTC, Some(Permill::from_percent(20)))
+
+	add_extra_collateral {
+		let owner = create_pool()?;
+		set_balance(CurrencyId::FBTC, &owner, dollars(100u128))?;
+		SyntheticProtocol::set_collateral_currency(RawOrigin::Root.into(), CurrencyId::FBTC, Some(Permill::from_percent(20)))?;
+	}: _(RawOrigin::Signed(owner), 0, FEUR, CurrencyId::FBTC, dollars(100u128))
+
+	withdraw_extra_collateral {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+		set_balance(CurrencyId::FBTC, &owner, balance)?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![
+			(CurrencyId::FEUR, Price::saturating_from_integer(1)),
+			(CurrencyId::FBTC, Price::saturating_from_integer(1)),
+		])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+
+		SyntheticProtocol::set_collateral_currency(RawOrigin::Root.into(), CurrencyId::FBTC, Some(Permill::from_percent(20)))?;
+		SyntheticProtocol::add_extra_collateral(RawOrigin::Signed(owner.clone()).into(), 0, FEUR, CurrencyId::FBTC, balance)?;
+	}: _(RawOrigin::Signed(owner), 0, FEUR, CurrencyId::FBTC, balance / 2)
+
+	set_fee_rates {
+	}: _(RawOrigin::Root, FEUR, Permill::from_percent(1), Permill::from_percent(1))
+
+	set_stability_fee_rate {
+	}: _(RawOrigin::Root, FEUR, Permill::from_percent(5))
+
+	on_initialize {
+		let p in 1 .. 10;
+		let n in 0 .. 1;
+
+		let owner = create_pool()?;
+		for _ in 1 .. p {
+			BaseLiquidityPoolsForSynthetic::create_pool(RawOrigin::Signed(owner.clone()).into())?;
+		}
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		if n == 1 {
+			SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+			SyntheticProtocol::set_stability_fee_rate(RawOrigin::Root.into(), FEUR, Permill::from_percent(5))?;
+		}
+
+		System::set_block_number(1);
+		pallet_timestamp::Now::<Runtime>::put(1_000);
+		SyntheticProtocol::on_initialize(System::block_number());
+		pallet_timestamp::Now::<Runtime>::put(1_000 + 60 * 60 * 1_000);
+	}: {
+		SyntheticProtocol::on_initialize(System::block_number());
+	}
+
+	start_collateral_auction {
+		let owner = create_pool()?;
+		let trader: AccountId = account("trader", 0, SEED);
+
+		let balance = dollars(100u128);
+		set_ausd_balance(&trader, balance + dollars(1u128))?;
+
+		add_liquidity(&owner, balance)?;
+
+		set_up_oracle();
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_integer(1))])?;
+		SyntheticProtocol::mint(RawOrigin::Signed(trader.clone()).into(), 0, FEUR, balance, Price::saturating_from_integer(2))?;
+
+		set_price(vec![(CurrencyId::FEUR, Price::saturating_from_rational(12, 10))])?;
+	}: _(RawOrigin::None, 0, FEUR)
 }
 
 #[cfg(test)]
@@ -153,6 +417,27 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn routed_mint() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_routed_mint());
+		});
+	}
+
+	#[test]
+	fn routed_redeem() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_routed_redeem());
+		});
+	}
+
+	#[test]
+	fn swap() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_swap());
+		});
+	}
+
 	#[test]
 	fn liquidate() {
 		new_test_ext().execute_with(|| {
@@ -173,4 +458,109 @@ mod tests {
 			assert_ok!(test_benchmark_withdraw_collateral());
 		});
 	}
+
+	#[test]
+	fn vault_mint() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_vault_mint());
+		});
+	}
+
+	#[test]
+	fn vault_burn() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_vault_burn());
+		});
+	}
+
+	#[test]
+	fn vault_add_collateral() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_vault_add_collateral());
+		});
+	}
+
+	#[test]
+	fn vault_withdraw_collateral() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_vault_withdraw_collateral());
+		});
+	}
+
+	#[test]
+	fn vault_liquidate() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_vault_liquidate());
+		});
+	}
+
+	#[test]
+	fn force_add_collateral() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_force_add_collateral());
+		});
+	}
+
+	#[test]
+	fn mark_under_liquidation() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_mark_under_liquidation());
+		});
+	}
+
+	#[test]
+	fn position_become_safe() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_position_become_safe());
+		});
+	}
+
+	#[test]
+	fn start_collateral_auction() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_start_collateral_auction());
+		});
+	}
+
+	#[test]
+	fn set_collateral_currency() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_collateral_currency());
+		});
+	}
+
+	#[test]
+	fn add_extra_collateral() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_add_extra_collateral());
+		});
+	}
+
+	#[test]
+	fn withdraw_extra_collateral() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_withdraw_extra_collateral());
+		});
+	}
+
+	#[test]
+	fn set_fee_rates() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_fee_rates());
+		});
+	}
+
+	#[test]
+	fn set_stability_fee_rate() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_stability_fee_rate());
+		});
+	}
+
+	#[test]
+	fn on_initialize() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_on_initialize());
+		});
+	}
 }
@@ -10,13 +10,16 @@ runtime_benchmarks! {
 	_ {}
 
 	set_extreme_ratio {
-	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(1))
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(2))
 
 	set_liquidation_ratio {
-	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(1))
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(6))
 
 	set_collateral_ratio {
-	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(1))
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(20))
+
+	set_ratios {
+	}: _(RawOrigin::Root, CurrencyId::FEUR, Permill::from_percent(2), Permill::from_percent(6), Permill::from_percent(20))
 }
 
 #[cfg(test)]
@@ -51,4 +54,11 @@ mod tests {
 			assert_ok!(test_benchmark_set_collateral_ratio());
 		});
 	}
+
+	#[test]
+	fn test_set_ratios() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_set_ratios());
+		});
+	}
 }
@@ -33,7 +33,7 @@ use sp_runtime::{
 	generic, impl_opaque_keys,
 	traits::{Extrinsic, Saturating, Verify, Zero},
 	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
-	ApplyExtrinsicResult, DispatchResult, FixedPointNumber, ModuleId,
+	ApplyExtrinsicResult, DispatchError, DispatchResult, FixedPointNumber, ModuleId,
 };
 use sp_std::prelude::*;
 #[cfg(feature = "std")]
@@ -46,13 +46,20 @@ use orml_traits::{
 	create_median_value_data_provider, parameter_type_with_key, DataFeeder, DataProvider, DataProviderExtended,
 };
 pub use primitives::{
-	AccountId, AccountIndex, Amount, Balance, BlockNumber, CurrencyId, DataProviderId, EraIndex, Hash, LiquidityPoolId,
-	Moment, Nonce, Price, Signature,
+	AccountId, AccountIndex, Amount, AuctionId, Balance, BlockNumber, CurrencyId, DataProviderId, EraIndex, Hash,
+	Leverage, LiquidityPoolId, Moment, Nonce, Price, PriceRoute, Signature, TradingPair,
 };
 pub use sp_arithmetic::FixedI128;
 
-use margin_protocol_rpc_runtime_api::{MarginPoolState, MarginTraderState};
-use synthetic_protocol_rpc_runtime_api::SyntheticPoolState;
+use liquidity_pools_rpc_runtime_api::{
+	LiquidityPoolInfo, MarginPoolInfo, MarginPoolTradingPairInfo, SyntheticPoolCurrencyInfo, SyntheticPoolInfo,
+};
+use margin_protocol_rpc_runtime_api::{
+	MarginOpenPositionQuote, MarginPoolRisk, MarginPoolState, MarginPosition, MarginRisk, MarginRiskThreshold,
+	MarginTraderRisk, MarginTraderState, MarginTraderStatus,
+};
+use module_traits::{LiquidityPools, MarginProtocolLiquidityPools, SyntheticProtocolLiquidityPools};
+use synthetic_protocol_rpc_runtime_api::{SyntheticMintQuote, SyntheticPoolState, SyntheticRedeemQuote};
 
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
@@ -330,6 +337,7 @@ parameter_types! {
 	pub const SessionDuration: BlockNumber = EPOCH_DURATION_IN_SLOTS as _;
 	pub const StakingUnsignedPriority: TransactionPriority = TransactionPriority::max_value();
 	pub const MarginProtocolUnsignedPriority: TransactionPriority = TransactionPriority::max_value() - 2;
+	pub const SyntheticProtocolUnsignedPriority: TransactionPriority = TransactionPriority::max_value() - 3;
 }
 
 parameter_types! {
@@ -590,7 +598,7 @@ parameter_types! {
 type LaminarDataProvider = orml_oracle::Instance1;
 impl orml_oracle::Config<LaminarDataProvider> for Runtime {
 	type Event = Event;
-	type OnNewData = ();
+	type OnNewData = Prices;
 	type CombineData = orml_oracle::DefaultCombineData<Runtime, MinimumCount, ExpiresIn, LaminarDataProvider>;
 	type Time = Timestamp;
 	type OracleKey = CurrencyId;
@@ -602,7 +610,7 @@ impl orml_oracle::Config<LaminarDataProvider> for Runtime {
 type BandDataProvider = orml_oracle::Instance2;
 impl orml_oracle::Config<BandDataProvider> for Runtime {
 	type Event = Event;
-	type OnNewData = ();
+	type OnNewData = Prices;
 	type CombineData = orml_oracle::DefaultCombineData<Runtime, MinimumCount, ExpiresIn, BandDataProvider>;
 	type Time = Timestamp;
 	type OracleKey = CurrencyId;
@@ -643,7 +651,7 @@ impl orml_tokens::Config for Runtime {
 
 parameter_types! {
 	pub const GetLaminarTokenId: CurrencyId = CurrencyId::LAMI;
-	pub SyntheticCurrencyIds: Vec<CurrencyId> = vec![
+	pub LegacySyntheticCurrencyIds: Vec<CurrencyId> = vec![
 		CurrencyId::FEUR,
 		CurrencyId::FJPY,
 		CurrencyId::FAUD,
@@ -659,6 +667,16 @@ parameter_types! {
 	pub const DefaultCollateralRatio: Permill = Permill::from_percent(10);
 }
 
+/// Synthetic currencies of the legacy `CurrencyId` variants and the ones registered in `AssetRegistry`.
+pub type SyntheticCurrencyIds = asset_registry::SyntheticCurrencyIds<Runtime>;
+
+impl asset_registry::Config for Runtime {
+	type Event = Event;
+	type UpdateOrigin = EnsureHalfFinancialCouncilOrRoot;
+	type LegacySyntheticCurrencyIds = LegacySyntheticCurrencyIds;
+	type WeightInfo = weights::asset_registry::WeightInfo<Runtime>;
+}
+
 pub type LaminarToken = BasicCurrencyAdapter<Runtime, Balances, Amount, BlockNumber>;
 
 impl orml_currencies::Config for Runtime {
@@ -674,10 +692,46 @@ impl DataProvider<CurrencyId, Price> for WrappedLaminarDataProvider {
 	fn get(currency: &CurrencyId) -> Option<Price> {
 		match currency {
 			CurrencyId::AUSD => Some(Price::saturating_from_integer(1)),
-			_ => <AggregatedDataProvider as DataProvider<CurrencyId, Price>>::get(currency),
+			_ => {
+				let oracle_key = AssetRegistry::oracle_key(*currency);
+				<AggregatedDataProvider as DataProvider<CurrencyId, Price>>::get(&oracle_key)
+			}
+		}
+	}
+}
+
+parameter_types! {
+	pub const TwapWindow: Moment = 1000 * 60 * 30; // 30 minutes
+	pub const MaxTwapObservations: u32 = 100;
+}
+
+impl prices::Config for Runtime {
+	type Event = Event;
+	type OracleKey = asset_registry::OracleKey<Runtime>;
+	type Source = AggregatedDataProvider;
+	type LaminarSource = LaminarOracle;
+	type BandSource = BandOracle;
+	type UnixTime = Timestamp;
+	type TwapWindow = TwapWindow;
+	type MaxTwapObservations = MaxTwapObservations;
+	type UpdateOrigin = EnsureHalfFinancialCouncilOrRoot;
+	type WeightInfo = weights::prices::WeightInfo<Runtime>;
+}
+
+/// Time-weighted average prices, falling back to spot prices if no observations yet.
+pub struct WrappedTwapDataProvider;
+impl DataProvider<CurrencyId, Price> for WrappedTwapDataProvider {
+	fn get(currency: &CurrencyId) -> Option<Price> {
+		match currency {
+			CurrencyId::AUSD => Some(Price::saturating_from_integer(1)),
+			_ => {
+				let oracle_key = AssetRegistry::oracle_key(*currency);
+				Prices::twap(oracle_key).or_else(|| WrappedLaminarDataProvider::get(currency))
+			}
 		}
 	}
 }
+
 impl synthetic_tokens::Config for Runtime {
 	type Event = Event;
 	type DefaultExtremeRatio = DefaultExtremeRatio;
@@ -691,6 +745,7 @@ impl synthetic_tokens::Config for Runtime {
 parameter_types! {
 	pub const GetLiquidityCurrencyId: CurrencyId = CurrencyId::AUSD;
 	pub MaxSwap: FixedI128 = FixedI128::saturating_from_integer(2); // TODO: set this
+	pub const VolatilityWindow: u32 = 60; // 1 hour
 }
 
 type LiquidityCurrency = orml_currencies::Currency<Runtime, GetLiquidityCurrencyId>;
@@ -742,6 +797,9 @@ impl margin_liquidity_pools::Config for Runtime {
 	type MaxSwapRate = MaxSwap;
 	type UnixTime = Timestamp;
 	type Moment = Moment;
+	type PriceProvider = orml_traits::DefaultPriceProvider<CurrencyId, WrappedLaminarDataProvider>;
+	type VolatilityWindow = VolatilityWindow;
+	type TradableAssets = AssetRegistry;
 	type WeightInfo = weights::margin_liquidity_pools::WeightInfo<Runtime>;
 }
 
@@ -749,11 +807,36 @@ impl synthetic_liquidity_pools::Config for Runtime {
 	type Event = Event;
 	type BaseLiquidityPools = BaseLiquidityPoolsForSynthetic;
 	type UpdateOrigin = EnsureHalfFinancialCouncilOrRoot;
+	type TradableAssets = AssetRegistry;
 	type WeightInfo = weights::synthetic_liquidity_pools::WeightInfo<Runtime>;
 }
 
+parameter_types! {
+	pub const GraduallyUpdateFrequency: BlockNumber = 10;
+}
+
+impl orml_gradually_update::Config for Runtime {
+	type Event = Event;
+	type UpdateFrequency = GraduallyUpdateFrequency;
+	type DispatchOrigin = EnsureHalfFinancialCouncilOrRoot;
+	type WeightInfo = ();
+}
+
+impl orml_auction::Config for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AuctionId = AuctionId;
+	type Handler = SyntheticProtocol;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const GetCollateralCurrencyId: CurrencyId = CurrencyId::AUSD;
+	pub const SyntheticAuctionDuration: BlockNumber = 2 * HOURS;
+	pub const SyntheticAuctionTimeToClose: BlockNumber = 15 * MINUTES;
+	pub const StabilityFeeInterval: u64 = 60 * 60; // 1 hour
+	pub const StabilityFeeHistoryLength: u32 = 24 * 7;
+	pub const MaxStabilityFeeChecksPerBlock: u32 = 100;
 }
 type CollateralCurrency = orml_currencies::Currency<Runtime, GetCollateralCurrencyId>;
 impl synthetic_protocol::Config for Runtime {
@@ -761,9 +844,20 @@ impl synthetic_protocol::Config for Runtime {
 	type MultiCurrency = orml_currencies::Module<Runtime>;
 	type CollateralCurrency = CollateralCurrency;
 	type GetCollateralCurrencyId = GetCollateralCurrencyId;
+	type GetTreasuryAccountId = TreasuryAccount;
 	type PriceProvider = orml_traits::DefaultPriceProvider<CurrencyId, WrappedLaminarDataProvider>;
+	type RiskPriceProvider = orml_traits::DefaultPriceProvider<CurrencyId, WrappedTwapDataProvider>;
+	type PriceGuard = Prices;
 	type LiquidityPools = synthetic_liquidity_pools::Module<Runtime>;
 	type SyntheticProtocolLiquidityPools = synthetic_liquidity_pools::Module<Runtime>;
+	type Auction = Auction;
+	type AuctionDuration = SyntheticAuctionDuration;
+	type AuctionTimeToClose = SyntheticAuctionTimeToClose;
+	type UnixTime = Timestamp;
+	type StabilityFeeInterval = StabilityFeeInterval;
+	type StabilityFeeHistoryLength = StabilityFeeHistoryLength;
+	type MaxStabilityFeeChecksPerBlock = MaxStabilityFeeChecksPerBlock;
+	type UnsignedPriority = SyntheticProtocolUnsignedPriority;
 	type WeightInfo = weights::synthetic_protocol::WeightInfo<Runtime>;
 }
 
@@ -833,6 +927,8 @@ impl margin_protocol::Config for Runtime {
 	type LiquidityCurrency = LiquidityCurrency;
 	type LiquidityPools = margin_liquidity_pools::Module<Runtime>;
 	type PriceProvider = orml_traits::DefaultPriceProvider<CurrencyId, WrappedLaminarDataProvider>;
+	type RiskPriceProvider = orml_traits::DefaultPriceProvider<CurrencyId, WrappedTwapDataProvider>;
+	type PriceGuard = Prices;
 	type GetTreasuryAccountId = TreasuryAccount;
 	type GetTraderMaxOpenPositions = GetTraderMaxOpenPositions;
 	type GetPoolMaxOpenPositions = GetPoolMaxOpenPositions;
@@ -877,8 +973,12 @@ construct_runtime!(
 		Session: pallet_session::{Module, Call, Storage, Event, Config<T>},
 		Tokens: orml_tokens::{Module, Storage, Call, Event<T>, Config<T>},
 		Currencies: orml_currencies::{Module, Call, Event<T>},
+		Auction: orml_auction::{Module, Storage, Call, Event<T>},
+		GraduallyUpdate: orml_gradually_update::{Module, Storage, Call, Event<T>},
+		AssetRegistry: asset_registry::{Module, Storage, Call, Event, Config},
+		Prices: prices::{Module, Storage, Call, Event},
 		SyntheticTokens: synthetic_tokens::{Module, Storage, Call, Event, Config},
-		SyntheticProtocol: synthetic_protocol::{Module, Call, Event<T>},
+		SyntheticProtocol: synthetic_protocol::{Module, Storage, Call, Event<T>, ValidateUnsigned},
 		MarginProtocol: margin_protocol::{Module, Storage, Call, Event<T>, Config, ValidateUnsigned},
 		BaseLiquidityPoolsForMargin: base_liquidity_pools::<Instance1>::{Module, Storage, Call, Event<T>},
 		MarginLiquidityPools: margin_liquidity_pools::{Module, Storage, Call, Event<T>, Config<T>},
@@ -917,6 +1017,49 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExt
 pub type Executive =
 	frame_executive::Executive<Runtime, Block, frame_system::ChainContext<Runtime>, Runtime, AllModules>;
 
+/// Margin risk of a trader or a liquidity pool at risk.
+#[cfg(not(feature = "disable-runtime-api"))]
+fn margin_risk(risk: margin_protocol::Risk) -> MarginRisk {
+	match risk {
+		margin_protocol::Risk::StopOut => MarginRisk::StopOut,
+		_ => MarginRisk::MarginCall,
+	}
+}
+
+/// Margin risk threshold of a trader, or ENP or ELL of a liquidity pool.
+#[cfg(not(feature = "disable-runtime-api"))]
+fn margin_risk_threshold(threshold: margin_protocol::RiskThreshold) -> MarginRiskThreshold {
+	MarginRiskThreshold {
+		margin_call: threshold.margin_call,
+		stop_out: threshold.stop_out,
+	}
+}
+
+/// Info of all liquidity pools of base liquidity pools instance `I`, sorted by pool ID.
+#[cfg(not(feature = "disable-runtime-api"))]
+fn liquidity_pools_info<I: base_liquidity_pools::Instance>() -> Vec<LiquidityPoolInfo<AccountId>>
+where
+	Runtime: base_liquidity_pools::Config<I>,
+{
+	let mut pools: Vec<LiquidityPoolInfo<AccountId>> =
+		<base_liquidity_pools::Module<Runtime, I> as LiquidityPools<AccountId>>::all()
+			.into_iter()
+			.filter_map(|pool_id| {
+				let pool = base_liquidity_pools::Module::<Runtime, I>::pools(pool_id)?;
+				let identity = base_liquidity_pools::Module::<Runtime, I>::identity_infos(pool_id);
+				Some(LiquidityPoolInfo {
+					pool_id,
+					owner: pool.owner,
+					liquidity: pool.balance,
+					is_verified: identity.as_ref().map_or(false, |(_, _, is_verified)| *is_verified),
+					identity: identity.map(|(identity, _, _)| identity),
+				})
+			})
+			.collect();
+	pools.sort_unstable_by_key(|pool| pool.pool_id);
+	pools
+}
+
 #[cfg(not(feature = "disable-runtime-api"))]
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
@@ -1123,12 +1266,156 @@ impl_runtime_apis! {
 			}
 		}
 
+		fn trader_status(who: AccountId, pool_id: LiquidityPoolId) -> MarginTraderStatus {
+			MarginTraderStatus {
+				trader_state: Self::trader_state(who.clone(), pool_id),
+				is_margin_called: MarginProtocol::is_trader_margin_called(&who, pool_id),
+				should_stop_out: MarginProtocol::trader_reached_stop_out(&who, pool_id),
+			}
+		}
+
 		fn pool_state(pool_id: LiquidityPoolId) -> Option<MarginPoolState> {
 			let (enp, ell) = MarginProtocol::enp_and_ell(pool_id)?;
 			let required_deposit = MarginProtocol::pool_required_deposit(pool_id)?;
 
 			Some(MarginPoolState { enp, ell, required_deposit })
 		}
+
+		fn positions(who: AccountId, pool_id: Option<LiquidityPoolId>) -> Vec<MarginPosition> {
+			MarginProtocol::trader_position_ids(&who, pool_id)
+				.into_iter()
+				.filter_map(|position_id| {
+					let position = MarginProtocol::positions(position_id)?;
+					let (unrealized_pl, current_price) =
+						MarginProtocol::unrealized_pl_and_market_price_of_position(&position, None).unwrap_or_default();
+					let accumulated_swap =
+						MarginProtocol::accumulated_swap_rate_of_position(&position).unwrap_or_default();
+
+					Some(MarginPosition {
+						position_id,
+						pool_id: position.pool,
+						pair: position.pair,
+						leverage: position.leverage,
+						open_price: position.open_price(),
+						current_price,
+						unrealized_pl,
+						accumulated_swap,
+						margin_held: position.margin_held,
+					})
+				})
+				.collect()
+		}
+
+		fn open_position_quote(
+			who: AccountId,
+			pool_id: LiquidityPoolId,
+			pair: TradingPair,
+			leverage: Leverage,
+			leveraged_amount: Balance,
+		) -> Result<MarginOpenPositionQuote, DispatchError> {
+			MarginProtocol::dry_run_open_position(&who, pool_id, pair, leverage, leveraged_amount, |position| {
+				let (enp, ell) = MarginProtocol::enp_and_ell(pool_id).unwrap_or_default();
+
+				MarginOpenPositionQuote {
+					fill_price: position.open_price(),
+					margin_held: position.margin_held,
+					trader_state: Self::trader_state(who.clone(), pool_id),
+					enp,
+					ell,
+				}
+			})
+		}
+
+		fn price_route(pair: TradingPair) -> PriceRoute {
+			MarginProtocol::price_route(pair)
+		}
+
+		fn trader_liquidation_candidates(offset: u32, limit: u32) -> Vec<MarginTraderRisk<AccountId>> {
+			MarginProtocol::traders_at_risk()
+				.into_iter()
+				.skip(offset as usize)
+				.take(limit as usize)
+				.map(|(who, pool_id, risk, margin_level, threshold)| MarginTraderRisk {
+					who,
+					pool_id,
+					risk: margin_risk(risk),
+					margin_level,
+					threshold: margin_risk_threshold(threshold),
+				})
+				.collect()
+		}
+
+		fn pool_liquidation_candidates(offset: u32, limit: u32) -> Vec<MarginPoolRisk> {
+			MarginProtocol::pools_at_risk()
+				.into_iter()
+				.skip(offset as usize)
+				.take(limit as usize)
+				.map(|(pool_id, risk, (enp, ell), (enp_threshold, ell_threshold))| MarginPoolRisk {
+					pool_id,
+					risk: margin_risk(risk),
+					enp,
+					ell,
+					enp_threshold: margin_risk_threshold(enp_threshold),
+					ell_threshold: margin_risk_threshold(ell_threshold),
+				})
+				.collect()
+		}
+	}
+
+	impl liquidity_pools_rpc_runtime_api::LiquidityPoolsApi<Block, AccountId> for Runtime {
+		fn margin_pools() -> Vec<MarginPoolInfo<AccountId>> {
+			liquidity_pools_info::<BaseLiquidityPoolsMarginInstance>()
+				.into_iter()
+				.map(|pool| {
+					let pool_id = pool.pool_id;
+					let trading_pairs = MarginLiquidityPools::pool_enabled_trading_pairs(pool_id)
+						.into_iter()
+						.map(|pair| {
+							let option = MarginLiquidityPools::pool_trading_pair_options(pool_id, pair);
+							MarginPoolTradingPairInfo {
+								pair,
+								bid_spread: option.bid_spread,
+								ask_spread: option.ask_spread,
+								enabled_leverages: option.enabled_trades,
+								long_swap_rate: MarginLiquidityPools::swap_rate(pool_id, pair, true),
+								short_swap_rate: MarginLiquidityPools::swap_rate(pool_id, pair, false),
+							}
+						})
+						.collect();
+					let (enp, ell) = MarginProtocol::enp_and_ell(pool_id)
+						.map_or((None, None), |(enp, ell)| (Some(enp), Some(ell)));
+
+					MarginPoolInfo { pool, trading_pairs, enp, ell }
+				})
+				.collect()
+		}
+
+		fn synthetic_pools() -> Vec<SyntheticPoolInfo<AccountId>> {
+			liquidity_pools_info::<BaseLiquidityPoolsSyntheticInstance>()
+				.into_iter()
+				.map(|pool| {
+					let pool_id = pool.pool_id;
+					let currencies = SyntheticLiquidityPools::pool_enabled_currencies(pool_id)
+						.into_iter()
+						.map(|currency_id| {
+							let option = SyntheticLiquidityPools::pool_currency_options(pool_id, currency_id);
+							SyntheticPoolCurrencyInfo {
+								currency_id,
+								bid_spread: option.bid_spread,
+								ask_spread: option.ask_spread,
+								additional_collateral_ratio: SyntheticLiquidityPools::additional_collateral_ratio(
+									pool_id,
+									currency_id,
+								),
+								collateral_ratio: SyntheticProtocol::collateral_ratio(pool_id, currency_id),
+							}
+						})
+						.collect();
+
+					SyntheticPoolInfo { pool, currencies }
+				})
+				.collect()
+		}
 	}
 
 	impl synthetic_protocol_rpc_runtime_api::SyntheticProtocolApi<Block, AccountId> for Runtime {
@@ -1138,6 +1425,38 @@ impl_runtime_apis! {
 
 			Some(SyntheticPoolState { collateral_ratio, is_safe })
 		}
+
+		fn mint_quote(
+			pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			collateral_amount: Balance,
+		) -> Result<SyntheticMintQuote, DispatchError> {
+			let quote = SyntheticProtocol::mint_quote(pool_id, currency_id, collateral_amount)?;
+
+			Ok(SyntheticMintQuote {
+				ask_price: quote.price,
+				synthetic_amount: quote.amount,
+				fee: quote.fee,
+				additional_collateral: quote.pool_collateral,
+				has_enough_liquidity: quote.is_sufficient,
+			})
+		}
+
+		fn redeem_quote(
+			pool_id: LiquidityPoolId,
+			currency_id: CurrencyId,
+			synthetic_amount: Balance,
+		) -> Result<SyntheticRedeemQuote, DispatchError> {
+			let quote = SyntheticProtocol::redeem_quote(pool_id, currency_id, synthetic_amount)?;
+
+			Ok(SyntheticRedeemQuote {
+				bid_price: quote.price,
+				collateral_amount: quote.amount,
+				fee: quote.fee,
+				pool_refund_collateral: quote.pool_collateral,
+				has_enough_liquidity: quote.is_sufficient,
+			})
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -1152,6 +1471,8 @@ impl_runtime_apis! {
 			let mut batches = Vec::<BenchmarkBatch>::new();
 			let params = (&config, &whitelist);
 
+			add_benchmark!(params, batches, asset_registry, benchmarking::asset_registry);
+			add_benchmark!(params, batches, prices, benchmarking::prices);
 			add_benchmark!(params, batches, base_liquidity_pools, benchmarking::base_liquidity_pools);
 			add_benchmark!(params, batches, margin_liquidity_pools, benchmarking::margin_liquidity_pools);
 			add_benchmark!(params, batches, synthetic_liquidity_pools, benchmarking::synthetic_liquidity_pools);
@@ -12,6 +12,7 @@ use frame_support::{
 	traits::{OnFinalize, OnInitialize},
 };
 
+use liquidity_pools_rpc_runtime_api::runtime_decl_for_LiquidityPoolsApi::LiquidityPoolsApi;
 use margin_protocol::RiskThreshold;
 use margin_protocol_rpc_runtime_api::runtime_decl_for_MarginProtocolApi::MarginProtocolApi;
 use module_traits::LiquidityPools;
@@ -536,10 +537,43 @@ pub fn margin_trader_state(who: &AccountId) -> MarginTraderState {
 	Runtime::trader_state(who.clone(), LIQUIDITY_POOL_ID_0)
 }
 
+pub fn margin_trader_status(who: &AccountId) -> MarginTraderStatus {
+	Runtime::trader_status(who.clone(), LIQUIDITY_POOL_ID_0)
+}
+
+pub fn margin_open_position_quote(
+	who: &AccountId,
+	pair: TradingPair,
+	leverage: Leverage,
+	amount: Balance,
+) -> Result<MarginOpenPositionQuote, DispatchError> {
+	Runtime::open_position_quote(who.clone(), LIQUIDITY_POOL_ID_0, pair, leverage, amount)
+}
+
+pub fn margin_positions(who: &AccountId) -> Vec<MarginPosition> {
+	Runtime::positions(who.clone(), Some(LIQUIDITY_POOL_ID_0))
+}
+
 pub fn margin_pool_state() -> Option<MarginPoolState> {
 	<Runtime as MarginProtocolApi<Block, AccountId>>::pool_state(LIQUIDITY_POOL_ID_0)
 }
 
+pub fn margin_trader_liquidation_candidates() -> Vec<MarginTraderRisk<AccountId>> {
+	Runtime::trader_liquidation_candidates(0, 10)
+}
+
+pub fn margin_pool_liquidation_candidates() -> Vec<MarginPoolRisk> {
+	Runtime::pool_liquidation_candidates(0, 10)
+}
+
+pub fn margin_pools() -> Vec<MarginPoolInfo<AccountId>> {
+	Runtime::margin_pools()
+}
+
+pub fn synthetic_pools() -> Vec<SyntheticPoolInfo<AccountId>> {
+	Runtime::synthetic_pools()
+}
+
 pub fn margin_set_identity() -> DispatchResult {
 	let identity = IdentityInfo {
 		legal_name: b"laminar".to_vec(),
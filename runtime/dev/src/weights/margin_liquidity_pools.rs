@@ -15,6 +15,20 @@ impl<T: frame_system::Config> margin_liquidity_pools::WeightInfo for WeightInfo<
 			.saturating_add(DbWeight::get().reads(6 as Weight))
 			.saturating_add(DbWeight::get().writes(3 as Weight))
 	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_spread_formula() -> Weight {
+		(170_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	// Not benchmarked yet, a conservative placeholder: twice the estimate of a similar call,
+	// plus a read and a write.
+	fn set_spread_skew() -> Weight {
+		(170_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
 	fn set_enabled_leverages() -> Weight {
 		(71_972_000 as Weight)
 			.saturating_add(DbWeight::get().reads(6 as Weight))
@@ -1,8 +1,10 @@
 //! A list of the different weight modules for our runtime.
 
+pub mod asset_registry;
 pub mod base_liquidity_pools;
 pub mod margin_liquidity_pools;
 pub mod margin_protocol;
+pub mod prices;
 pub mod synthetic_liquidity_pools;
 pub mod synthetic_protocol;
 pub mod synthetic_tokens;
@@ -25,4 +25,9 @@ impl<T: frame_system::Config> synthetic_tokens::WeightInfo for WeightInfo<T> {
 			.saturating_add(DbWeight::get().reads(5 as Weight))
 			.saturating_add(DbWeight::get().writes(3 as Weight))
 	}
+	fn set_ratios() -> Weight {
+		(68_912_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
 }
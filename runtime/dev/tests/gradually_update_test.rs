@@ -0,0 +1,70 @@
+#![cfg(test)]
+
+use codec::Encode;
+use dev_runtime::{tests::*, CurrencyId::FEUR, Runtime};
+use frame_support::{
+	assert_ok,
+	storage::{StorageDoubleMap, StorageMap},
+	traits::OnFinalize,
+};
+use orml_gradually_update::GraduallyUpdate;
+use sp_runtime::Permill;
+
+pub type ModuleGraduallyUpdate = orml_gradually_update::Module<Runtime>;
+
+fn root() -> <Runtime as frame_system::Config>::Origin {
+	<Runtime as frame_system::Config>::Origin::root()
+}
+
+#[test]
+fn test_gradually_update_synthetic_extreme_ratio() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(ModuleTokens::set_extreme_ratio(root(), FEUR, Permill::from_percent(1)));
+
+		let key = synthetic_tokens::ExtremeRatios::hashed_key_for(FEUR);
+		assert_ok!(ModuleGraduallyUpdate::gradually_update(
+			root(),
+			GraduallyUpdate {
+				key,
+				target_value: Permill::from_percent(3).encode(),
+				// 1% every 10 blocks
+				per_block: Permill::from_parts(1_000).encode(),
+			}
+		));
+		assert_eq!(ModuleGraduallyUpdate::gradually_updates().len(), 1);
+
+		ModuleGraduallyUpdate::on_finalize(10);
+		assert_eq!(ModuleTokens::extreme_ratio_or_default(FEUR), Permill::from_percent(2));
+
+		ModuleGraduallyUpdate::on_finalize(20);
+		assert_eq!(ModuleTokens::extreme_ratio_or_default(FEUR), Permill::from_percent(3));
+		assert!(ModuleGraduallyUpdate::gradually_updates().is_empty());
+	});
+}
+
+#[test]
+fn test_gradually_update_margin_trader_stop_out() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			ModuleMarginProtocol::trader_risk_threshold(EUR_USD),
+			Some(risk_threshold(3, 1))
+		);
+
+		let key =
+			margin_protocol::StopOutThresholds::hashed_key_for(EUR_USD, margin_protocol::RiskThresholdType::Trader);
+		assert_ok!(ModuleGraduallyUpdate::gradually_update(
+			root(),
+			GraduallyUpdate {
+				key,
+				target_value: Permill::from_percent(2).encode(),
+				per_block: Permill::from_parts(1_000).encode(),
+			}
+		));
+
+		ModuleGraduallyUpdate::on_finalize(10);
+		assert_eq!(
+			ModuleMarginProtocol::trader_risk_threshold(EUR_USD),
+			Some(risk_threshold(3, 2))
+		);
+	});
+}
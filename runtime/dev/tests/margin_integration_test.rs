@@ -11,9 +11,11 @@ mod tests {
 	};
 	use frame_support::{assert_noop, assert_ok};
 
-	use margin_protocol_rpc_runtime_api::{MarginPoolState, MarginTraderState};
+	use margin_protocol_rpc_runtime_api::{
+		MarginOpenPositionQuote, MarginPoolState, MarginPosition, MarginRisk, MarginTraderState, MarginTraderStatus,
+	};
 	use module_traits::MarginProtocolLiquidityPools;
-	use primitives::{Leverage::*, Price};
+	use primitives::{Leverage, Price};
 	use sp_arithmetic::{FixedI128, FixedPointNumber};
 	use sp_runtime::traits::{Bounded, CheckedAdd};
 
@@ -71,6 +73,13 @@ mod tests {
 
 				assert_ok!(margin_enable_trading_pair(EUR_USD));
 				assert_ok!(margin_liquidity_pool_enable_trading_pair(EUR_USD));
+				let pools = margin_pools();
+				assert_eq!(pools.len(), 1);
+				assert_eq!(pools[0].pool.owner, POOL::get());
+				assert_eq!(pools[0].pool.liquidity, dollar(15000));
+				assert_eq!(pools[0].trading_pairs.len(), 1);
+				assert_eq!(pools[0].trading_pairs[0].pair, EUR_USD);
+				assert_eq!(pools[0].trading_pairs[0].bid_spread, Some(Price::from_fraction(0.01)));
 				assert_ok!(margin_disable_trading_pair(EUR_USD));
 				assert_ok!(margin_liquidity_pool_disable_trading_pair(EUR_USD));
 				assert_ok!(margin_withdraw_liquidity(&POOL::get(), dollar(10_000)));
@@ -78,6 +87,7 @@ mod tests {
 				assert_ok!(margin_disable_pool(&POOL::get()));
 				assert_ok!(margin_remove_pool(&POOL::get()));
 				assert_eq!(collateral_balance(&POOL::get()), dollar(15000));
+				assert_eq!(margin_pools(), vec![]);
 			});
 	}
 
@@ -127,10 +137,31 @@ mod tests {
 						unrealized_pl: FixedI128::zero()
 					}
 				);
+				assert_eq!(
+					margin_open_position_quote(&ALICE::get(), EUR_USD, Leverage::LongTen, dollar(5000)),
+					Ok(MarginOpenPositionQuote {
+						fill_price: FixedI128::saturating_from_rational(303, 100),
+						margin_held: FixedI128::saturating_from_integer(1515),
+						trader_state: MarginTraderState {
+							equity: FixedI128::saturating_from_integer(4700),
+							margin_held: FixedI128::saturating_from_integer(1515),
+							margin_level: FixedI128::from_inner(310231023102310231),
+							free_margin: FixedI128::saturating_from_integer(3185),
+							unrealized_pl: FixedI128::saturating_from_integer(-300)
+						},
+						enp: FixedI128::from_inner(0_686666666666666667),
+						ell: FixedI128::from_inner(0_686666666666666667),
+					})
+				);
+				assert_eq!(
+					margin_open_position_quote(&BOB::get(), EUR_USD, Leverage::LongTen, dollar(5000)),
+					Err(margin_protocol::Error::<Runtime>::InsufficientFreeMargin.into())
+				);
+				assert!(margin_positions(&ALICE::get()).is_empty());
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -154,6 +185,20 @@ mod tests {
 						unrealized_pl: FixedI128::saturating_from_integer(-300)
 					}
 				);
+				assert_eq!(
+					margin_positions(&ALICE::get()),
+					vec![MarginPosition {
+						position_id: 0,
+						pool_id: LIQUIDITY_POOL_ID_0,
+						pair: EUR_USD,
+						leverage: Leverage::LongTen,
+						open_price: FixedI128::saturating_from_rational(303, 100),
+						current_price: FixedI128::saturating_from_rational(297, 100),
+						unrealized_pl: FixedI128::saturating_from_integer(-300),
+						accumulated_swap: FixedI128::zero(),
+						margin_held: FixedI128::saturating_from_integer(1515),
+					}]
+				);
 
 				assert_ok!(margin_close_position(
 					&ALICE::get(),
@@ -185,6 +230,7 @@ mod tests {
 						unrealized_pl: FixedI128::zero()
 					}
 				);
+				assert!(margin_positions(&ALICE::get()).is_empty());
 				assert_ok!(margin_withdraw(&ALICE::get(), dollar(4700)));
 				assert_eq!(collateral_balance(&ALICE::get()), dollar(9700));
 			});
@@ -223,7 +269,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -275,7 +321,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -337,7 +383,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -382,6 +428,19 @@ mod tests {
 					})
 				);
 				assert_ok!(margin_trader_margin_call(&ALICE::get()));
+				assert_eq!(
+					margin_trader_status(&ALICE::get()),
+					MarginTraderStatus {
+						trader_state: margin_trader_state(&ALICE::get()),
+						is_margin_called: true,
+						should_stop_out: false,
+					}
+				);
+				let candidates = margin_trader_liquidation_candidates();
+				assert_eq!(candidates.len(), 1);
+				assert_eq!(candidates[0].who, ALICE::get());
+				assert_eq!(candidates[0].risk, MarginRisk::MarginCall);
+				assert_eq!(margin_pool_liquidation_candidates(), vec![]);
 				assert_noop!(
 					margin_trader_stop_out(&ALICE::get()),
 					margin_protocol::Error::<Runtime>::NotReachedRiskThreshold
@@ -420,6 +479,9 @@ mod tests {
 				assert_ok!(margin_trader_become_safe(&ALICE::get()));
 
 				assert_ok!(set_oracle_price(vec![(FEUR, Price::saturating_from_rational(19, 10))]));
+				assert!(!margin_trader_status(&ALICE::get()).is_margin_called);
+				assert!(margin_trader_status(&ALICE::get()).should_stop_out);
+				assert_eq!(margin_trader_liquidation_candidates()[0].risk, MarginRisk::StopOut);
 				assert_ok!(margin_trader_stop_out(&ALICE::get()));
 
 				assert_eq!(collateral_balance(&ALICE::get()), dollar(4500));
@@ -467,7 +529,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -550,7 +612,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -560,7 +622,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&BOB::get(),
 					EUR_USD,
-					ShortTen,
+					Leverage::ShortTen,
 					dollar(6000),
 					Price::saturating_from_rational(2, 1)
 				));
@@ -571,7 +633,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTwenty,
+					Leverage::LongTwenty,
 					dollar(1000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -594,7 +656,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&BOB::get(),
 					EUR_USD,
-					ShortTwenty,
+					Leverage::ShortTwenty,
 					dollar(2000),
 					Price::saturating_from_rational(2, 1)
 				));
@@ -694,7 +756,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -714,7 +776,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&BOB::get(),
 					JPY_EUR,
-					ShortTen,
+					Leverage::ShortTen,
 					dollar(6000),
 					Price::saturating_from_rational(1, 1)
 				));
@@ -738,7 +800,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					JPY_EUR,
-					LongTwenty,
+					Leverage::LongTwenty,
 					dollar(1000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -787,7 +849,7 @@ mod tests {
 				assert_ok!(margin_open_position(
 					&BOB::get(),
 					EUR_USD,
-					ShortTwenty,
+					Leverage::ShortTwenty,
 					dollar(2000),
 					Price::saturating_from_rational(2, 1)
 				));
@@ -903,11 +965,11 @@ mod tests {
 				assert_ok!(margin_enable_trading_pair(EUR_USD));
 				assert_ok!(margin_liquidity_pool_enable_trading_pair(EUR_USD));
 
-				// LongTen
+				// Leverage::LongTen
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -935,11 +997,11 @@ mod tests {
 				);
 				assert_eq!(margin_liquidity(), 10451500000000000000000);
 
-				// ShortTen
+				// Leverage::ShortTen
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					ShortTen,
+					Leverage::ShortTen,
 					dollar(5000),
 					Price::saturating_from_rational(2, 1)
 				));
@@ -1011,11 +1073,11 @@ mod tests {
 					MarginLiquidityPools::swap_rate(LIQUIDITY_POOL_ID_0, EUR_USD, true),
 					MarginLiquidityPools::swap_rate(LIQUIDITY_POOL_ID_0, EUR_USD, false)
 				);
-				// LongTen
+				// Leverage::LongTen
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					LongTen,
+					Leverage::LongTen,
 					dollar(5000),
 					Price::saturating_from_rational(4, 1)
 				));
@@ -1043,11 +1105,11 @@ mod tests {
 				);
 				assert_eq!(margin_liquidity(), 10453_015000000000000000);
 
-				// ShortTen
+				// Leverage::ShortTen
 				assert_ok!(margin_open_position(
 					&ALICE::get(),
 					EUR_USD,
-					ShortTen,
+					Leverage::ShortTen,
 					dollar(5000),
 					Price::saturating_from_rational(2, 1)
 				));
@@ -1095,6 +1157,7 @@ mod tests {
 			assert_eq!(native_currency_balance(&POOL::get()), 90_000 * DOLLARS);
 			assert_ok!(margin_verify_identity());
 			assert_eq!(native_currency_balance(&POOL::get()), 90_000 * DOLLARS);
+			assert!(margin_pools()[0].pool.is_verified);
 
 			// clear identity
 			assert_ok!(margin_clear_identity());
@@ -1106,6 +1169,10 @@ mod tests {
 			assert_ok!(synthetic_create_pool());
 			assert_ok!(synthetic_set_identity());
 			assert_eq!(native_currency_balance(&POOL::get()), 80_000 * DOLLARS);
+			let pools = synthetic_pools();
+			assert_eq!(pools.len(), 1);
+			assert!(pools[0].pool.identity.is_some());
+			assert!(!pools[0].pool.is_verified);
 
 			// remove identity
 			assert_ok!(margin_remove_pool(&POOL::get()));
@@ -1,9 +1,11 @@
+use asset_registry::AssetMetadata;
 use dev_runtime::{
-	opaque::SessionKeys, AccountId, BabeConfig, BalancesConfig, BandOracleConfig, Block, CurrencyId,
-	FinancialCouncilMembershipConfig, GeneralCouncilMembershipConfig, GenesisConfig, GrandpaConfig, IndicesConfig,
-	LaminarOracleConfig, MarginLiquidityPoolsConfig, MarginProtocolConfig, Moment, OperatorMembershipBandConfig,
-	OperatorMembershipLaminarConfig, Price, SessionConfig, Signature, StakerStatus, StakingConfig, SudoConfig,
-	SyntheticLiquidityPoolsConfig, SyntheticTokensConfig, SystemConfig, TokensConfig, DOLLARS, WASM_BINARY,
+	opaque::SessionKeys, AccountId, AssetRegistryConfig, BabeConfig, BalancesConfig, BandOracleConfig, Block,
+	CurrencyId, FinancialCouncilMembershipConfig, GeneralCouncilMembershipConfig, GenesisConfig, GrandpaConfig,
+	IndicesConfig, LaminarOracleConfig, MarginLiquidityPoolsConfig, MarginProtocolConfig, Moment,
+	OperatorMembershipBandConfig, OperatorMembershipLaminarConfig, Price, SessionConfig, Signature, StakerStatus,
+	StakingConfig, SudoConfig, SyntheticLiquidityPoolsConfig, SyntheticTokensConfig, SystemConfig, TokensConfig,
+	DOLLARS, WASM_BINARY,
 };
 use hex_literal::hex;
 use laminar_primitives::{AccumulateConfig, SwapRate, TradingPair};
@@ -293,6 +295,42 @@ fn risk_threshold(margin_call_percent: u32, stop_out_percent: u32) -> RiskThresh
 	}
 }
 
+fn asset_metadata(
+	symbol: &[u8],
+	currency_id: CurrencyId,
+	is_synthetic: bool,
+	is_tradable: bool,
+) -> (CurrencyId, AssetMetadata) {
+	(
+		currency_id,
+		AssetMetadata {
+			symbol: symbol.to_vec(),
+			decimals: 18,
+			oracle_key: currency_id,
+			is_synthetic,
+			is_tradable,
+		},
+	)
+}
+
+/// Metadata of the legacy currencies.
+fn legacy_assets() -> Vec<(CurrencyId, AssetMetadata)> {
+	vec![
+		asset_metadata(b"LAMI", CurrencyId::LAMI, false, false),
+		// quote currency of trading pairs
+		asset_metadata(b"AUSD", CurrencyId::AUSD, false, true),
+		asset_metadata(b"FEUR", CurrencyId::FEUR, true, true),
+		asset_metadata(b"FJPY", CurrencyId::FJPY, true, true),
+		asset_metadata(b"FBTC", CurrencyId::FBTC, true, true),
+		asset_metadata(b"FETH", CurrencyId::FETH, true, true),
+		asset_metadata(b"FAUD", CurrencyId::FAUD, true, true),
+		asset_metadata(b"FCAD", CurrencyId::FCAD, true, true),
+		asset_metadata(b"FCHF", CurrencyId::FCHF, true, true),
+		asset_metadata(b"FXAU", CurrencyId::FXAU, true, true),
+		asset_metadata(b"FOIL", CurrencyId::FOIL, true, true),
+	]
+}
+
 fn dev_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AccountId, AccountId, GrandpaId, BabeId)>,
@@ -345,6 +383,9 @@ fn dev_genesis(
 				.flat_map(|x| vec![(x.clone(), CurrencyId::AUSD, INITIAL_BALANCE)])
 				.collect(),
 		}),
+		asset_registry: Some(AssetRegistryConfig {
+			assets: legacy_assets(),
+		}),
 		synthetic_liquidity_pools: Some(SyntheticLiquidityPoolsConfig {
 			min_additional_collateral_ratio: Permill::from_percent(10), // default min additional collateral ratio
 		}),
@@ -634,6 +675,9 @@ fn turbulence_genesis(
 				.flat_map(|x| vec![(x.clone(), CurrencyId::AUSD, INITIAL_BALANCE)])
 				.collect(),
 		}),
+		asset_registry: Some(AssetRegistryConfig {
+			assets: legacy_assets(),
+		}),
 		synthetic_liquidity_pools: Some(SyntheticLiquidityPoolsConfig {
 			min_additional_collateral_ratio: Permill::from_percent(10), // default min additional collateral ratio
 		}),
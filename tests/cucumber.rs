@@ -179,24 +179,16 @@ fn parse_time_range(value: Option<&String>) -> Range<Moment> {
 }
 
 fn parse_leverage(leverage: Option<&String>) -> Leverage {
-	let leverage = leverage.expect("Missing leverage");
-	match leverage.to_ascii_lowercase().trim() {
-		"long 2" => Leverage::LongTwo,
-		"long 3" => Leverage::LongThree,
-		"long 5" => Leverage::LongFive,
-		"long 10" => Leverage::LongTen,
-		"long 20" => Leverage::LongTwenty,
-		"long 30" => Leverage::LongThirty,
-		"long 50" => Leverage::LongFifty,
-		"short 2" => Leverage::ShortTwo,
-		"short 3" => Leverage::ShortThree,
-		"short 5" => Leverage::ShortFive,
-		"short 10" => Leverage::ShortTen,
-		"short 20" => Leverage::ShortTwenty,
-		"short 30" => Leverage::ShortThirty,
-		"short 50" => Leverage::ShortFifty,
-		_ => panic!("Unsupported leverage"),
+	let leverage = leverage.expect("Missing leverage").to_ascii_lowercase();
+	let mut parts = leverage.split_whitespace();
+	let direction = parts.next();
+	let value = parts.next().and_then(|v| v.parse::<u8>().ok());
+	match (direction, value) {
+		(Some("long"), Some(value)) => Leverage::long(value),
+		(Some("short"), Some(value)) => Leverage::short(value),
+		_ => None,
 	}
+	.expect("Unsupported leverage")
 }
 
 fn parse_position_id(value: Option<&String>) -> PositionId {